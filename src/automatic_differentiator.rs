@@ -1,11 +1,20 @@
-use crate::{number::Number, operation::Operation, shared_data_communication_channel};
+use crate::{
+    linear_algebra::{self, CholeskyFactorization, TridiagonalSystem},
+    number::Number,
+    operation::Operation,
+    shared_data_communication_channel, special_functions,
+};
 
 use once_cell::sync::Lazy;
 use ordered_hash_map::OrderedHashMap;
-use statrs::distribution::{Continuous, Normal};
-use std::{collections::HashMap, sync::Mutex};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
 
 use sorted_vec::SortedVec;
+use statrs::function::{erf, gamma};
+use std::f64::consts::FRAC_2_SQRT_PI;
 
 static DATA_RACE: Lazy<Mutex<i32>> = Lazy::new(|| Mutex::new(0));
 
@@ -15,6 +24,42 @@ pub struct Evaluation {
     pub derivatives: Vec<Derivative>,
 }
 
+impl Evaluation {
+    /// Derivative with respect to `input`. An input the result does not depend
+    /// on has derivative `0`.
+    pub fn derivative(&self, input: Number) -> f64 {
+        self.derivatives
+            .iter()
+            .find(|d| d.input.id == input.id)
+            .map_or(0.0, |d| d.derivative)
+    }
+}
+
+/// Result of [`AutomaticDifferentiator::replay`].
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub evaluation: Evaluation,
+    /// Ids of the select nodes that took the other branch than in the previous
+    /// evaluation of the tape.
+    pub changed_branches: Vec<i64>,
+}
+
+impl Replay {
+    pub fn control_flow_changed(&self) -> bool {
+        !self.changed_branches.is_empty()
+    }
+}
+
+/// A node evaluated again by [`AutomaticDifferentiator::replay`]: its result,
+/// and whatever else the node records about its arguments.
+enum Replayed {
+    Result(f64),
+    /// The result of a select node and the outcome of its condition.
+    Select(f64, bool),
+    /// The result of an implicit function and its partials.
+    ImplicitFunction(f64, Vec<f64>),
+}
+
 #[derive(Debug, Clone)]
 pub struct Derivative {
     pub input: Number,
@@ -27,6 +72,9 @@ pub struct AutomaticDifferentiator {
     node_list: SortedVec<i64>,
     parent_child_map: OrderedHashMap<i64, Vec<i64>>,
     child_parent_map: OrderedHashMap<i64, Vec<i64>>,
+    arguments: Vec<Number>,
+    // id of the number returned by the recorded function, whose adjoint is seeded
+    output: i64,
 }
 
 impl Default for AutomaticDifferentiator {
@@ -42,6 +90,8 @@ impl AutomaticDifferentiator {
             node_list: SortedVec::new(),
             parent_child_map: OrderedHashMap::new(),
             child_parent_map: OrderedHashMap::new(),
+            arguments: Vec::new(),
+            output: 0,
         }
     }
 
@@ -50,9 +100,126 @@ impl AutomaticDifferentiator {
         F: Fn(&[Number]) -> Number,
     {
         let forward_evalutation = self.forward_evaluate(func, arguments);
+        self.arguments = arguments.to_vec();
+        self.output = forward_evalutation.id;
+        self.reverse_propagate_adjoints();
+
+        Evaluation {
+            result: forward_evalutation.result,
+            derivatives: self.collect_derivatives(),
+        }
+    }
+
+    /// Records `func` once and sweeps once from each number it returns: the
+    /// evaluations are the rows of the Jacobian of `func`, in output order.
+    /// Each sweep only visits the nodes its output depends on, so outputs
+    /// computed apart cost no more together than one by one.
+    ///
+    /// A [`AutomaticDifferentiator::replay`] afterwards follows the last
+    /// output.
+    pub fn jacobian<F>(&mut self, func: F, arguments: &[Number]) -> Vec<Evaluation>
+    where
+        F: Fn(&[Number]) -> Vec<Number>,
+    {
+        let outputs = self.forward_evaluate(func, arguments);
+        self.arguments = arguments.to_vec();
+
+        let mut rows = Vec::with_capacity(outputs.len());
+        for output in &outputs {
+            self.output = output.id;
+            let dependencies = self.dependencies(output.id);
+            if let Some(rec) = self.record.get_mut(&output.id) {
+                rec.set_adjoint(1.0);
+            }
+            self.propagate_adjoints(&dependencies);
+            rows.push(Evaluation {
+                result: output.result,
+                derivatives: self.collect_derivatives(),
+            });
+
+            // clear the adjoints for the sweep from the next output
+            for id in std::iter::once(&output.id).chain(&dependencies) {
+                if let Some(rec) = self.record.get_mut(id) {
+                    rec.set_adjoint(0.0);
+                }
+            }
+        }
+        rows
+    }
+
+    /// Re-evaluates the tape of the last call to [`AutomaticDifferentiator::derivatives`]
+    /// with new values for its arguments, in the same order, and propagates
+    /// the adjoints again. The function itself is not called.
+    ///
+    /// Select nodes (`Number::select`) re-evaluate their condition and follow
+    /// the branch the new values lead to; the ids of those that switched
+    /// branch compared with the previous evaluation are reported. Branches
+    /// taken by comparing `result`s directly are not on the tape and are
+    /// replayed as recorded.
+    pub fn replay(&mut self, values: &[f64]) -> Replay {
+        assert_eq!(
+            values.len(),
+            self.arguments.len(),
+            "replay needs one value per argument of the recorded function"
+        );
+
+        for (argument, value) in self.arguments.iter_mut().zip(values) {
+            argument.result = *value;
+            if let Some(Operation::Value(_, result, _)) = self.record.get_mut(&argument.id) {
+                *result = *value;
+            }
+        }
+
+        // results of the outputs of block operations, computed once per block
+        let mut block_results: HashMap<i64, Vec<f64>> = HashMap::new();
+        let mut changed_branches = Vec::new();
+        for node_id in self.node_list.iter() {
+            let Some(operation) = self.record.get(node_id) else {
+                continue;
+            };
+            let replayed = self.replay_operation(operation, &mut block_results);
+            if let Some(operation) = self.record.get_mut(node_id) {
+                let result = match replayed {
+                    Replayed::Result(result) => result,
+                    Replayed::Select(result, holds) => {
+                        if let Operation::Select(_, _, _, _, _, _, recorded, _, _) = operation
+                            && *recorded != holds
+                        {
+                            *recorded = holds;
+                            changed_branches.push(*node_id);
+                        }
+                        result
+                    }
+                    Replayed::ImplicitFunction(result, new_partials) => {
+                        if let Operation::ImplicitFunction(_, _, _, partials, _, _) = operation {
+                            *partials = new_partials;
+                        }
+                        result
+                    }
+                };
+                operation.set_result(result);
+                operation.set_adjoint(0.0);
+            }
+        }
+
         self.reverse_propagate_adjoints();
 
-        let derivatives = arguments
+        let result = self
+            .record
+            .get(&self.output)
+            .map_or(f64::NAN, |op| op.get_result());
+
+        Replay {
+            evaluation: Evaluation {
+                result,
+                derivatives: self.collect_derivatives(),
+            },
+            changed_branches,
+        }
+    }
+
+    fn collect_derivatives(&self) -> Vec<Derivative> {
+        self.arguments
             .iter()
             .filter_map(|arg| {
                 self.record.get(&arg.id).and_then(|op| {
@@ -67,17 +234,172 @@ impl AutomaticDifferentiator {
                 input: *der.0,
                 derivative: der.1,
             })
-            .collect();
+            .collect()
+    }
 
-        Evaluation {
-            result: forward_evalutation.result,
-            derivatives,
-        }
+    /// Result of `operation` from the current results of its arguments, with
+    /// the outcome of the condition of a select node and the partials of an
+    /// implicit function. The
+    /// outputs of a block operation are read from `block_results`, filled by
+    /// its first output: the solution of a tridiagonal solve, or the rows of
+    /// a Cholesky factor one after the other (empty if the factorization
+    /// failed).
+    fn replay_operation(
+        &self,
+        operation: &Operation,
+        block_results: &mut HashMap<i64, Vec<f64>>,
+    ) -> Replayed {
+        let value = |id: &i64| self.record.get(id).map_or(f64::NAN, |op| op.get_result());
+        let result = match operation {
+            Operation::Add(_, lhs_id, rhs_id, _, _) => value(lhs_id) + value(rhs_id),
+            Operation::Sub(_, lhs_id, rhs_id, _, _) => value(lhs_id) - value(rhs_id),
+            Operation::Mul(_, lhs_id, rhs_id, _, _) => value(lhs_id) * value(rhs_id),
+            Operation::Div(_, lhs_id, rhs_id, _, _) => value(lhs_id) / value(rhs_id),
+            Operation::Ln(_, arg_id, _, _) => value(arg_id).ln(),
+            Operation::Sin(_, arg_id, _, _) => value(arg_id).sin(),
+            Operation::Cos(_, arg_id, _, _) => value(arg_id).cos(),
+            Operation::Exp(_, arg_id, _, _) => value(arg_id).exp(),
+            Operation::Pow(_, base_id, exp, _, _) => value(base_id).powf(*exp),
+            Operation::Sqrt(_, arg_id, _, _) => value(arg_id).sqrt(),
+            Operation::Log(_, arg_id, base, _, _) => value(arg_id).log(*base),
+            Operation::Cdf(_, arg_id, _, _) => special_functions::norm_cdf(value(arg_id)),
+            Operation::Abs(_, arg_id, _, _) => value(arg_id).abs(),
+            Operation::Max(_, lhs_id, rhs_id, _, _) => value(lhs_id).max(value(rhs_id)),
+            Operation::Min(_, lhs_id, rhs_id, _, _) => value(lhs_id).min(value(rhs_id)),
+            Operation::Clamp(_, arg_id, lo, hi, _, _) => value(arg_id).clamp(*lo, *hi),
+            Operation::Floor(_, arg_id, _, _) => value(arg_id).floor(),
+            Operation::Ceil(_, arg_id, _, _) => value(arg_id).ceil(),
+            Operation::Signum(_, arg_id, _, _) => value(arg_id).signum(),
+            Operation::Tan(_, arg_id, _, _) => value(arg_id).tan(),
+            Operation::Asin(_, arg_id, _, _) => value(arg_id).asin(),
+            Operation::Acos(_, arg_id, _, _) => value(arg_id).acos(),
+            Operation::Atan(_, arg_id, _, _) => value(arg_id).atan(),
+            Operation::Atan2(_, y_id, x_id, _, _) => value(y_id).atan2(value(x_id)),
+            Operation::Sinh(_, arg_id, _, _) => value(arg_id).sinh(),
+            Operation::Cosh(_, arg_id, _, _) => value(arg_id).cosh(),
+            Operation::Tanh(_, arg_id, _, _) => value(arg_id).tanh(),
+            Operation::Asinh(_, arg_id, _, _) => value(arg_id).asinh(),
+            Operation::Acosh(_, arg_id, _, _) => value(arg_id).acosh(),
+            Operation::Atanh(_, arg_id, _, _) => value(arg_id).atanh(),
+            Operation::Exp2(_, arg_id, _, _) => value(arg_id).exp2(),
+            Operation::Expm1(_, arg_id, _, _) => value(arg_id).exp_m1(),
+            Operation::Ln1p(_, arg_id, _, _) => value(arg_id).ln_1p(),
+            Operation::Cbrt(_, arg_id, _, _) => value(arg_id).cbrt(),
+            Operation::Hypot(_, lhs_id, rhs_id, _, _) => value(lhs_id).hypot(value(rhs_id)),
+            Operation::Recip(_, arg_id, _, _) => value(arg_id).recip(),
+            Operation::Powi(_, base_id, exp, _, _) => value(base_id).powi(*exp),
+            Operation::Powf(_, base_id, exp_id, _, _) => value(base_id).powf(value(exp_id)),
+            Operation::LogBase(_, arg_id, base_id, _, _) => value(arg_id).log(value(base_id)),
+            Operation::NormPdf(_, arg_id, _, _) => special_functions::norm_pdf(value(arg_id)),
+            Operation::NormInvCdf(_, arg_id, _, _) => {
+                special_functions::norm_inv_cdf(value(arg_id))
+            }
+            Operation::Erf(_, arg_id, _, _) => erf::erf(value(arg_id)),
+            Operation::Erfc(_, arg_id, _, _) => erf::erfc(value(arg_id)),
+            Operation::NormalCdf(_, arg_id, mu_id, sigma_id, _, _) => {
+                special_functions::norm_cdf((value(arg_id) - value(mu_id)) / value(sigma_id))
+            }
+            Operation::Gamma(_, arg_id, _, _) => gamma::gamma(value(arg_id)),
+            Operation::LnGamma(_, arg_id, _, _) => gamma::ln_gamma(value(arg_id)),
+            Operation::Digamma(_, arg_id, _, _) => gamma::digamma(value(arg_id)),
+            Operation::Beta(_, a_id, b_id, _, _) => {
+                special_functions::beta(value(a_id), value(b_id))
+            }
+            Operation::GammaP(_, arg_id, a_id, _, _) => {
+                special_functions::gamma_p(value(a_id), value(arg_id))
+            }
+            Operation::GammaQ(_, arg_id, a_id, _, _) => {
+                special_functions::gamma_q(value(a_id), value(arg_id))
+            }
+            Operation::BetaReg(_, arg_id, a_id, b_id, _, _) => {
+                special_functions::beta_reg(value(a_id), value(b_id), value(arg_id))
+            }
+            Operation::StudentsTCdf(_, arg_id, nu_id, _, _) => {
+                special_functions::students_t_cdf(value(arg_id), value(nu_id))
+            }
+            Operation::ChiSquaredCdf(_, arg_id, k_id, _, _) => {
+                special_functions::chi_squared_cdf(value(arg_id), value(k_id))
+            }
+            Operation::LogDensity(_, family, arg_ids, _, _) => {
+                let values: Vec<f64> = arg_ids.iter().map(value).collect();
+                family.log_density(values[0], &values[1..]).0
+            }
+            Operation::LogLikelihood(_, family, data, param_ids, _, _) => {
+                let values: Vec<f64> = param_ids.iter().map(value).collect();
+                family.log_likelihood(data, &values).0
+            }
+            Operation::SmoothStep(_, arg_id, eps, _, _) => {
+                special_functions::sigmoid(value(arg_id) / eps)
+            }
+            Operation::SmoothMax(_, lhs_id, rhs_id, eps, _, _) => {
+                let rhs = value(rhs_id);
+                rhs + eps * special_functions::softplus((value(lhs_id) - rhs) / eps)
+            }
+            Operation::SmoothAbs(_, arg_id, eps, _, _) => value(arg_id).hypot(*eps),
+            Operation::SmoothIndicator(_, arg_id, lo, hi, eps, _, _) => {
+                let arg = value(arg_id);
+                special_functions::sigmoid((arg - lo) / eps)
+                    - special_functions::sigmoid((arg - hi) / eps)
+            }
+            Operation::Select(_, lhs_id, comparison, rhs_id, a_id, b_id, _, _, _) => {
+                let holds = comparison.holds(value(lhs_id), value(rhs_id));
+                let result = if holds { value(a_id) } else { value(b_id) };
+                return Replayed::Select(result, holds);
+            }
+            Operation::ImplicitFunction(_, solver, arg_ids, _, _, _) => {
+                let values: Vec<f64> = arg_ids.iter().map(value).collect();
+                let (result, partials) = (solver.0)(&values);
+                return Replayed::ImplicitFunction(result, partials);
+            }
+            Operation::TridiagonalSolve(_, _, _, _) => 0.0,
+            Operation::TridiagonalSolution(_, solve_id, index, _, _) => {
+                let solution = block_results.entry(*solve_id).or_insert_with(|| {
+                    match self.record.get(solve_id) {
+                        Some(Operation::TridiagonalSolve(_, system, _, _)) => {
+                            let values =
+                                |ids: &[i64]| -> Vec<f64> { ids.iter().map(value).collect() };
+                            linear_algebra::thomas(
+                                &values(&system.lower),
+                                &values(&system.diagonal),
+                                &values(&system.upper),
+                                &values(&system.rhs),
+                            )
+                        }
+                        _ => Vec::new(),
+                    }
+                });
+                solution.get(*index).copied().unwrap_or(f64::NAN)
+            }
+            Operation::Cholesky(_, _, _, _) => 0.0,
+            Operation::CholeskyFactor(_, factorization_id, row, column, _, _) => {
+                let factor = block_results.entry(*factorization_id).or_insert_with(|| {
+                    match self.record.get(factorization_id) {
+                        Some(Operation::Cholesky(_, factorization, _, _)) => {
+                            let lower_triangle: Vec<Vec<f64>> = factorization
+                                .matrix
+                                .iter()
+                                .map(|ids| ids.iter().map(value).collect())
+                                .collect();
+                            linear_algebra::cholesky_values(&lower_triangle)
+                                .map_or(Vec::new(), |factor| factor.concat())
+                        }
+                        _ => Vec::new(),
+                    }
+                });
+                // row i of the factor holds i + 1 entries
+                factor
+                    .get(row * (row + 1) / 2 + column)
+                    .copied()
+                    .unwrap_or(f64::NAN)
+            }
+            Operation::Value(_, result, _) => *result,
+        };
+        Replayed::Result(result)
     }
 
-    fn forward_evaluate<F>(&mut self, func: F, arguments: &[Number]) -> Number
+    fn forward_evaluate<F, T>(&mut self, func: F, arguments: &[Number]) -> T
     where
-        F: Fn(&[Number]) -> Number,
+        F: Fn(&[Number]) -> T,
     {
         // Lock to avoid data races. Effectively serializes calls to forward_evaluate across all instances of AutomaticDifferentiator
         let _lock = DATA_RACE.lock().unwrap();
@@ -98,38 +420,69 @@ impl AutomaticDifferentiator {
     }
 
     fn reverse_propagate_adjoints(&mut self) {
-        print!("Running reverse mode adjoint propagation");
+        // Set adjoint of f() = y to 1.0. y is usually the last entry, but need not be:
+        // the nodes recorded after it do not contribute to it and keep a zero adjoint
+        if let Some(rec) = self.record.get_mut(&self.output) {
+            rec.set_adjoint(1.0);
+        }
 
-        // Set adjoint of f() = y to 1.0. i.e. set value of the last entry to 1.0.
-        if let Some(last_id) = self.node_list.last() {
-            println!("Setting adjoint to 1.0 for id {}", last_id);
-            if let Some(rec) = self.record.get_mut(last_id) {
-                rec.set_adjoint(1.0);
+        // Reverse through the nodes before y, which has already been set to 1.0
+        let output = self.output;
+        let nodes: Vec<i64> = self
+            .node_list
+            .iter()
+            .rev()
+            .skip_while(|id| **id >= output)
+            .copied()
+            .collect();
+        self.propagate_adjoints(&nodes);
+    }
+
+    /// Ids of the nodes `output` depends on, latest first, without `output`
+    /// itself.
+    fn dependencies(&self, output: i64) -> Vec<i64> {
+        let mut visited = HashSet::new();
+        let mut stack = vec![output];
+        while let Some(id) = stack.pop() {
+            if let Some(children) = self.parent_child_map.get(&id) {
+                for child in children {
+                    if visited.insert(*child) {
+                        stack.push(*child);
+                    }
+                }
             }
         }
+        visited.remove(&output);
+        let mut dependencies: Vec<i64> = visited.into_iter().collect();
+        dependencies.sort_unstable_by(|a, b| b.cmp(a));
+        dependencies
+    }
+
+    /// Adjoint equations of `nodes`, latest first, from the adjoints of their
+    /// parents.
+    fn propagate_adjoints(&mut self, nodes: &[i64]) {
+        // adjoints of the inputs of block operations, computed once per block
+        let mut block_adjoints: HashMap<i64, HashMap<i64, f64>> = HashMap::new();
 
-        // Reverse through the rest of the nodes, except the last which has already been set to 1.0
-        for node_map_entry in self.node_list.iter().rev().skip(1) {
+        for node_map_entry in nodes {
             // Implement the adjoint equation
             let mut adjoint = 0.0;
             if let Some(node) = self.record.get(node_map_entry) {
                 let node_id = node.get_id();
-
-                println!("Calculating adjoint for node Vi with id {}", node_id);
                 if let Some(parents) = self.child_parent_map.get(&node_id) {
                     for parent in parents {
                         if let Some(parent_operation) = self.record.get(parent) {
                             let parent_adj = parent_operation.get_adjoint();
-                            let parent_id = parent_operation.get_id();
                             match parent_operation {
-                                // lhs_ = parent_ * Dparent/Dlhs = parent_ * 1
-                                // rhs_ = parent_ * Dparent/Drhs = parent_ * 1
-                                Operation::Add(_, _, _, _, _) => {
-                                    adjoint += parent_adj; // lhs, rhs are identical, so this is enough
-                                    println!(
-                                        "node with id {} has adjoint {}. ParentId: {}",
-                                        node_id, adjoint, parent_id
-                                    );
+                                Operation::Add(_, lhs_id, rhs_id, _, _) => {
+                                    // lhs_ = parent_ * Dparent/Dlhs = parent_ * 1
+                                    if node_id == *lhs_id {
+                                        adjoint += parent_adj;
+                                    }
+                                    // rhs_ = parent_ * Dparent/Drhs = parent_ * 1
+                                    if node_id == *rhs_id {
+                                        adjoint += parent_adj;
+                                    }
                                 }
                                 Operation::Sub(_, lhs_id, rhs_id, _, _) => {
                                     // lhs_ = parent_ * Dparent/Dlhs = parent_
@@ -140,137 +493,639 @@ impl AutomaticDifferentiator {
                                     if node_id == *rhs_id {
                                         adjoint -= parent_adj;
                                     }
-                                    println!(
-                                        "node with id {} has adjoint {}. ParentId: {}",
-                                        node_id, adjoint, parent_id
-                                    );
                                 }
                                 Operation::Mul(_, lhs_id, rhs_id, _, _) => {
                                     // lhs_ = parent_ * Dparent/Dlhs = parent_ * rhs
-                                    if node_id == *lhs_id {
-                                        if let Some(rhs) = self.record.get(rhs_id) {
-                                            adjoint += parent_adj * rhs.get_result();
-                                        }
+                                    if node_id == *lhs_id
+                                        && let Some(rhs) = self.record.get(rhs_id)
+                                    {
+                                        adjoint += parent_adj * rhs.get_result();
                                     }
                                     // rhs_ = parent_ * Dparent/Drhs = parent_ * lhs
-                                    if node_id == *rhs_id {
-                                        if let Some(lhs) = self.record.get(lhs_id) {
-                                            adjoint += parent_adj * lhs.get_result();
-                                        }
+                                    if node_id == *rhs_id
+                                        && let Some(lhs) = self.record.get(lhs_id)
+                                    {
+                                        adjoint += parent_adj * lhs.get_result();
                                     }
-                                    println!(
-                                        "node with id {} has adjoint {}. ParentId: {}",
-                                        node_id, adjoint, parent_id
-                                    );
                                 }
                                 Operation::Div(_, num_id, den_id, _, _) => {
                                     // num_ = parent_ * Dparent/Dnum = parent_ * 1/den
-                                    if node_id == *num_id {
-                                        if let Some(den) = self.record.get(den_id) {
-                                            adjoint += parent_adj / den.get_result();
-                                        }
+                                    if node_id == *num_id
+                                        && let Some(den) = self.record.get(den_id)
+                                    {
+                                        adjoint += parent_adj / den.get_result();
                                     }
                                     // den_ = parent_ * Dparent/Dden = parent_ * -1 * (num/den^2)
-                                    if node_id == *den_id {
-                                        if let Some(num) = self.record.get(num_id) {
-                                            if let Some(den) = self.record.get(den_id) {
-                                                let num = num.get_result();
-                                                let den = den.get_result();
-                                                adjoint -= parent_adj * num / (den * den);
-                                            }
-                                        }
+                                    if node_id == *den_id
+                                        && let Some(num) = self.record.get(num_id)
+                                        && let Some(den) = self.record.get(den_id)
+                                    {
+                                        let num = num.get_result();
+                                        let den = den.get_result();
+                                        adjoint -= parent_adj * num / (den * den);
                                     }
-                                    println!(
-                                        "node with id {} has adjoint {}. ParentId: {}",
-                                        node_id, adjoint, parent_id
-                                    );
                                 }
                                 Operation::Ln(_, arg_id, _, _) => {
                                     // arg_ = parent_ * Dparent / Darg = parent_ * 1/arg
                                     if let Some(arg) = self.record.get(arg_id) {
                                         adjoint += parent_adj / arg.get_result();
-                                        println!(
-                                            "node with id {} has adjoint {}. ParentId: {}",
-                                            node_id, adjoint, parent_id
-                                        );
                                     }
                                 }
                                 Operation::Sin(_, arg_id, _, _) => {
                                     // arg_ = parent_ * Dparent / Darg = parent_ * cos(arg)
                                     if let Some(arg) = self.record.get(arg_id) {
                                         adjoint += parent_adj * arg.get_result().cos();
-                                        println!(
-                                            "node with id {} has adjoint {}. ParentId: {}",
-                                            node_id, adjoint, parent_id
-                                        );
                                     }
                                 }
                                 Operation::Cos(_, arg_id, _, _) => {
                                     // arg_ = parent_ * Dparent / Darg = parent_ * -sin(arg)
                                     if let Some(arg) = self.record.get(arg_id) {
                                         adjoint -= parent_adj * arg.get_result().sin();
-                                        println!(
-                                            "node with id {} has adjoint {}. ParentId: {}",
-                                            node_id, adjoint, parent_id
-                                        );
                                     }
                                 }
                                 Operation::Exp(_, _, _, _) => {
                                     // arg_ = parent_ * Dparent / Darg = parent_ * result (d(e^x)/dx = e^x)
                                     adjoint += parent_adj * parent_operation.get_result();
-                                    println!(
-                                        "node with id {} has adjoint {}. ParentId: {}",
-                                        node_id, adjoint, parent_id
-                                    );
                                 }
                                 Operation::Pow(_, base_id, exp, _, _) => {
                                     // arg_ = parent_ * Dparent / Darg = parent_ * exp * base ^ (exp - 1)
                                     if let Some(base) = self.record.get(base_id) {
                                         let exp = *exp;
-                                        adjoint += parent_adj * exp * base.get_result().powf(exp - 1.0);
-                                        println!(
-                                            "node with id {} has adjoint {}. ParentId: {}",
-                                            node_id, adjoint, parent_id
-                                        );
+                                        adjoint +=
+                                            parent_adj * exp * base.get_result().powf(exp - 1.0);
                                     }
                                 }
                                 Operation::Sqrt(_, arg_id, _, _) => {
                                     // arg_ = parent_ * Dparent / Darg = parent_ * (1 / (2*sqrt(x)))
                                     if let Some(arg) = self.record.get(arg_id) {
                                         adjoint += parent_adj / (2.0 * arg.get_result().sqrt());
-                                        println!(
-                                            "node with id {} has adjoint {}. ParentId: {}",
-                                            node_id, adjoint, parent_id
-                                        );
                                     }
                                 }
                                 Operation::Log(_, arg_id, base, _, _) => {
                                     // arg_ = parent_ * Dparent / Darg = parent_ * (1/(arg*ln(base)))
                                     if let Some(arg) = self.record.get(arg_id) {
                                         adjoint += parent_adj / (arg.get_result() * base.ln());
-                                        println!(
-                                            "node with id {} has adjoint {}. ParentId: {}",
-                                            node_id, adjoint, parent_id
-                                        );
                                     }
                                 }
                                 Operation::Cdf(_, arg_id, _, _) => {
                                     // arg_ = parent_ * Dparent / Darg = parent_ * pdf(x)
                                     if let Some(arg) = self.record.get(arg_id) {
-                                        let norm = Normal::new(0.0, 1.0).unwrap();
-                                        adjoint += parent_adj * norm.pdf(arg.get_result());
-                                        println!(
-                                            "node with id {} has adjoint {}. ParentId: {}",
-                                            node_id, adjoint, parent_id
+                                        adjoint += parent_adj
+                                            * special_functions::norm_pdf(arg.get_result());
+                                    }
+                                }
+                                Operation::Abs(_, arg_id, _, _) => {
+                                    // arg_ = parent_ * Dparent / Darg = parent_ * signum(arg), 0 at arg = 0
+                                    if let Some(arg) = self.record.get(arg_id) {
+                                        let arg = arg.get_result();
+                                        if arg != 0.0 {
+                                            adjoint += parent_adj * arg.signum();
+                                        }
+                                    }
+                                }
+                                Operation::Max(_, lhs_id, rhs_id, _, _)
+                                | Operation::Min(_, lhs_id, rhs_id, _, _) => {
+                                    // The selected argument gets parent_, the other nothing.
+                                    // On a tie both get parent_ / 2
+                                    if let Some(lhs) = self.record.get(lhs_id)
+                                        && let Some(rhs) = self.record.get(rhs_id)
+                                    {
+                                        let lhs = lhs.get_result();
+                                        let rhs = rhs.get_result();
+                                        let lhs_selected = match parent_operation {
+                                            Operation::Max(_, _, _, _, _) => lhs > rhs,
+                                            _ => lhs < rhs,
+                                        };
+                                        let lhs_weight = if lhs == rhs {
+                                            0.5
+                                        } else if lhs_selected {
+                                            1.0
+                                        } else {
+                                            0.0
+                                        };
+                                        if node_id == *lhs_id {
+                                            adjoint += parent_adj * lhs_weight;
+                                        }
+                                        if node_id == *rhs_id {
+                                            adjoint += parent_adj * (1.0 - lhs_weight);
+                                        }
+                                    }
+                                }
+                                Operation::Clamp(_, arg_id, lo, hi, _, _) => {
+                                    // arg_ = parent_ inside (lo, hi), 0 outside and parent_ / 2 on a bound
+                                    if let Some(arg) = self.record.get(arg_id) {
+                                        let arg = arg.get_result();
+                                        if lo < hi {
+                                            if *lo < arg && arg < *hi {
+                                                adjoint += parent_adj;
+                                            } else if arg == *lo || arg == *hi {
+                                                adjoint += 0.5 * parent_adj;
+                                            }
+                                        }
+                                    }
+                                }
+                                Operation::Floor(_, _, _, _)
+                                | Operation::Ceil(_, _, _, _)
+                                | Operation::Signum(_, _, _, _) => {
+                                    // Piecewise constant: arg_ = parent_ * 0
+                                }
+                                Operation::Tan(_, _, _, _) => {
+                                    // arg_ = parent_ * (1 + tan(arg)^2)
+                                    let res = parent_operation.get_result();
+                                    adjoint += parent_adj * (1.0 + res * res);
+                                }
+                                Operation::Asin(_, arg_id, _, _) => {
+                                    // arg_ = parent_ * 1/sqrt(1 - arg^2)
+                                    if let Some(arg) = self.record.get(arg_id) {
+                                        let x = arg.get_result();
+                                        adjoint += parent_adj / (1.0 - x * x).sqrt();
+                                    }
+                                }
+                                Operation::Acos(_, arg_id, _, _) => {
+                                    // arg_ = parent_ * -1/sqrt(1 - arg^2)
+                                    if let Some(arg) = self.record.get(arg_id) {
+                                        let x = arg.get_result();
+                                        adjoint += -parent_adj / (1.0 - x * x).sqrt();
+                                    }
+                                }
+                                Operation::Atan(_, arg_id, _, _) => {
+                                    // arg_ = parent_ * 1/(1 + arg^2)
+                                    if let Some(arg) = self.record.get(arg_id) {
+                                        let x = arg.get_result();
+                                        adjoint += parent_adj / (1.0 + x * x);
+                                    }
+                                }
+                                Operation::Atan2(_, y_id, x_id, _, _) => {
+                                    // y_ = parent_ * x/(x^2 + y^2), x_ = parent_ * -y/(x^2 + y^2)
+                                    if let Some(y) = self.record.get(y_id)
+                                        && let Some(x) = self.record.get(x_id)
+                                    {
+                                        let y = y.get_result();
+                                        let x = x.get_result();
+                                        let r2 = x * x + y * y;
+                                        if node_id == *y_id {
+                                            adjoint += parent_adj * x / r2;
+                                        }
+                                        if node_id == *x_id {
+                                            adjoint -= parent_adj * y / r2;
+                                        }
+                                    }
+                                }
+                                Operation::Sinh(_, arg_id, _, _) => {
+                                    // arg_ = parent_ * cosh(arg)
+                                    if let Some(arg) = self.record.get(arg_id) {
+                                        let x = arg.get_result();
+                                        adjoint += parent_adj * x.cosh();
+                                    }
+                                }
+                                Operation::Cosh(_, arg_id, _, _) => {
+                                    // arg_ = parent_ * sinh(arg)
+                                    if let Some(arg) = self.record.get(arg_id) {
+                                        let x = arg.get_result();
+                                        adjoint += parent_adj * x.sinh();
+                                    }
+                                }
+                                Operation::Tanh(_, _, _, _) => {
+                                    // arg_ = parent_ * (1 - tanh(arg)^2)
+                                    let res = parent_operation.get_result();
+                                    adjoint += parent_adj * (1.0 - res * res);
+                                }
+                                Operation::Asinh(_, arg_id, _, _) => {
+                                    // arg_ = parent_ * 1/sqrt(arg^2 + 1)
+                                    if let Some(arg) = self.record.get(arg_id) {
+                                        let x = arg.get_result();
+                                        adjoint += parent_adj / (x * x + 1.0).sqrt();
+                                    }
+                                }
+                                Operation::Acosh(_, arg_id, _, _) => {
+                                    // arg_ = parent_ * 1/sqrt(arg^2 - 1)
+                                    if let Some(arg) = self.record.get(arg_id) {
+                                        let x = arg.get_result();
+                                        adjoint += parent_adj / (x * x - 1.0).sqrt();
+                                    }
+                                }
+                                Operation::Atanh(_, arg_id, _, _) => {
+                                    // arg_ = parent_ * 1/(1 - arg^2)
+                                    if let Some(arg) = self.record.get(arg_id) {
+                                        let x = arg.get_result();
+                                        adjoint += parent_adj / (1.0 - x * x);
+                                    }
+                                }
+                                Operation::Exp2(_, _, _, _) => {
+                                    // arg_ = parent_ * 2^arg * ln(2)
+                                    let res = parent_operation.get_result();
+                                    adjoint += parent_adj * res * std::f64::consts::LN_2;
+                                }
+                                Operation::Expm1(_, _, _, _) => {
+                                    // arg_ = parent_ * e^arg = parent_ * (result + 1)
+                                    let res = parent_operation.get_result();
+                                    adjoint += parent_adj * (res + 1.0);
+                                }
+                                Operation::Ln1p(_, arg_id, _, _) => {
+                                    // arg_ = parent_ * 1/(1 + arg)
+                                    if let Some(arg) = self.record.get(arg_id) {
+                                        let x = arg.get_result();
+                                        adjoint += parent_adj / (1.0 + x);
+                                    }
+                                }
+                                Operation::Cbrt(_, _, _, _) => {
+                                    // arg_ = parent_ * 1/(3 * cbrt(arg)^2)
+                                    let res = parent_operation.get_result();
+                                    adjoint += parent_adj / (3.0 * res * res);
+                                }
+                                Operation::Hypot(_, lhs_id, rhs_id, _, _) => {
+                                    // lhs_ = parent_ * lhs/hypot, rhs_ = parent_ * rhs/hypot. 0 at the origin
+                                    let res = parent_operation.get_result();
+                                    if res != 0.0 {
+                                        if node_id == *lhs_id
+                                            && let Some(lhs) = self.record.get(lhs_id)
+                                        {
+                                            adjoint += parent_adj * lhs.get_result() / res;
+                                        }
+                                        if node_id == *rhs_id
+                                            && let Some(rhs) = self.record.get(rhs_id)
+                                        {
+                                            adjoint += parent_adj * rhs.get_result() / res;
+                                        }
+                                    }
+                                }
+                                Operation::Recip(_, _, _, _) => {
+                                    // arg_ = parent_ * -1/arg^2 = parent_ * -result^2
+                                    let res = parent_operation.get_result();
+                                    adjoint += -parent_adj * res * res;
+                                }
+                                Operation::Powi(_, base_id, exp, _, _) => {
+                                    // arg_ = parent_ * exp * base ^ (exp - 1)
+                                    if let Some(base) = self.record.get(base_id)
+                                        && *exp != 0
+                                    {
+                                        let exp = *exp;
+                                        adjoint += parent_adj
+                                            * exp as f64
+                                            * base.get_result().powi(exp - 1);
+                                    }
+                                }
+                                Operation::Powf(_, base_id, exp_id, _, _) => {
+                                    // base_ = parent_ * exp * base^(exp - 1), 0 if exp = 0
+                                    // exp_ = parent_ * base^exp * ln(base), 0 if base = 0
+                                    if let Some(base) = self.record.get(base_id)
+                                        && let Some(exp) = self.record.get(exp_id)
+                                    {
+                                        let base = base.get_result();
+                                        let exp = exp.get_result();
+                                        if node_id == *base_id && exp != 0.0 {
+                                            adjoint += parent_adj * exp * base.powf(exp - 1.0);
+                                        }
+                                        if node_id == *exp_id && base != 0.0 {
+                                            adjoint += parent_adj
+                                                * parent_operation.get_result()
+                                                * base.ln();
+                                        }
+                                    }
+                                }
+                                Operation::LogBase(_, arg_id, base_id, _, _) => {
+                                    // arg_ = parent_ * 1/(arg * ln(base))
+                                    // base_ = parent_ * -ln(arg)/(base * ln(base)^2) = parent_ * -result/(base * ln(base))
+                                    if let Some(arg) = self.record.get(arg_id)
+                                        && let Some(base) = self.record.get(base_id)
+                                    {
+                                        let arg = arg.get_result();
+                                        let base = base.get_result();
+                                        if node_id == *arg_id {
+                                            adjoint += parent_adj / (arg * base.ln());
+                                        }
+                                        if node_id == *base_id {
+                                            adjoint -= parent_adj * parent_operation.get_result()
+                                                / (base * base.ln());
+                                        }
+                                    }
+                                }
+                                Operation::NormPdf(_, arg_id, _, _) => {
+                                    // arg_ = parent_ * -arg * pdf(arg)
+                                    if let Some(arg) = self.record.get(arg_id) {
+                                        adjoint -= parent_adj
+                                            * arg.get_result()
+                                            * parent_operation.get_result();
+                                    }
+                                }
+                                Operation::NormInvCdf(_, _, _, _) => {
+                                    // arg_ = parent_ * 1/pdf(inv_cdf(arg))
+                                    let res = parent_operation.get_result();
+                                    adjoint += parent_adj / special_functions::norm_pdf(res);
+                                }
+                                Operation::Erf(_, arg_id, _, _) => {
+                                    // arg_ = parent_ * 2/sqrt(PI) * e^(-arg^2)
+                                    if let Some(arg) = self.record.get(arg_id) {
+                                        let x = arg.get_result();
+                                        adjoint += parent_adj * FRAC_2_SQRT_PI * (-x * x).exp();
+                                    }
+                                }
+                                Operation::Erfc(_, arg_id, _, _) => {
+                                    // arg_ = parent_ * -2/sqrt(PI) * e^(-arg^2)
+                                    if let Some(arg) = self.record.get(arg_id) {
+                                        let x = arg.get_result();
+                                        adjoint -= parent_adj * FRAC_2_SQRT_PI * (-x * x).exp();
+                                    }
+                                }
+                                Operation::NormalCdf(_, arg_id, mu_id, sigma_id, _, _) => {
+                                    // With z = (arg - mu)/sigma:
+                                    // arg_ = parent_ * pdf(z)/sigma, mu_ = -arg_, sigma_ = -arg_ * z
+                                    if let Some(arg) = self.record.get(arg_id)
+                                        && let Some(mu) = self.record.get(mu_id)
+                                        && let Some(sigma) = self.record.get(sigma_id)
+                                    {
+                                        let sigma = sigma.get_result();
+                                        let z = (arg.get_result() - mu.get_result()) / sigma;
+                                        let d_arg = special_functions::norm_pdf(z) / sigma;
+                                        if node_id == *arg_id {
+                                            adjoint += parent_adj * d_arg;
+                                        }
+                                        if node_id == *mu_id {
+                                            adjoint -= parent_adj * d_arg;
+                                        }
+                                        if node_id == *sigma_id {
+                                            adjoint -= parent_adj * d_arg * z;
+                                        }
+                                    }
+                                }
+                                Operation::Gamma(_, arg_id, _, _) => {
+                                    // arg_ = parent_ * gamma(arg) * digamma(arg)
+                                    if let Some(arg) = self.record.get(arg_id) {
+                                        let x = arg.get_result();
+                                        adjoint += parent_adj
+                                            * parent_operation.get_result()
+                                            * gamma::digamma(x);
+                                    }
+                                }
+                                Operation::LnGamma(_, arg_id, _, _) => {
+                                    // arg_ = parent_ * digamma(arg)
+                                    if let Some(arg) = self.record.get(arg_id) {
+                                        let x = arg.get_result();
+                                        adjoint += parent_adj * gamma::digamma(x);
+                                    }
+                                }
+                                Operation::Digamma(_, arg_id, _, _) => {
+                                    // arg_ = parent_ * trigamma(arg)
+                                    if let Some(arg) = self.record.get(arg_id) {
+                                        let x = arg.get_result();
+                                        adjoint += parent_adj * special_functions::trigamma(x);
+                                    }
+                                }
+                                Operation::Beta(_, a_id, b_id, _, _) => {
+                                    // a_ = parent_ * B(a, b) * (digamma(a) - digamma(a + b)), likewise for b
+                                    if let Some(a) = self.record.get(a_id)
+                                        && let Some(b) = self.record.get(b_id)
+                                    {
+                                        let a = a.get_result();
+                                        let b = b.get_result();
+                                        let res = parent_operation.get_result();
+                                        let digamma_ab = gamma::digamma(a + b);
+                                        if node_id == *a_id {
+                                            adjoint +=
+                                                parent_adj * res * (gamma::digamma(a) - digamma_ab);
+                                        }
+                                        if node_id == *b_id {
+                                            adjoint +=
+                                                parent_adj * res * (gamma::digamma(b) - digamma_ab);
+                                        }
+                                    }
+                                }
+                                Operation::GammaP(_, arg_id, a_id, _, _) => {
+                                    if let Some(x) = self.record.get(arg_id)
+                                        && let Some(a) = self.record.get(a_id)
+                                    {
+                                        let (x, a) = (x.get_result(), a.get_result());
+                                        // arg_ = parent_ * arg^(a-1) * e^(-arg) / gamma(a)
+                                        if node_id == *arg_id {
+                                            adjoint += parent_adj
+                                                * special_functions::gamma_p_density(a, x);
+                                        }
+                                        // a_ = parent_ * DP/Da, by central difference
+                                        if node_id == *a_id {
+                                            adjoint += parent_adj
+                                                * special_functions::parameter_derivative(
+                                                    |a| special_functions::gamma_p(a, x),
+                                                    a,
+                                                );
+                                        }
+                                    }
+                                }
+                                Operation::GammaQ(_, arg_id, a_id, _, _) => {
+                                    if let Some(x) = self.record.get(arg_id)
+                                        && let Some(a) = self.record.get(a_id)
+                                    {
+                                        let (x, a) = (x.get_result(), a.get_result());
+                                        // arg_ = parent_ * -arg^(a-1) * e^(-arg) / gamma(a)
+                                        if node_id == *arg_id {
+                                            adjoint -= parent_adj
+                                                * special_functions::gamma_p_density(a, x);
+                                        }
+                                        // a_ = parent_ * DQ/Da, by central difference
+                                        if node_id == *a_id {
+                                            adjoint += parent_adj
+                                                * special_functions::parameter_derivative(
+                                                    |a| special_functions::gamma_q(a, x),
+                                                    a,
+                                                );
+                                        }
+                                    }
+                                }
+                                Operation::BetaReg(_, arg_id, a_id, b_id, _, _) => {
+                                    if let Some(x) = self.record.get(arg_id)
+                                        && let Some(a) = self.record.get(a_id)
+                                        && let Some(b) = self.record.get(b_id)
+                                    {
+                                        let (x, a, b) =
+                                            (x.get_result(), a.get_result(), b.get_result());
+                                        // arg_ = parent_ * arg^(a-1) * (1 - arg)^(b-1) / B(a, b)
+                                        if node_id == *arg_id {
+                                            adjoint += parent_adj
+                                                * special_functions::beta_reg_density(a, b, x);
+                                        }
+                                        // a_ and b_ = parent_ * DI/Da and DI/Db, by central differences
+                                        if node_id == *a_id {
+                                            adjoint += parent_adj
+                                                * special_functions::parameter_derivative(
+                                                    |a| special_functions::beta_reg(a, b, x),
+                                                    a,
+                                                );
+                                        }
+                                        if node_id == *b_id {
+                                            adjoint += parent_adj
+                                                * special_functions::parameter_derivative(
+                                                    |b| special_functions::beta_reg(a, b, x),
+                                                    b,
+                                                );
+                                        }
+                                    }
+                                }
+                                Operation::StudentsTCdf(_, arg_id, nu_id, _, _) => {
+                                    if let Some(x) = self.record.get(arg_id)
+                                        && let Some(nu) = self.record.get(nu_id)
+                                    {
+                                        let (x, nu) = (x.get_result(), nu.get_result());
+                                        // arg_ = parent_ * Student's t pdf(arg)
+                                        if node_id == *arg_id {
+                                            adjoint += parent_adj
+                                                * special_functions::students_t_pdf(x, nu);
+                                        }
+                                        // nu_ = parent_ * DF/Dnu, by central difference
+                                        if node_id == *nu_id {
+                                            adjoint += parent_adj
+                                                * special_functions::parameter_derivative(
+                                                    |nu| special_functions::students_t_cdf(x, nu),
+                                                    nu,
+                                                );
+                                        }
+                                    }
+                                }
+                                Operation::ChiSquaredCdf(_, arg_id, k_id, _, _) => {
+                                    if let Some(x) = self.record.get(arg_id)
+                                        && let Some(k) = self.record.get(k_id)
+                                    {
+                                        let (x, k) = (x.get_result(), k.get_result());
+                                        // arg_ = parent_ * chi-squared pdf(arg)
+                                        if node_id == *arg_id {
+                                            adjoint += parent_adj
+                                                * special_functions::chi_squared_pdf(x, k);
+                                        }
+                                        // k_ = parent_ * DF/Dk, by central difference
+                                        if node_id == *k_id {
+                                            adjoint += parent_adj
+                                                * special_functions::parameter_derivative(
+                                                    |k| special_functions::chi_squared_cdf(x, k),
+                                                    k,
+                                                );
+                                        }
+                                    }
+                                }
+                                Operation::SmoothStep(_, _, eps, _, _) => {
+                                    // arg_ = parent_ * s * (1 - s) / eps, where s is the result
+                                    let res = parent_operation.get_result();
+                                    adjoint += parent_adj * res * (1.0 - res) / eps;
+                                }
+                                Operation::SmoothMax(_, lhs_id, rhs_id, eps, _, _) => {
+                                    // lhs_ = parent_ * sigmoid((lhs - rhs)/eps), rhs_ = parent_ * (1 - sigmoid(..))
+                                    if let Some(lhs) = self.record.get(lhs_id)
+                                        && let Some(rhs) = self.record.get(rhs_id)
+                                    {
+                                        let lhs_weight = special_functions::sigmoid(
+                                            (lhs.get_result() - rhs.get_result()) / eps,
                                         );
+                                        if node_id == *lhs_id {
+                                            adjoint += parent_adj * lhs_weight;
+                                        }
+                                        if node_id == *rhs_id {
+                                            adjoint += parent_adj * (1.0 - lhs_weight);
+                                        }
+                                    }
+                                }
+                                Operation::SmoothAbs(_, arg_id, _, _, _) => {
+                                    // arg_ = parent_ * arg / sqrt(arg^2 + eps^2)
+                                    let res = parent_operation.get_result();
+                                    if res != 0.0
+                                        && let Some(arg) = self.record.get(arg_id)
+                                    {
+                                        adjoint += parent_adj * arg.get_result() / res;
+                                    }
+                                }
+                                Operation::SmoothIndicator(_, arg_id, lo, hi, eps, _, _) => {
+                                    // arg_ = parent_ * (s_lo * (1 - s_lo) - s_hi * (1 - s_hi)) / eps
+                                    if let Some(arg) = self.record.get(arg_id) {
+                                        let arg = arg.get_result();
+                                        let s_lo = special_functions::sigmoid((arg - lo) / eps);
+                                        let s_hi = special_functions::sigmoid((arg - hi) / eps);
+                                        adjoint += parent_adj
+                                            * (s_lo * (1.0 - s_lo) - s_hi * (1.0 - s_hi))
+                                            / eps;
+                                    }
+                                }
+                                Operation::LogDensity(_, family, arg_ids, _, _) => {
+                                    // arg_ = parent_ * Dlog_density/Darg, for every position arg takes
+                                    let values: Option<Vec<f64>> = arg_ids
+                                        .iter()
+                                        .map(|arg_id| {
+                                            self.record.get(arg_id).map(|arg| arg.get_result())
+                                        })
+                                        .collect();
+                                    if let Some(values) = values {
+                                        let (_, gradient) =
+                                            family.log_density(values[0], &values[1..]);
+                                        for (arg_id, partial) in arg_ids.iter().zip(gradient) {
+                                            if node_id == *arg_id {
+                                                adjoint += parent_adj * partial;
+                                            }
+                                        }
+                                    }
+                                }
+                                Operation::LogLikelihood(_, family, data, param_ids, _, _) => {
+                                    // param_ = parent_ * sum over data of Dlog_density/Dparam
+                                    let values: Option<Vec<f64>> = param_ids
+                                        .iter()
+                                        .map(|param_id| {
+                                            self.record
+                                                .get(param_id)
+                                                .map(|param| param.get_result())
+                                        })
+                                        .collect();
+                                    if let Some(values) = values {
+                                        let (_, gradient) = family.log_likelihood(data, &values);
+                                        for (param_id, partial) in
+                                            param_ids.iter().zip(&gradient[1..])
+                                        {
+                                            if node_id == *param_id {
+                                                adjoint += parent_adj * partial;
+                                            }
+                                        }
                                     }
                                 }
+                                Operation::Select(_, _, _, _, a_id, b_id, holds, _, _) => {
+                                    // a_ = parent_ if the condition holds, b_ = parent_ otherwise.
+                                    // The sides of the condition get nothing
+                                    if node_id == *a_id && *holds {
+                                        adjoint += parent_adj;
+                                    }
+                                    if node_id == *b_id && !*holds {
+                                        adjoint += parent_adj;
+                                    }
+                                }
+                                Operation::ImplicitFunction(_, _, arg_ids, partials, _, _) => {
+                                    // arg_ = parent_ * Dresult/Darg, with the partials stored on the node
+                                    for (arg_id, partial) in arg_ids.iter().zip(partials) {
+                                        if node_id == *arg_id {
+                                            adjoint += parent_adj * partial;
+                                        }
+                                    }
+                                }
+                                Operation::TridiagonalSolve(solve_id, system, _, _) => {
+                                    // coefficients_ and rhs_ from lambda = A^-T x_, see tridiagonal_solve_adjoints
+                                    let adjoints =
+                                        block_adjoints.entry(*solve_id).or_insert_with(|| {
+                                            self.tridiagonal_solve_adjoints(*solve_id, system)
+                                        });
+                                    if let Some(partial) = adjoints.get(&node_id) {
+                                        adjoint += partial;
+                                    }
+                                }
+                                Operation::TridiagonalSolution(_, _, _, _, _) => {
+                                    // the solve collects the adjoints of all its solutions itself
+                                }
+                                Operation::Cholesky(factorization_id, factorization, _, _) => {
+                                    // A_ from the adjoint of the factor, see cholesky_adjoints
+                                    let adjoints = block_adjoints
+                                        .entry(*factorization_id)
+                                        .or_insert_with(|| {
+                                            self.cholesky_adjoints(*factorization_id, factorization)
+                                        });
+                                    if let Some(partial) = adjoints.get(&node_id) {
+                                        adjoint += partial;
+                                    }
+                                }
+                                Operation::CholeskyFactor(_, _, _, _, _, _) => {
+                                    // the factorization collects the adjoints of all its entries itself
+                                }
                                 Operation::Value(_, _, _) => {
                                     adjoint += parent_adj;
-                                    println!(
-                                        "node with id {} has adjoint {}. ParentId: {}",
-                                        node_id, adjoint, parent_id
-                                    );
                                 }
                             };
                         }
@@ -285,6 +1140,84 @@ impl AutomaticDifferentiator {
         }
     }
 
+    /// Adjoints of the coefficients and right hand side of the tridiagonal
+    /// solve `solve_id`, keyed by id, from the adjoints of its solutions x:
+    /// with lambda = A^-T x_, rhs_ = lambda and A_ij = -lambda_i x_j.
+    fn tridiagonal_solve_adjoints(
+        &self,
+        solve_id: i64,
+        system: &TridiagonalSystem,
+    ) -> HashMap<i64, f64> {
+        let n = system.diagonal.len();
+        let mut x = vec![0.0; n];
+        let mut x_adjoint = vec![0.0; n];
+        if let Some(solutions) = self.child_parent_map.get(&solve_id) {
+            for solution in solutions {
+                if let Some(Operation::TridiagonalSolution(_, _, index, result, adjoint)) =
+                    self.record.get(solution)
+                {
+                    x[*index] = *result;
+                    x_adjoint[*index] = *adjoint;
+                }
+            }
+        }
+
+        let value = |id: &i64| self.record.get(id).map_or(f64::NAN, |op| op.get_result());
+        let values = |ids: &[i64]| -> Vec<f64> { ids.iter().map(value).collect() };
+        // the transpose swaps the lower and upper diagonals
+        let lambda = linear_algebra::thomas(
+            &values(&system.upper),
+            &values(&system.diagonal),
+            &values(&system.lower),
+            &x_adjoint,
+        );
+
+        let mut adjoints = HashMap::new();
+        for i in 0..n {
+            *adjoints.entry(system.rhs[i]).or_insert(0.0) += lambda[i];
+            *adjoints.entry(system.diagonal[i]).or_insert(0.0) -= lambda[i] * x[i];
+        }
+        for i in 0..n - 1 {
+            *adjoints.entry(system.lower[i]).or_insert(0.0) -= lambda[i + 1] * x[i];
+            *adjoints.entry(system.upper[i]).or_insert(0.0) -= lambda[i] * x[i + 1];
+        }
+        adjoints
+    }
+
+    /// Adjoints of the lower triangle of the matrix of the Cholesky
+    /// factorization `factorization_id`, keyed by id, from the adjoints of the
+    /// entries of its factor. An entry below the diagonal stands for both
+    /// (i, j) and (j, i) of the symmetric matrix and gets both adjoints.
+    fn cholesky_adjoints(
+        &self,
+        factorization_id: i64,
+        factorization: &CholeskyFactorization,
+    ) -> HashMap<i64, f64> {
+        let n = factorization.matrix.len();
+        let mut factor: Vec<Vec<f64>> = (0..n).map(|i| vec![0.0; i + 1]).collect();
+        let mut factor_adjoint = factor.clone();
+        if let Some(entries) = self.child_parent_map.get(&factorization_id) {
+            for entry in entries {
+                if let Some(Operation::CholeskyFactor(_, _, row, column, result, adjoint)) =
+                    self.record.get(entry)
+                {
+                    factor[*row][*column] = *result;
+                    factor_adjoint[*row][*column] = *adjoint;
+                }
+            }
+        }
+
+        let matrix_adjoint = linear_algebra::cholesky_adjoint(&factor, &factor_adjoint);
+        let mut adjoints = HashMap::new();
+        for (i, ids) in factorization.matrix.iter().enumerate() {
+            for (j, id) in ids.iter().enumerate() {
+                let multiplicity = if i == j { 1.0 } else { 2.0 };
+                *adjoints.entry(*id).or_insert(0.0) += multiplicity * matrix_adjoint[i][j];
+            }
+        }
+        adjoints
+    }
+
     pub fn print_parent_map(&self) {
         for kv in self.parent_child_map.iter() {
             if let Some(rec) = self.record.get(kv.0) {
@@ -339,18 +1272,17 @@ impl AutomaticDifferentiator {
             for child in kv.1.iter() {
                 let parent_record_option = self.record.get(kv.0);
                 let child_record_option = self.record.get(child);
-                if let Some(parent_record) = parent_record_option {
-                    if let Some(child_record) = child_record_option {
-                        println!(
-                            "{} -> {};",
-                            parent_record.get_graph_string(),
-                            child_record.get_graph_string()
-                        );
-                    }
+                if let Some(parent_record) = parent_record_option
+                    && let Some(child_record) = child_record_option
+                {
+                    println!(
+                        "{} -> {};",
+                        parent_record.get_graph_string(),
+                        child_record.get_graph_string()
+                    );
                 }
             }
         }
         println!("}}");
     }
 }
-
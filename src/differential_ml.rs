@@ -0,0 +1,256 @@
+//! Training sets and proxies for differential machine learning.
+//!
+//! A proxy pricer is fitted not only to sampled values y(x) but also to their
+//! derivatives dy/dx, which the tape delivers for the cost of a sweep. With
+//! Monte Carlo samples y is the payoff of a single path from the state x and
+//! dy/dx its pathwise derivative: both are unbiased estimates of the price
+//! and its gradient, and the derivatives carry n more labels per sample.
+//!
+//! The proxy here is a linear regression on the monomials of the inputs up
+//! to a degree, fitted in closed form. The loss adds to the squared errors
+//! of the values the squared errors of the derivatives times a weight:
+//!
+//! ```text
+//! sum_s (f(x_s) - y_s)^2 + w sum_s sum_i (df/dz_i(x_s) - dy_s/dz_i)^2
+//! ```
+//!
+//! with z the inputs standardized over the training set, which puts every
+//! derivative on the scale of the values.
+
+use crate::automatic_differentiator::AutomaticDifferentiator;
+use crate::linear_algebra;
+use crate::number::Number;
+use crate::random::{NormalMethod, Rng};
+
+/// A training sample: the inputs, the value and its derivatives with respect
+/// to the inputs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub x: Vec<f64>,
+    pub y: f64,
+    pub dydx: Vec<f64>,
+}
+
+/// Draws input states uniformly in the box between `lower` and `upper` and
+/// records a pricer on each.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampleGenerator {
+    pub lower: Vec<f64>,
+    pub upper: Vec<f64>,
+    /// Standard normals handed to the pricer with each state, to simulate a
+    /// path from it.
+    pub normals: usize,
+    pub seed: u64,
+    pub normal_method: NormalMethod,
+}
+
+impl SampleGenerator {
+    pub fn new(lower: Vec<f64>, upper: Vec<f64>, normals: usize, seed: u64) -> Self {
+        assert!(
+            lower.len() == upper.len() && lower.iter().zip(&upper).all(|(lo, hi)| lo <= hi),
+            "the lower and upper bounds of the states must pair up"
+        );
+        SampleGenerator {
+            lower,
+            upper,
+            normals,
+            seed,
+            normal_method: NormalMethod::BoxMuller,
+        }
+    }
+
+    /// `samples` samples of `pricer`, which receives a state and fresh
+    /// normals and returns the value y, recorded with the state as arguments.
+    pub fn generate<P>(&self, samples: usize, pricer: P) -> Vec<Sample>
+    where
+        P: Fn(&[Number], &[f64]) -> Number,
+    {
+        let mut rng = Rng::new(self.seed);
+        let mut automatic_differentiator = AutomaticDifferentiator::new();
+        (0..samples)
+            .map(|_| {
+                let x: Vec<f64> = self
+                    .lower
+                    .iter()
+                    .zip(&self.upper)
+                    .map(|(lo, hi)| lo + (hi - lo) * rng.uniform())
+                    .collect();
+                let normals = rng.normals(self.normals, self.normal_method);
+
+                let arguments: Vec<Number> = x.iter().map(|x| Number::new(*x)).collect();
+                let evaluation = automatic_differentiator
+                    .derivatives(|args: &[Number]| pricer(args, &normals), &arguments);
+                Sample {
+                    y: evaluation.result,
+                    dydx: arguments
+                        .iter()
+                        .map(|argument| evaluation.derivative(*argument))
+                        .collect(),
+                    x,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Polynomial proxy sum_k c_k prod_i z_i^e_ki in the standardized inputs
+/// z_i = (x_i - mean_i) / scale_i.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolynomialProxy {
+    pub mean: Vec<f64>,
+    pub scale: Vec<f64>,
+    /// Exponents of the inputs in each monomial.
+    pub exponents: Vec<Vec<u32>>,
+    pub coefficients: Vec<f64>,
+}
+
+impl PolynomialProxy {
+    /// Value of the proxy at `x`, on the tape if it is recording, so that the
+    /// derivatives of the proxy come from a sweep like those of the pricer.
+    pub fn value(&self, x: &[Number]) -> Number {
+        let z: Vec<Number> = x
+            .iter()
+            .zip(self.mean.iter().zip(&self.scale))
+            .map(|(x, (mean, scale))| (*x - *mean) / *scale)
+            .collect();
+        self.exponents.iter().zip(&self.coefficients).fold(
+            Number::from(0.0),
+            |sum, (exponents, coefficient)| {
+                let monomial = z
+                    .iter()
+                    .zip(exponents)
+                    .filter(|(_, exponent)| **exponent > 0)
+                    .fold(Number::from(*coefficient), |product, (z, exponent)| {
+                        product * z.powi(*exponent as i32)
+                    });
+                sum + monomial
+            },
+        )
+    }
+}
+
+/// Least-squares fit of a [`PolynomialProxy`] of total degree `degree` to the
+/// values and derivatives of samples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DifferentialRegression {
+    pub degree: u32,
+    /// Weight of the squared errors of the derivatives in the loss, zero for
+    /// a fit to the values alone.
+    pub derivative_weight: f64,
+}
+
+impl DifferentialRegression {
+    pub fn new(degree: u32, derivative_weight: f64) -> Self {
+        DifferentialRegression {
+            degree,
+            derivative_weight,
+        }
+    }
+
+    /// Solves the normal equations of the loss for the coefficients.
+    pub fn fit(&self, samples: &[Sample]) -> PolynomialProxy {
+        assert!(!samples.is_empty(), "a fit needs samples");
+        let dimension = samples[0].x.len();
+        let count = samples.len() as f64;
+        let mean: Vec<f64> = (0..dimension)
+            .map(|i| samples.iter().map(|s| s.x[i]).sum::<f64>() / count)
+            .collect();
+        let scale: Vec<f64> = (0..dimension)
+            .map(|i| {
+                let variance = samples
+                    .iter()
+                    .map(|s| (s.x[i] - mean[i]).powi(2))
+                    .sum::<f64>()
+                    / count;
+                if variance > 0.0 { variance.sqrt() } else { 1.0 }
+            })
+            .collect();
+
+        let exponents = monomials(dimension, self.degree);
+        let size = exponents.len();
+        let mut normal_matrix = vec![vec![0.0; size]; size];
+        let mut rhs = vec![0.0; size];
+        let mut accumulate = |features: &[f64], target: f64, weight: f64| {
+            for (j, feature_j) in features.iter().enumerate() {
+                rhs[j] += weight * feature_j * target;
+                for (k, feature_k) in features.iter().enumerate() {
+                    normal_matrix[j][k] += weight * feature_j * feature_k;
+                }
+            }
+        };
+
+        for sample in samples {
+            let z: Vec<f64> = (0..dimension)
+                .map(|i| (sample.x[i] - mean[i]) / scale[i])
+                .collect();
+            let features: Vec<f64> = exponents.iter().map(|e| monomial(&z, e)).collect();
+            accumulate(&features, sample.y, 1.0);
+            if self.derivative_weight > 0.0 {
+                for (i, (dydx, scale)) in sample.dydx.iter().zip(&scale).enumerate() {
+                    let gradients: Vec<f64> = exponents
+                        .iter()
+                        .map(|e| monomial_derivative(&z, e, i))
+                        .collect();
+                    // dy/dz_i = dy/dx_i dx_i/dz_i
+                    let target = dydx * scale;
+                    accumulate(&gradients, target, self.derivative_weight);
+                }
+            }
+        }
+
+        PolynomialProxy {
+            coefficients: linear_algebra::solve(normal_matrix, rhs),
+            mean,
+            scale,
+            exponents,
+        }
+    }
+}
+
+/// Exponents of the monomials of `dimension` variables up to total degree
+/// `degree`, the constant first.
+fn monomials(dimension: usize, degree: u32) -> Vec<Vec<u32>> {
+    let mut exponents = vec![vec![0; dimension]];
+    for total in 1..=degree {
+        let mut current = vec![0; dimension];
+        push_monomials(&mut exponents, &mut current, 0, total);
+    }
+    exponents
+}
+
+/// Appends every way of spreading `remaining` over the variables from
+/// `variable` on.
+fn push_monomials(
+    exponents: &mut Vec<Vec<u32>>,
+    current: &mut Vec<u32>,
+    variable: usize,
+    remaining: u32,
+) {
+    if variable == current.len() - 1 {
+        current[variable] = remaining;
+        exponents.push(current.clone());
+        return;
+    }
+    for power in (0..=remaining).rev() {
+        current[variable] = power;
+        push_monomials(exponents, current, variable + 1, remaining - power);
+    }
+    current[variable] = 0;
+}
+
+fn monomial(z: &[f64], exponents: &[u32]) -> f64 {
+    z.iter()
+        .zip(exponents)
+        .map(|(z, exponent)| z.powi(*exponent as i32))
+        .product()
+}
+
+/// Derivative of the monomial with respect to `z[variable]`.
+fn monomial_derivative(z: &[f64], exponents: &[u32], variable: usize) -> f64 {
+    if exponents[variable] == 0 {
+        return 0.0;
+    }
+    let mut lowered = exponents.to_vec();
+    lowered[variable] -= 1;
+    exponents[variable] as f64 * monomial(z, &lowered)
+}
@@ -0,0 +1,426 @@
+//! Log-densities of common distributions on [`Number`] data and parameters.
+//!
+//! Every distribution implements [`LogDensity`]. `log_pdf` records a single
+//! node for one observation, `log_likelihood` records a single node for a whole
+//! data set, so the tape stays a handful of nodes long however many
+//! observations there are. The partial derivatives are evaluated analytically
+//! during the reverse sweep.
+//!
+//! Like any other `Number` arithmetic, distributions should be built inside the
+//! function passed to `AutomaticDifferentiator::derivatives`:
+//!
+//! ```ignore
+//! let evaluation = automatic_differentiator.derivatives(
+//!     |args: &[Number]| Normal::new(args[0], args[1]).log_likelihood(&data),
+//!     &[mu, sigma],
+//! );
+//! ```
+//!
+//! Outside the support the log-density is `-inf` and all partials are zero.
+
+use std::sync::Arc;
+
+use statrs::function::gamma;
+
+use crate::number::Number;
+use crate::operation::Operation;
+
+/// ln(sqrt(2 * PI))
+const LN_SQRT_2PI: f64 = 0.918_938_533_204_672_7;
+
+/// The distribution families known to the tape.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Family {
+    Normal,                   // mu, sigma
+    LogNormal,                // mu, sigma
+    Exponential,              // rate
+    Gamma,                    // shape, rate
+    Beta,                     // alpha, beta
+    StudentsT,                // location, scale, freedom
+    Poisson,                  // rate
+    Binomial { trials: u64 }, // probability
+}
+
+impl Family {
+    /// Log-density at `x` together with its partial derivatives. Index 0 of the
+    /// gradient holds the derivative with respect to `x`, index `i + 1` the one
+    /// with respect to `params[i]`.
+    pub(crate) fn log_density(&self, x: f64, params: &[f64]) -> (f64, [f64; 4]) {
+        const OUTSIDE_SUPPORT: (f64, [f64; 4]) = (f64::NEG_INFINITY, [0.0; 4]);
+
+        match *self {
+            Family::Normal => {
+                let (mu, sigma) = (params[0], params[1]);
+                let z = (x - mu) / sigma;
+                let value = -0.5 * z * z - sigma.ln() - LN_SQRT_2PI;
+                (value, [-z / sigma, z / sigma, (z * z - 1.0) / sigma, 0.0])
+            }
+            Family::LogNormal => {
+                if x <= 0.0 {
+                    return OUTSIDE_SUPPORT;
+                }
+                let (mu, sigma) = (params[0], params[1]);
+                let ln_x = x.ln();
+                let z = (ln_x - mu) / sigma;
+                let value = -0.5 * z * z - sigma.ln() - LN_SQRT_2PI - ln_x;
+                let dx = -(1.0 + z / sigma) / x;
+                (value, [dx, z / sigma, (z * z - 1.0) / sigma, 0.0])
+            }
+            Family::Exponential => {
+                if x < 0.0 {
+                    return OUTSIDE_SUPPORT;
+                }
+                let rate = params[0];
+                (rate.ln() - rate * x, [-rate, 1.0 / rate - x, 0.0, 0.0])
+            }
+            Family::Gamma => {
+                if x <= 0.0 {
+                    return OUTSIDE_SUPPORT;
+                }
+                let (shape, rate) = (params[0], params[1]);
+                let (ln_x, ln_rate) = (x.ln(), rate.ln());
+                let value =
+                    shape * ln_rate + (shape - 1.0) * ln_x - rate * x - gamma::ln_gamma(shape);
+                let dx = (shape - 1.0) / x - rate;
+                let dshape = ln_rate + ln_x - gamma::digamma(shape);
+                (value, [dx, dshape, shape / rate - x, 0.0])
+            }
+            Family::Beta => {
+                if x <= 0.0 || x >= 1.0 {
+                    return OUTSIDE_SUPPORT;
+                }
+                let (a, b) = (params[0], params[1]);
+                let (ln_x, ln_1mx) = (x.ln(), (-x).ln_1p());
+                let value =
+                    (a - 1.0) * ln_x + (b - 1.0) * ln_1mx - gamma::ln_gamma(a) - gamma::ln_gamma(b)
+                        + gamma::ln_gamma(a + b);
+                let dx = (a - 1.0) / x - (b - 1.0) / (1.0 - x);
+                let digamma_ab = gamma::digamma(a + b);
+                let da = ln_x - gamma::digamma(a) + digamma_ab;
+                let db = ln_1mx - gamma::digamma(b) + digamma_ab;
+                (value, [dx, da, db, 0.0])
+            }
+            Family::StudentsT => {
+                let (location, scale, nu) = (params[0], params[1], params[2]);
+                let z = (x - location) / scale;
+                let z2 = z * z;
+                let log_kernel = (z2 / nu).ln_1p();
+                let value = gamma::ln_gamma(0.5 * (nu + 1.0))
+                    - gamma::ln_gamma(0.5 * nu)
+                    - 0.5 * (nu * std::f64::consts::PI).ln()
+                    - scale.ln()
+                    - 0.5 * (nu + 1.0) * log_kernel;
+                let dx = -(nu + 1.0) * z / (scale * (nu + z2));
+                let dscale = ((nu + 1.0) * z2 / (nu + z2) - 1.0) / scale;
+                let dnu = 0.5 * (gamma::digamma(0.5 * (nu + 1.0)) - gamma::digamma(0.5 * nu))
+                    - 0.5 / nu
+                    - 0.5 * log_kernel
+                    + 0.5 * (nu + 1.0) * z2 / (nu * (nu + z2));
+                (value, [dx, -dx, dscale, dnu])
+            }
+            Family::Poisson => {
+                if x < 0.0 || x.fract() != 0.0 {
+                    return OUTSIDE_SUPPORT;
+                }
+                let rate = params[0];
+                // k * ln(rate) is taken as 0 for k = 0, also when rate = 0
+                let (k_ln_rate, drate) = if x == 0.0 {
+                    (0.0, -1.0)
+                } else {
+                    (x * rate.ln(), x / rate - 1.0)
+                };
+                let value = k_ln_rate - rate - gamma::ln_gamma(x + 1.0);
+                (value, [0.0, drate, 0.0, 0.0])
+            }
+            Family::Binomial { trials } => {
+                let n = trials as f64;
+                if x < 0.0 || x > n || x.fract() != 0.0 {
+                    return OUTSIDE_SUPPORT;
+                }
+                let p = params[0];
+                let ln_choose = gamma::ln_gamma(n + 1.0)
+                    - gamma::ln_gamma(x + 1.0)
+                    - gamma::ln_gamma(n - x + 1.0);
+                // the terms of k = 0 successes or failures vanish, also at p = 0 or p = 1
+                let (successes, dsuccesses) = if x == 0.0 {
+                    (0.0, 0.0)
+                } else {
+                    (x * p.ln(), x / p)
+                };
+                let (failures, dfailures) = if x == n {
+                    (0.0, 0.0)
+                } else {
+                    ((n - x) * (-p).ln_1p(), -(n - x) / (1.0 - p))
+                };
+                let value = ln_choose + successes + failures;
+                (value, [0.0, dsuccesses + dfailures, 0.0, 0.0])
+            }
+        }
+    }
+
+    /// Sum of the log-densities over `data` together with the summed partial
+    /// derivatives with respect to the parameters, in the same layout as
+    /// [`Family::log_density`].
+    pub(crate) fn log_likelihood(&self, data: &[f64], params: &[f64]) -> (f64, [f64; 4]) {
+        data.iter()
+            .fold((0.0, [0.0; 4]), |(value, mut gradient), x| {
+                let (log_density, partials) = self.log_density(*x, params);
+                for (total, partial) in gradient.iter_mut().zip(partials) {
+                    *total += partial;
+                }
+                (value + log_density, gradient)
+            })
+    }
+}
+
+/// A distribution with `Number` parameters.
+pub trait LogDensity {
+    fn family(&self) -> Family;
+
+    fn parameters(&self) -> Vec<Number>;
+
+    /// Log-density (log-mass for discrete distributions) at `x`, recorded as a
+    /// single node depending on `x` and the parameters.
+    fn log_pdf(&self, x: Number) -> Number {
+        let family = self.family();
+        let parameters = self.parameters();
+        let values: Vec<f64> = parameters.iter().map(|p| p.result).collect();
+        let (value, _) = family.log_density(x.result, &values);
+
+        let mut args = vec![x];
+        args.extend(parameters);
+        let arg_ids = args.iter().map(|arg| arg.id).collect();
+        Number::record(value, &args, |id, res| {
+            Operation::LogDensity(id, family, arg_ids, res, 0.0)
+        })
+    }
+
+    /// Log-likelihood of the observations in `data`, recorded as a single node
+    /// depending on the parameters only. Its adjoint costs one pass over `data`
+    /// per parameter.
+    fn log_likelihood(&self, data: &[f64]) -> Number {
+        let family = self.family();
+        let parameters = self.parameters();
+        let values: Vec<f64> = parameters.iter().map(|p| p.result).collect();
+        let (value, _) = family.log_likelihood(data, &values);
+
+        let data: Arc<[f64]> = data.into();
+        let param_ids = parameters.iter().map(|p| p.id).collect();
+        Number::record(value, &parameters, |id, res| {
+            Operation::LogLikelihood(id, family, data, param_ids, res, 0.0)
+        })
+    }
+}
+
+/// Normal distribution with mean `mu` and standard deviation `sigma`.
+#[derive(Debug, Clone, Copy)]
+pub struct Normal {
+    pub mu: Number,
+    pub sigma: Number,
+}
+
+impl Normal {
+    pub fn new<M: Into<Number>, S: Into<Number>>(mu: M, sigma: S) -> Self {
+        Normal {
+            mu: mu.into(),
+            sigma: sigma.into(),
+        }
+    }
+}
+
+impl LogDensity for Normal {
+    fn family(&self) -> Family {
+        Family::Normal
+    }
+
+    fn parameters(&self) -> Vec<Number> {
+        vec![self.mu, self.sigma]
+    }
+}
+
+/// Log-normal distribution, `ln(x)` is normal with mean `mu` and standard
+/// deviation `sigma`.
+#[derive(Debug, Clone, Copy)]
+pub struct LogNormal {
+    pub mu: Number,
+    pub sigma: Number,
+}
+
+impl LogNormal {
+    pub fn new<M: Into<Number>, S: Into<Number>>(mu: M, sigma: S) -> Self {
+        LogNormal {
+            mu: mu.into(),
+            sigma: sigma.into(),
+        }
+    }
+}
+
+impl LogDensity for LogNormal {
+    fn family(&self) -> Family {
+        Family::LogNormal
+    }
+
+    fn parameters(&self) -> Vec<Number> {
+        vec![self.mu, self.sigma]
+    }
+}
+
+/// Exponential distribution with density `rate * e^(-rate * x)`.
+#[derive(Debug, Clone, Copy)]
+pub struct Exponential {
+    pub rate: Number,
+}
+
+impl Exponential {
+    pub fn new<R: Into<Number>>(rate: R) -> Self {
+        Exponential { rate: rate.into() }
+    }
+}
+
+impl LogDensity for Exponential {
+    fn family(&self) -> Family {
+        Family::Exponential
+    }
+
+    fn parameters(&self) -> Vec<Number> {
+        vec![self.rate]
+    }
+}
+
+/// Gamma distribution in the shape/rate parametrisation.
+#[derive(Debug, Clone, Copy)]
+pub struct Gamma {
+    pub shape: Number,
+    pub rate: Number,
+}
+
+impl Gamma {
+    pub fn new<A: Into<Number>, B: Into<Number>>(shape: A, rate: B) -> Self {
+        Gamma {
+            shape: shape.into(),
+            rate: rate.into(),
+        }
+    }
+}
+
+impl LogDensity for Gamma {
+    fn family(&self) -> Family {
+        Family::Gamma
+    }
+
+    fn parameters(&self) -> Vec<Number> {
+        vec![self.shape, self.rate]
+    }
+}
+
+/// Beta distribution on the open unit interval.
+#[derive(Debug, Clone, Copy)]
+pub struct Beta {
+    pub alpha: Number,
+    pub beta: Number,
+}
+
+impl Beta {
+    pub fn new<A: Into<Number>, B: Into<Number>>(alpha: A, beta: B) -> Self {
+        Beta {
+            alpha: alpha.into(),
+            beta: beta.into(),
+        }
+    }
+}
+
+impl LogDensity for Beta {
+    fn family(&self) -> Family {
+        Family::Beta
+    }
+
+    fn parameters(&self) -> Vec<Number> {
+        vec![self.alpha, self.beta]
+    }
+}
+
+/// Location-scale Student's t distribution with `freedom` degrees of freedom.
+/// The degrees of freedom are differentiable as well.
+#[derive(Debug, Clone, Copy)]
+pub struct StudentsT {
+    pub location: Number,
+    pub scale: Number,
+    pub freedom: Number,
+}
+
+impl StudentsT {
+    pub fn new<L: Into<Number>, S: Into<Number>, F: Into<Number>>(
+        location: L,
+        scale: S,
+        freedom: F,
+    ) -> Self {
+        StudentsT {
+            location: location.into(),
+            scale: scale.into(),
+            freedom: freedom.into(),
+        }
+    }
+}
+
+impl LogDensity for StudentsT {
+    fn family(&self) -> Family {
+        Family::StudentsT
+    }
+
+    fn parameters(&self) -> Vec<Number> {
+        vec![self.location, self.scale, self.freedom]
+    }
+}
+
+/// Poisson distribution. Observations are counts, so the derivative with
+/// respect to the observation is zero.
+#[derive(Debug, Clone, Copy)]
+pub struct Poisson {
+    pub rate: Number,
+}
+
+impl Poisson {
+    pub fn new<R: Into<Number>>(rate: R) -> Self {
+        Poisson { rate: rate.into() }
+    }
+}
+
+impl LogDensity for Poisson {
+    fn family(&self) -> Family {
+        Family::Poisson
+    }
+
+    fn parameters(&self) -> Vec<Number> {
+        vec![self.rate]
+    }
+}
+
+/// Binomial distribution of the number of successes in `trials` attempts.
+/// Observations are counts, so the derivative with respect to the observation
+/// is zero.
+#[derive(Debug, Clone, Copy)]
+pub struct Binomial {
+    pub trials: u64,
+    pub probability: Number,
+}
+
+impl Binomial {
+    pub fn new<P: Into<Number>>(trials: u64, probability: P) -> Self {
+        Binomial {
+            trials,
+            probability: probability.into(),
+        }
+    }
+}
+
+impl LogDensity for Binomial {
+    fn family(&self) -> Family {
+        Family::Binomial {
+            trials: self.trials,
+        }
+    }
+
+    fn parameters(&self) -> Vec<Number> {
+        vec![self.probability]
+    }
+}
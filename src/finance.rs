@@ -0,0 +1,13 @@
+//! Pricing models for quantitative finance written on `Number`, so that the
+//! adjoint sweep delivers their sensitivities.
+
+pub mod basket;
+pub mod black_scholes;
+pub mod curve;
+pub mod cva;
+pub mod heston;
+pub mod longstaff_schwartz;
+pub mod monte_carlo;
+pub mod pde;
+pub mod portfolio;
+pub mod sabr;
@@ -0,0 +1,270 @@
+//! Basket and worst-of options on correlated geometric Brownian motions.
+//!
+//! Each asset follows `dS_i = (r - q_i) S_i dt + sigma_i S_i dW_i` with
+//! `d<W_i, W_j> = rho_ij dt`. The correlated increments are the independent
+//! normals of the path multiplied by the Cholesky factor of the correlation
+//! matrix, which is factorized on the tape from the correlation inputs: the
+//! adjoint of the factorization carries the sensitivities to the
+//! correlations, as it does to the volatilities and spots through the rest
+//! of the path.
+
+use std::cmp::Ordering;
+
+use crate::automatic_differentiator::AutomaticDifferentiator;
+use crate::finance::black_scholes::OptionType;
+use crate::finance::monte_carlo;
+use crate::linear_algebra;
+use crate::number::Number;
+use crate::random::{NormalMethod, Rng};
+
+/// Market of `n` correlated assets. `correlation` is the full symmetric
+/// matrix with ones on the diagonal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasketMarket {
+    pub spots: Vec<f64>,
+    pub volatilities: Vec<f64>,
+    pub dividend_yields: Vec<f64>,
+    pub rate: f64,
+    pub correlation: Vec<Vec<f64>>,
+}
+
+impl BasketMarket {
+    pub fn assets(&self) -> usize {
+        self.spots.len()
+    }
+
+    /// Spots, volatilities, dividend yields, rate, then the correlations below
+    /// the diagonal row by row: rho_10, rho_20, rho_21, ...
+    pub fn inputs(&self) -> Vec<f64> {
+        let mut inputs = [
+            self.spots.as_slice(),
+            &self.volatilities,
+            &self.dividend_yields,
+        ]
+        .concat();
+        inputs.push(self.rate);
+        for (i, row) in self.correlation.iter().enumerate() {
+            inputs.extend_from_slice(&row[..i]);
+        }
+        inputs
+    }
+
+    /// The same number of assets with the inputs replaced by `inputs`, laid
+    /// out as [`BasketMarket::inputs`].
+    pub fn with_inputs(&self, inputs: &[f64]) -> Self {
+        let n = self.assets();
+        assert_eq!(
+            inputs.len(),
+            3 * n + 1 + n * n.saturating_sub(1) / 2,
+            "a basket market needs three inputs per asset, a rate and a correlation per pair"
+        );
+        BasketMarket {
+            spots: inputs[..n].to_vec(),
+            volatilities: inputs[n..2 * n].to_vec(),
+            dividend_yields: inputs[2 * n..3 * n].to_vec(),
+            rate: inputs[3 * n],
+            correlation: symmetric(&inputs[3 * n + 1..], 1.0, n),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BasketPayoff {
+    /// Weighted sum of the assets at maturity.
+    Basket(Vec<f64>),
+    /// Worst performance S_i(T) / S_i(0) of the assets.
+    WorstOf,
+}
+
+/// European option on the underlying given by `payoff`, which for a worst-of
+/// option is quoted in performance, so that the strike is a fraction of the
+/// initial spots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasketOption {
+    pub option_type: OptionType,
+    pub strike: f64,
+    pub maturity: f64,
+    pub payoff: BasketPayoff,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasketSensitivities {
+    pub spots: Vec<f64>,
+    pub volatilities: Vec<f64>,
+    pub dividend_yields: Vec<f64>,
+    pub rate: f64,
+    /// Derivative with respect to rho_ij = rho_ji, moved together, in a
+    /// symmetric matrix with zeros on the diagonal.
+    pub correlation: Vec<Vec<f64>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasketResult {
+    pub price: f64,
+    /// Standard error of `price`, NaN with fewer than two paths.
+    pub standard_error: f64,
+    pub sensitivities: BasketSensitivities,
+}
+
+/// Monte Carlo pricer of [`BasketOption`]s, one exact step to maturity per
+/// path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasketMonteCarlo {
+    pub paths: usize,
+    pub seed: u64,
+    pub normal_method: NormalMethod,
+}
+
+impl BasketMonteCarlo {
+    pub fn new(paths: usize, seed: u64) -> Self {
+        BasketMonteCarlo {
+            paths,
+            seed,
+            normal_method: NormalMethod::BoxMuller,
+        }
+    }
+
+    /// Price without the tape, on the paths of [`BasketMonteCarlo::run`].
+    pub fn value(&self, market: &BasketMarket, option: &BasketOption) -> f64 {
+        Self::check(market, option);
+        crate::no_tape(|| {
+            let inputs: Vec<Number> = market.inputs().iter().map(|x| Number::new(*x)).collect();
+            let mut rng = Rng::new(self.seed);
+            let sum: f64 = (0..self.paths)
+                .map(|_| {
+                    let normals = rng.normals(market.assets(), self.normal_method);
+                    discounted_payoff(option, market.assets(), &inputs, &normals).result
+                })
+                .sum();
+            sum / self.paths as f64
+        })
+    }
+
+    /// Price and sensitivities to every input of `market`, each path recorded
+    /// and swept on its own.
+    pub fn run(&self, market: &BasketMarket, option: &BasketOption) -> BasketResult {
+        Self::check(market, option);
+        let n = market.assets();
+        let mut rng = Rng::new(self.seed);
+        let mut automatic_differentiator = AutomaticDifferentiator::new();
+        let arguments: Vec<Number> = market.inputs().iter().map(|x| Number::new(*x)).collect();
+
+        let mut sum = 0.0;
+        let mut sum_of_squares = 0.0;
+        let mut sensitivities = vec![0.0; arguments.len()];
+
+        for _ in 0..self.paths {
+            let normals = rng.normals(n, self.normal_method);
+            let evaluation = automatic_differentiator.derivatives(
+                |args: &[Number]| discounted_payoff(option, n, args, &normals),
+                &arguments,
+            );
+
+            sum += evaluation.result;
+            sum_of_squares += evaluation.result * evaluation.result;
+            for (sensitivity, argument) in sensitivities.iter_mut().zip(&arguments) {
+                *sensitivity += evaluation.derivative(*argument);
+            }
+        }
+
+        let (price, standard_error) =
+            monte_carlo::mean_and_standard_error(sum, sum_of_squares, self.paths);
+        let paths = self.paths as f64;
+        let sensitivities: Vec<f64> = sensitivities.iter().map(|s| s / paths).collect();
+
+        let correlation = symmetric(&sensitivities[3 * n + 1..], 0.0, n);
+
+        BasketResult {
+            price,
+            standard_error,
+            sensitivities: BasketSensitivities {
+                spots: sensitivities[..n].to_vec(),
+                volatilities: sensitivities[n..2 * n].to_vec(),
+                dividend_yields: sensitivities[2 * n..3 * n].to_vec(),
+                rate: sensitivities[3 * n],
+                correlation,
+            },
+        }
+    }
+
+    fn check(market: &BasketMarket, option: &BasketOption) {
+        let n = market.assets();
+        assert!(
+            market.volatilities.len() == n
+                && market.dividend_yields.len() == n
+                && market.correlation.len() == n
+                && market.correlation.iter().all(|row| row.len() == n),
+            "a basket market needs a volatility, a dividend yield and a row of correlations per asset"
+        );
+        if let BasketPayoff::Basket(weights) = &option.payoff {
+            assert_eq!(weights.len(), n, "a basket needs one weight per asset");
+        }
+    }
+}
+
+/// Discounted payoff of `option` on the path driven by the independent
+/// `normals`, from the inputs laid out as [`BasketMarket::inputs`].
+fn discounted_payoff(
+    option: &BasketOption,
+    assets: usize,
+    inputs: &[Number],
+    normals: &[f64],
+) -> Number {
+    let spots = &inputs[..assets];
+    let volatilities = &inputs[assets..2 * assets];
+    let dividend_yields = &inputs[2 * assets..3 * assets];
+    let rate = inputs[3 * assets];
+
+    let correlation = symmetric(&inputs[3 * assets + 1..], Number::from(1.0), assets);
+    let factor = linear_algebra::cholesky(&correlation);
+
+    let t = option.maturity;
+    let performances: Vec<Number> = (0..assets)
+        .map(|i| {
+            let z = factor[i][..=i]
+                .iter()
+                .zip(normals)
+                .fold(Number::from(0.0), |sum, (l, z)| sum + *l * *z);
+            let volatility = volatilities[i];
+            let log_drift = rate - dividend_yields[i] - 0.5 * volatility * volatility;
+            (log_drift * t + volatility * t.sqrt() * z).exp()
+        })
+        .collect();
+
+    let underlying = match &option.payoff {
+        BasketPayoff::Basket(weights) => performances
+            .iter()
+            .zip(spots)
+            .zip(weights)
+            .fold(Number::from(0.0), |sum, ((performance, spot), weight)| {
+                sum + *weight * *spot * *performance
+            }),
+        BasketPayoff::WorstOf => performances[1..]
+            .iter()
+            .fold(performances[0], |worst, performance| {
+                worst.min(*performance)
+            }),
+    };
+    let intrinsic = match option.option_type {
+        OptionType::Call => (underlying - option.strike).max(0.0),
+        OptionType::Put => (option.strike - underlying).max(0.0),
+    };
+    (-1.0 * rate * t).exp() * intrinsic
+}
+
+/// Symmetric n x n matrix with `diagonal` on the diagonal and the entries
+/// below it, row by row, in `lower`.
+fn symmetric<T: Copy>(lower: &[T], diagonal: T, n: usize) -> Vec<Vec<T>> {
+    let entry = |i: usize, j: usize| lower[i * (i - 1) / 2 + j];
+    (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| match j.cmp(&i) {
+                    Ordering::Less => entry(i, j),
+                    Ordering::Equal => diagonal,
+                    Ordering::Greater => entry(j, i),
+                })
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,321 @@
+//! Single-curve discount curve bootstrapped from deposits, FRAs and par swaps.
+//!
+//! Discount factors are interpolated log-linearly between pillars, i.e. with
+//! piecewise constant continuously compounded forward rates, and extrapolated
+//! with the forward rate of the last segment. Year fractions are plain times
+//! in years: day counts and calendars are left to the caller.
+//!
+//! Every pillar solves `par_rate(instrument) = quote` for its discount
+//! factor. The root is found without the tape, then a single Newton step from
+//! it is recorded, `df = df* - (par_rate(df*) - quote) / slope`, with the
+//! exact slope of the par rate in the discount factor. At the root that step
+//! has the value `df*` and the derivatives of the implicit function theorem,
+//! so the bootstrapped curve carries the sensitivities of every discount
+//! factor to every quote and [`bucketed_delta`] needs one sweep.
+
+use crate::automatic_differentiator::AutomaticDifferentiator;
+use crate::number::Number;
+
+/// Newton iterations of the untaped root search of a pillar.
+const MAX_ITERATIONS: usize = 50;
+
+/// A quoted instrument. Times are in years from today, `rate` is the quote.
+#[derive(Debug, Clone, Copy)]
+pub enum Instrument {
+    /// Simply compounded deposit rate from today to `maturity`.
+    Deposit { maturity: f64, rate: Number },
+    /// Simply compounded forward rate from `start` to `end`.
+    Fra { start: f64, end: f64, rate: Number },
+    /// Par rate of a swap paying fixed every `fixed_period` against the
+    /// floating leg, which is worth 1 - df(maturity) on a single curve.
+    Swap {
+        maturity: f64,
+        fixed_period: f64,
+        rate: Number,
+    },
+}
+
+impl Instrument {
+    /// Time of the last cash flow, the pillar the instrument bootstraps.
+    pub fn maturity(&self) -> f64 {
+        match *self {
+            Instrument::Deposit { maturity, .. } => maturity,
+            Instrument::Fra { end, .. } => end,
+            Instrument::Swap { maturity, .. } => maturity,
+        }
+    }
+
+    pub fn quote(&self) -> Number {
+        match *self {
+            Instrument::Deposit { rate, .. } => rate,
+            Instrument::Fra { rate, .. } => rate,
+            Instrument::Swap { rate, .. } => rate,
+        }
+    }
+
+    /// The same instrument quoted at `rate`.
+    pub fn with_quote(self, rate: Number) -> Self {
+        match self {
+            Instrument::Deposit { maturity, .. } => Instrument::Deposit { maturity, rate },
+            Instrument::Fra { start, end, .. } => Instrument::Fra { start, end, rate },
+            Instrument::Swap {
+                maturity,
+                fixed_period,
+                ..
+            } => Instrument::Swap {
+                maturity,
+                fixed_period,
+                rate,
+            },
+        }
+    }
+}
+
+/// Discount factors at increasing pillar times, with df(0) = 1.
+#[derive(Debug, Clone)]
+pub struct DiscountCurve {
+    pub times: Vec<f64>,
+    pub discount_factors: Vec<Number>,
+}
+
+impl DiscountCurve {
+    /// A curve through the given pillars. `times` must be positive and
+    /// strictly increasing.
+    pub fn new(times: Vec<f64>, discount_factors: Vec<Number>) -> Self {
+        assert_eq!(
+            times.len(),
+            discount_factors.len(),
+            "one discount factor per pillar"
+        );
+        assert!(
+            times.first().is_none_or(|t| *t > 0.0) && times.windows(2).all(|w| w[0] < w[1]),
+            "pillar times must be positive and strictly increasing"
+        );
+        DiscountCurve {
+            times,
+            discount_factors,
+        }
+    }
+
+    /// Bootstraps the pillars at the maturities of `instruments`, which must
+    /// be sorted by strictly increasing maturity.
+    pub fn bootstrap(instruments: &[Instrument]) -> Self {
+        let mut curve = DiscountCurve::new(vec![], vec![]);
+        for instrument in instruments {
+            let maturity = instrument.maturity();
+            assert!(
+                curve.times.last().is_none_or(|t| *t < maturity),
+                "instruments must be sorted by strictly increasing maturity"
+            );
+            curve.times.push(maturity);
+            // placeholder for the pillar, set by the solve below
+            curve
+                .discount_factors
+                .push(crate::no_tape(|| Number::new(1.0)));
+
+            let mut root = (-instrument.quote().result * maturity).exp();
+            for _ in 0..MAX_ITERATIONS {
+                let step = curve.last_pillar_residual(instrument, root)
+                    / curve.last_pillar_slope(instrument, root);
+                root -= step;
+                if step.abs() <= 1e-15 * root {
+                    break;
+                }
+            }
+            let slope = curve.last_pillar_slope(instrument, root);
+
+            *curve.discount_factors.last_mut().unwrap() = Number::from(root);
+            let correction = (curve.par_rate(instrument) - instrument.quote()) / slope;
+            *curve.discount_factors.last_mut().unwrap() = Number::from(root) - correction;
+        }
+        curve
+    }
+
+    /// Untaped `par_rate(instrument) - quote` with the last discount factor
+    /// set to `discount_factor`.
+    fn last_pillar_residual(&mut self, instrument: &Instrument, discount_factor: f64) -> f64 {
+        crate::no_tape(|| {
+            *self.discount_factors.last_mut().unwrap() = Number::new(discount_factor);
+            (self.par_rate(instrument) - instrument.quote()).result
+        })
+    }
+
+    /// Derivative of [`Self::last_pillar_residual`] with respect to the last
+    /// discount factor df, in closed form: df moves the discount factor at t
+    /// by dD(t) = D(t) w(t) ddf / df, where w(t) is the weight of the last
+    /// pillar in the log-linear interpolation at t.
+    fn last_pillar_slope(&mut self, instrument: &Instrument, discount_factor: f64) -> f64 {
+        crate::no_tape(|| {
+            *self.discount_factors.last_mut().unwrap() = Number::new(discount_factor);
+            let discount = |t: f64| self.discount(t).result;
+            let discount_slope =
+                |t: f64| discount(t) * self.last_pillar_weight(t) / discount_factor;
+            match *instrument {
+                Instrument::Deposit { maturity, .. } => {
+                    // (1 / D(T) - 1) / T
+                    -discount_slope(maturity) / (discount(maturity).powi(2) * maturity)
+                }
+                Instrument::Fra { start, end, .. } => {
+                    // (D(s) / D(e) - 1) / (e - s)
+                    let (start_discount, end_discount) = (discount(start), discount(end));
+                    (discount_slope(start) * end_discount - start_discount * discount_slope(end))
+                        / (end_discount * end_discount * (end - start))
+                }
+                Instrument::Swap {
+                    maturity,
+                    fixed_period,
+                    ..
+                } => {
+                    // (1 - D(T)) / A
+                    let (times, accrual) = fixed_leg(maturity, fixed_period);
+                    let annuity: f64 = times.iter().map(|t| discount(*t) * accrual).sum();
+                    let annuity_slope: f64 =
+                        times.iter().map(|t| discount_slope(*t) * accrual).sum();
+                    (-discount_slope(maturity) * annuity
+                        - (1.0 - discount(maturity)) * annuity_slope)
+                        / (annuity * annuity)
+                }
+            }
+        })
+    }
+
+    /// Weight of the last discount factor in the logarithm of the discount
+    /// factor at time `t`, see [`DiscountCurve::discount`].
+    fn last_pillar_weight(&self, t: f64) -> f64 {
+        let n = self.times.len();
+        let t1 = self.times[n - 1];
+        if t <= 0.0 {
+            0.0
+        } else if n == 1 {
+            t / t1
+        } else if t <= self.times[n - 2] {
+            0.0
+        } else {
+            let t0 = self.times[n - 2];
+            (t - t0) / (t1 - t0)
+        }
+    }
+
+    /// Discount factor at time `t`, log-linear between pillars.
+    pub fn discount(&self, t: f64) -> Number {
+        if t <= 0.0 || self.times.is_empty() {
+            return Number::from(1.0);
+        }
+        // segment [t0, t1] containing t, the last one beyond the last pillar
+        let i = self
+            .times
+            .partition_point(|pillar| *pillar < t)
+            .min(self.times.len() - 1);
+        let t1 = self.times[i];
+        let log_df1 = self.discount_factors[i].ln();
+        if i == 0 {
+            return (log_df1 * (t / t1)).exp();
+        }
+        let t0 = self.times[i - 1];
+        let log_df0 = self.discount_factors[i - 1].ln();
+        let w = (t - t0) / (t1 - t0);
+        (log_df0 * (1.0 - w) + log_df1 * w).exp()
+    }
+
+    /// Continuously compounded zero rate to time `t`.
+    pub fn zero_rate(&self, t: f64) -> Number {
+        -1.0 * self.discount(t).ln() / t
+    }
+
+    /// Simply compounded forward rate from `start` to `end`.
+    pub fn forward_rate(&self, start: f64, end: f64) -> Number {
+        (self.discount(start) / self.discount(end) - 1.0) / (end - start)
+    }
+
+    /// Sum of the discounted accrual fractions of the fixed leg of a swap.
+    pub fn annuity(&self, maturity: f64, fixed_period: f64) -> Number {
+        let (times, accrual) = fixed_leg(maturity, fixed_period);
+        times.iter().fold(Number::from(0.0), |annuity, t| {
+            annuity + self.discount(*t) * accrual
+        })
+    }
+
+    /// The rate that would make `instrument` worth zero on this curve.
+    pub fn par_rate(&self, instrument: &Instrument) -> Number {
+        match *instrument {
+            Instrument::Deposit { maturity, .. } => self.forward_rate(0.0, maturity),
+            Instrument::Fra { start, end, .. } => self.forward_rate(start, end),
+            Instrument::Swap {
+                maturity,
+                fixed_period,
+                ..
+            } => (1.0 - self.discount(maturity)) / self.annuity(maturity, fixed_period),
+        }
+    }
+
+    /// Value per `notional` of receiving the quoted rate of `instrument`:
+    /// lending at the deposit rate, receiving the FRA rate or the fixed leg
+    /// of the swap. Zero for the instruments the curve was bootstrapped from.
+    pub fn present_value(&self, instrument: &Instrument, notional: f64) -> Number {
+        let rate = instrument.quote();
+        let value = match *instrument {
+            Instrument::Deposit { maturity, .. } => {
+                self.discount(maturity) * (1.0 + rate * maturity) - 1.0
+            }
+            Instrument::Fra { start, end, .. } => {
+                (rate - self.forward_rate(start, end)) * (end - start) * self.discount(end)
+            }
+            Instrument::Swap {
+                maturity,
+                fixed_period,
+                ..
+            } => rate * self.annuity(maturity, fixed_period) - (1.0 - self.discount(maturity)),
+        };
+        value * notional
+    }
+}
+
+/// Payment times of the fixed leg of a swap, in whole periods as close to
+/// `fixed_period` as fits in `maturity`, and their accrual fraction.
+fn fixed_leg(maturity: f64, fixed_period: f64) -> (Vec<f64>, f64) {
+    let periods = (maturity / fixed_period).round().max(1.0) as usize;
+    let accrual = maturity / periods as f64;
+    ((1..=periods).map(|i| i as f64 * accrual).collect(), accrual)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurveRisk {
+    pub value: f64,
+    /// Derivative of `value` with respect to the quote of each instrument,
+    /// in instrument order.
+    pub deltas: Vec<f64>,
+}
+
+/// Value and bucketed delta of `pricer` on the curve bootstrapped from
+/// `instruments`. Bootstrap and pricing are recorded together, so a single
+/// sweep gives the derivatives with respect to all quotes.
+pub fn bucketed_delta<P>(instruments: &[Instrument], pricer: P) -> CurveRisk
+where
+    P: Fn(&DiscountCurve) -> Number,
+{
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+    let arguments: Vec<Number> = instruments
+        .iter()
+        .map(|instrument| Number::new(instrument.quote().result))
+        .collect();
+
+    let evaluation = automatic_differentiator.derivatives(
+        |args: &[Number]| {
+            let quoted: Vec<Instrument> = instruments
+                .iter()
+                .zip(args)
+                .map(|(instrument, rate)| instrument.with_quote(*rate))
+                .collect();
+            pricer(&DiscountCurve::bootstrap(&quoted))
+        },
+        &arguments,
+    );
+
+    CurveRisk {
+        value: evaluation.result,
+        deltas: arguments
+            .iter()
+            .map(|argument| evaluation.derivative(*argument))
+            .collect(),
+    }
+}
@@ -0,0 +1,621 @@
+//! Credit valuation adjustment of a netting set of interest rate swaps.
+//!
+//! Rates follow the Hull-White model fitted to a bootstrapped discount curve,
+//! r(t) = x(t) + phi(t) with
+//!
+//! ```text
+//! dx = -a x dt + sigma dW,    x(0) = 0
+//! ```
+//!
+//! and phi(t) such that the model reprices the curve. The state x and its
+//! integral are simulated exactly on the exposure dates, which gives the
+//! discount factor of the path and the bonds that value the swaps on it.
+//! With a piecewise constant hazard rate and a recovery R,
+//!
+//! ```text
+//! CVA = (1 - R) sum_i E[D(t_i) max(V(t_i), 0)] (S(t_i-1) - S(t_i))
+//! ```
+//!
+//! over the exposure dates t_i, with V the value of the netting set, D the
+//! discount factor and S the survival probability.
+//!
+//! The sensitivities come from two kinds of recordings. Everything that does
+//! not depend on the path (curve bootstrap, bond and transition coefficients,
+//! default probabilities) is a function of the market inputs recorded once.
+//! Each path is recorded on its own with those coefficients as arguments,
+//! and the adjoints of the coefficients are averaged over the paths. A last
+//! sweep of the coefficients, seeded with the averaged adjoints, carries them
+//! to the market inputs.
+
+use std::cell::RefCell;
+
+use crate::automatic_differentiator::AutomaticDifferentiator;
+use crate::finance::curve::{DiscountCurve, Instrument};
+use crate::finance::monte_carlo;
+use crate::number::Number;
+use crate::random::{NormalMethod, Rng};
+
+/// Hull-White model on a discount curve: mean reversion `a` and volatility
+/// `sigma` of the short rate.
+#[derive(Debug, Clone, Copy)]
+pub struct HullWhite {
+    pub mean_reversion: Number,
+    pub volatility: Number,
+}
+
+impl HullWhite {
+    pub fn new<A: Into<Number>, S: Into<Number>>(mean_reversion: A, volatility: S) -> Self {
+        HullWhite {
+            mean_reversion: mean_reversion.into(),
+            volatility: volatility.into(),
+        }
+    }
+
+    /// B(tau) = (1 - exp(-a tau)) / a, the sensitivity of a bond with time
+    /// to maturity tau to the state x.
+    fn b(&self, tau: f64) -> Number {
+        let a = self.mean_reversion;
+        (1.0 - (-1.0 * a * tau).exp()) / a
+    }
+
+    /// Variance of the integral of x over tau years, starting from a known x.
+    fn integral_variance(&self, tau: f64) -> Number {
+        let (a, sigma) = (self.mean_reversion, self.volatility);
+        sigma * sigma / (a * a)
+            * (tau - 2.0 * self.b(tau) + (1.0 - (-2.0 * a * tau).exp()) / (2.0 * a))
+    }
+
+    /// Coefficients A and B of the price A exp(-B x) at `t` of the zero
+    /// coupon bond maturing at `maturity`, given the state x at `t`.
+    /// `discount(s)` is the discount factor P(0, s) of the curve the model is
+    /// fitted to.
+    pub fn bond_coefficients<D>(&self, discount: D, t: f64, maturity: f64) -> (Number, Number)
+    where
+        D: Fn(f64) -> Number,
+    {
+        let convexity = 0.5
+            * (self.integral_variance(maturity - t) - self.integral_variance(maturity)
+                + self.integral_variance(t));
+        (
+            discount(maturity) / discount(t) * convexity.exp(),
+            self.b(maturity - t),
+        )
+    }
+
+    /// Price at `t` of the zero coupon bond maturing at `maturity`, given the
+    /// state `x` at `t`, see [`HullWhite::bond_coefficients`].
+    pub fn bond<D>(&self, discount: D, t: f64, maturity: f64, x: Number) -> Number
+    where
+        D: Fn(f64) -> Number,
+    {
+        let (a, b) = self.bond_coefficients(discount, t, maturity);
+        a * (-1.0 * b * x).exp()
+    }
+
+    /// Coefficients of the exact transition of the state x and its integral
+    /// over `dt`, see [`Transition`].
+    fn transition(&self, dt: f64) -> Transition {
+        let (a, sigma) = (self.mean_reversion, self.volatility);
+        let decay = (-1.0 * a * dt).exp();
+        let b = self.b(dt);
+        let state_variance = sigma * sigma * (1.0 - decay * decay) / (2.0 * a);
+        let covariance = 0.5 * sigma * sigma * b * b;
+        let state_deviation = state_variance.sqrt();
+        Transition {
+            decay,
+            b,
+            state_deviation,
+            integral_loading: covariance / state_deviation,
+            integral_deviation: (self.integral_variance(dt)
+                - covariance * covariance / state_variance)
+                .sqrt(),
+        }
+    }
+}
+
+/// Default intensity constant between `times`: `hazard_rates[i]` applies up
+/// to `times[i]`, the last one beyond.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HazardCurve {
+    pub times: Vec<f64>,
+    pub hazard_rates: Vec<Number>,
+}
+
+impl HazardCurve {
+    pub fn new(times: Vec<f64>, hazard_rates: Vec<Number>) -> Self {
+        assert!(
+            !times.is_empty() && times.len() == hazard_rates.len(),
+            "a hazard curve needs one hazard rate per time"
+        );
+        assert!(
+            times[0] > 0.0 && times.windows(2).all(|t| t[0] < t[1]),
+            "hazard curve times must be positive and strictly increasing"
+        );
+        HazardCurve {
+            times,
+            hazard_rates,
+        }
+    }
+
+    /// Probability of no default before `t`.
+    pub fn survival(&self, t: f64) -> Number {
+        let mut start = 0.0;
+        let mut integral = Number::from(0.0);
+        for (i, (end, hazard_rate)) in self.times.iter().zip(&self.hazard_rates).enumerate() {
+            let end = if i == self.times.len() - 1 {
+                t
+            } else {
+                end.min(t)
+            };
+            if end > start {
+                integral = integral + *hazard_rate * (end - start);
+            }
+            start = end;
+            if start >= t {
+                break;
+            }
+        }
+        (-1.0 * integral).exp()
+    }
+}
+
+/// A spot starting swap exchanging a fixed rate against the floating rate,
+/// with coupon dates every `fixed_period` years until `maturity` like the
+/// swaps of [`Instrument::Swap`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Swap {
+    pub notional: f64,
+    pub fixed_rate: f64,
+    pub maturity: f64,
+    pub fixed_period: f64,
+    /// Pays the fixed rate if true, receives it otherwise.
+    pub payer: bool,
+}
+
+impl Swap {
+    /// Coupon dates of the swap.
+    pub fn coupon_dates(&self) -> Vec<f64> {
+        let periods = (self.maturity / self.fixed_period).round().max(1.0) as usize;
+        let accrual = self.maturity / periods as f64;
+        (1..=periods).map(|i| i as f64 * accrual).collect()
+    }
+
+    /// Value at `t` from the bond prices `bond(T)` = P(t, T) of that time.
+    /// The floating leg is valued as if the current period had reset at `t`,
+    /// which is exact on the coupon dates.
+    pub fn value<B>(&self, t: f64, bond: B) -> Number
+    where
+        B: Fn(f64) -> Number,
+    {
+        let dates = self.coupon_dates();
+        let accrual = dates[0];
+        let remaining: Vec<f64> = dates.into_iter().filter(|date| *date > t).collect();
+        let Some(last) = remaining.last() else {
+            return Number::from(0.0);
+        };
+        let annuity = remaining.iter().fold(Number::from(0.0), |annuity, date| {
+            annuity + bond(*date) * accrual
+        });
+        let receiver = self.fixed_rate * annuity - (1.0 - bond(*last));
+        if self.payer {
+            -self.notional * receiver
+        } else {
+            self.notional * receiver
+        }
+    }
+}
+
+/// Market and model inputs of the CVA, all of which get a sensitivity.
+#[derive(Debug, Clone)]
+pub struct CvaMarket {
+    /// Instruments of the discount curve, see [`DiscountCurve::bootstrap`].
+    pub instruments: Vec<Instrument>,
+    pub mean_reversion: f64,
+    pub volatility: f64,
+    pub hazard_times: Vec<f64>,
+    pub hazard_rates: Vec<f64>,
+    pub recovery: f64,
+}
+
+impl CvaMarket {
+    /// The inputs in the order of [`CvaSensitivities`]: curve quotes, mean
+    /// reversion, volatility, hazard rates and recovery.
+    pub fn inputs(&self) -> Vec<f64> {
+        self.instruments
+            .iter()
+            .map(|instrument| instrument.quote().result)
+            .chain([self.mean_reversion, self.volatility])
+            .chain(self.hazard_rates.iter().copied())
+            .chain([self.recovery])
+            .collect()
+    }
+
+    /// The same market with its inputs replaced by `inputs`, laid out as
+    /// [`CvaMarket::inputs`].
+    pub fn with_inputs(&self, inputs: &[f64]) -> Self {
+        let quotes = self.instruments.len();
+        let hazard_rates = self.hazard_rates.len();
+        assert_eq!(
+            inputs.len(),
+            quotes + hazard_rates + 3,
+            "a CVA market needs one input per quote and hazard rate, and three more"
+        );
+        CvaMarket {
+            instruments: self
+                .instruments
+                .iter()
+                .zip(inputs)
+                .map(|(instrument, rate)| instrument.with_quote(Number::new(*rate)))
+                .collect(),
+            mean_reversion: inputs[quotes],
+            volatility: inputs[quotes + 1],
+            hazard_times: self.hazard_times.clone(),
+            hazard_rates: inputs[quotes + 2..quotes + 2 + hazard_rates].to_vec(),
+            recovery: inputs[quotes + 2 + hazard_rates],
+        }
+    }
+}
+
+/// Exact transition over one step: with z1 and z2 independent standard
+/// normals,
+///
+/// ```text
+/// x' = decay x + state_deviation z1
+/// I' = I + b x + integral_loading z1 + integral_deviation z2
+/// ```
+struct Transition {
+    decay: Number,
+    b: Number,
+    state_deviation: Number,
+    integral_loading: Number,
+    integral_deviation: Number,
+}
+
+/// What a path needs on one exposure date, independent of the path.
+struct DateCoefficients {
+    transition: Transition,
+    /// P(0, t) exp(-Var(I(t)) / 2), the discount factor is this times exp(-I(t)).
+    discount: Number,
+    /// (1 - R)(S(t_i-1) - S(t_i)), the loss given default in the period.
+    default_loss: Number,
+    /// A and B of the bond prices A exp(-B x) by maturity.
+    bonds: Vec<(f64, Number, Number)>,
+}
+
+impl DateCoefficients {
+    /// Number of coefficients of a date with `bonds` bonds.
+    fn len(bonds: usize) -> usize {
+        7 + 2 * bonds
+    }
+
+    fn flatten(&self) -> Vec<Number> {
+        let step = &self.transition;
+        let mut flat = vec![
+            step.decay,
+            step.b,
+            step.state_deviation,
+            step.integral_loading,
+            step.integral_deviation,
+            self.discount,
+            self.default_loss,
+        ];
+        for (_, a, b) in &self.bonds {
+            flat.push(*a);
+            flat.push(*b);
+        }
+        flat
+    }
+
+    fn from_flat(flat: &[Number], maturities: &[f64]) -> Self {
+        DateCoefficients {
+            transition: Transition {
+                decay: flat[0],
+                b: flat[1],
+                state_deviation: flat[2],
+                integral_loading: flat[3],
+                integral_deviation: flat[4],
+            },
+            discount: flat[5],
+            default_loss: flat[6],
+            bonds: maturities
+                .iter()
+                .zip(flat[7..].chunks(2))
+                .map(|(maturity, ab)| (*maturity, ab[0], ab[1]))
+                .collect(),
+        }
+    }
+}
+
+/// Derivatives of the CVA with respect to each input of [`CvaMarket`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CvaSensitivities {
+    /// With respect to the quote of each curve instrument.
+    pub curve: Vec<f64>,
+    pub mean_reversion: f64,
+    pub volatility: f64,
+    pub hazard_rates: Vec<f64>,
+    pub recovery: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CvaResult {
+    pub cva: f64,
+    /// Standard error of `cva`, NaN with fewer than two paths.
+    pub standard_error: f64,
+    /// Discounted expected positive exposure E[D(t) max(V(t), 0)] on each
+    /// exposure date.
+    pub expected_positive_exposure: Vec<f64>,
+    pub sensitivities: CvaSensitivities,
+}
+
+/// Monte Carlo CVA engine simulating on `exposure_times`, starting from 0.
+/// Every path draws two standard normals per exposure date.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cva {
+    pub paths: usize,
+    /// Strictly increasing, positive exposure dates.
+    pub exposure_times: Vec<f64>,
+    pub seed: u64,
+    pub normal_method: NormalMethod,
+}
+
+impl Cva {
+    pub fn new(paths: usize, exposure_times: Vec<f64>, seed: u64) -> Self {
+        assert!(
+            !exposure_times.is_empty()
+                && exposure_times[0] > 0.0
+                && exposure_times.windows(2).all(|t| t[0] < t[1]),
+            "exposure times must be positive and strictly increasing"
+        );
+        Cva {
+            paths,
+            exposure_times,
+            seed,
+            normal_method: NormalMethod::BoxMuller,
+        }
+    }
+
+    /// CVA of `netting_set` without sensitivities, on the same paths as
+    /// [`Cva::run`].
+    pub fn value(&self, market: &CvaMarket, netting_set: &[Swap]) -> f64 {
+        crate::no_tape(|| {
+            let inputs: Vec<Number> = market.inputs().into_iter().map(Number::new).collect();
+            let coefficients = self.coefficients(market, netting_set, &inputs);
+            let mut rng = Rng::new(self.seed);
+            let mut exposures = Vec::new();
+            let sum: f64 = (0..self.paths)
+                .map(|_| {
+                    let normals = rng.normals(2 * self.exposure_times.len(), self.normal_method);
+                    exposures.clear();
+                    self.path_cva(&coefficients, netting_set, &normals, &mut exposures)
+                        .result
+                })
+                .sum();
+            sum / self.paths as f64
+        })
+    }
+
+    /// CVA of `netting_set` and its sensitivities to the inputs of `market`.
+    pub fn run(&self, market: &CvaMarket, netting_set: &[Swap]) -> CvaResult {
+        let inputs = market.inputs();
+        let maturities = self.bond_maturities(netting_set);
+        let unflatten = |flat: &[Number]| -> Vec<DateCoefficients> {
+            let mut start = 0;
+            maturities
+                .iter()
+                .map(|maturities| {
+                    let end = start + DateCoefficients::len(maturities.len());
+                    let date = DateCoefficients::from_flat(&flat[start..end], maturities);
+                    start = end;
+                    date
+                })
+                .collect()
+        };
+
+        // the path-independent coefficients, date after date
+        let coefficients: Vec<f64> = crate::no_tape(|| {
+            let inputs: Vec<Number> = inputs.iter().map(|x| Number::new(*x)).collect();
+            self.coefficients(market, netting_set, &inputs)
+                .iter()
+                .flat_map(DateCoefficients::flatten)
+                .map(|coefficient| coefficient.result)
+                .collect()
+        });
+
+        let mut rng = Rng::new(self.seed);
+        let mut automatic_differentiator = AutomaticDifferentiator::new();
+        let arguments: Vec<Number> = coefficients.iter().map(|x| Number::new(*x)).collect();
+        let dates = self.exposure_times.len();
+
+        let mut sum = 0.0;
+        let mut sum_of_squares = 0.0;
+        let mut exposures = vec![0.0; dates];
+        let mut coefficient_adjoints = vec![0.0; arguments.len()];
+        let path_exposures = RefCell::new(Vec::with_capacity(dates));
+
+        for _ in 0..self.paths {
+            let normals = rng.normals(2 * dates, self.normal_method);
+            let evaluation = automatic_differentiator.derivatives(
+                |args: &[Number]| {
+                    let mut path_exposures = path_exposures.borrow_mut();
+                    path_exposures.clear();
+                    self.path_cva(&unflatten(args), netting_set, &normals, &mut path_exposures)
+                },
+                &arguments,
+            );
+
+            sum += evaluation.result;
+            sum_of_squares += evaluation.result * evaluation.result;
+            for (exposure, path_exposure) in
+                exposures.iter_mut().zip(path_exposures.borrow().iter())
+            {
+                *exposure += path_exposure;
+            }
+            for (adjoint, argument) in coefficient_adjoints.iter_mut().zip(&arguments) {
+                *adjoint += evaluation.derivative(*argument);
+            }
+        }
+        let n = self.paths as f64;
+
+        // carry the averaged adjoints of the coefficients to the market inputs
+        let market_arguments: Vec<Number> = inputs.iter().map(|x| Number::new(*x)).collect();
+        let evaluation = automatic_differentiator.derivatives(
+            |args: &[Number]| {
+                self.coefficients(market, netting_set, args)
+                    .iter()
+                    .flat_map(DateCoefficients::flatten)
+                    .zip(&coefficient_adjoints)
+                    .fold(Number::from(0.0), |sum, (coefficient, adjoint)| {
+                        sum + coefficient * (adjoint / n)
+                    })
+            },
+            &market_arguments,
+        );
+        let sensitivities: Vec<f64> = market_arguments
+            .iter()
+            .map(|argument| evaluation.derivative(*argument))
+            .collect();
+
+        let (cva, standard_error) =
+            monte_carlo::mean_and_standard_error(sum, sum_of_squares, self.paths);
+        let quotes = market.instruments.len();
+        let hazard_rates = market.hazard_rates.len();
+        CvaResult {
+            cva,
+            standard_error,
+            expected_positive_exposure: exposures.iter().map(|e| e / n).collect(),
+            sensitivities: CvaSensitivities {
+                curve: sensitivities[..quotes].to_vec(),
+                mean_reversion: sensitivities[quotes],
+                volatility: sensitivities[quotes + 1],
+                hazard_rates: sensitivities[quotes + 2..quotes + 2 + hazard_rates].to_vec(),
+                recovery: sensitivities[quotes + 2 + hazard_rates],
+            },
+        }
+    }
+
+    /// Coupon dates of the netting set still to come on each exposure date.
+    fn bond_maturities(&self, netting_set: &[Swap]) -> Vec<Vec<f64>> {
+        let mut coupon_dates: Vec<f64> = netting_set
+            .iter()
+            .flat_map(|swap| swap.coupon_dates())
+            .collect();
+        coupon_dates.sort_by(f64::total_cmp);
+        coupon_dates.dedup();
+        self.exposure_times
+            .iter()
+            .map(|t| {
+                coupon_dates
+                    .iter()
+                    .copied()
+                    .filter(|date| date > t)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The path-independent coefficients of every exposure date from the
+    /// market `inputs`, laid out as [`CvaMarket::inputs`].
+    fn coefficients(
+        &self,
+        market: &CvaMarket,
+        netting_set: &[Swap],
+        inputs: &[Number],
+    ) -> Vec<DateCoefficients> {
+        let quotes = market.instruments.len();
+        let hazard_rates = market.hazard_rates.len();
+        let instruments: Vec<Instrument> = market
+            .instruments
+            .iter()
+            .zip(inputs)
+            .map(|(instrument, rate)| instrument.with_quote(*rate))
+            .collect();
+        let curve = DiscountCurve::bootstrap(&instruments);
+        let discount = |t: f64| curve.discount(t);
+        let model = HullWhite::new(inputs[quotes], inputs[quotes + 1]);
+        let hazard_curve = HazardCurve::new(
+            market.hazard_times.clone(),
+            inputs[quotes + 2..quotes + 2 + hazard_rates].to_vec(),
+        );
+        let loss_given_default = 1.0 - inputs[quotes + 2 + hazard_rates];
+
+        let mut t = 0.0;
+        let mut survival = Number::from(1.0);
+        self.exposure_times
+            .iter()
+            .zip(self.bond_maturities(netting_set))
+            .map(|(next_t, maturities)| {
+                let transition = model.transition(next_t - t);
+                t = *next_t;
+                let next_survival = hazard_curve.survival(t);
+                let default_loss = loss_given_default * (survival - next_survival);
+                survival = next_survival;
+                DateCoefficients {
+                    transition,
+                    discount: discount(t) * (-0.5 * model.integral_variance(t)).exp(),
+                    default_loss,
+                    bonds: maturities
+                        .iter()
+                        .map(|maturity| {
+                            let (a, b) = model.bond_coefficients(discount, t, *maturity);
+                            (*maturity, a, b)
+                        })
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+
+    /// CVA of one path given its normals, two per exposure date. Pushes the
+    /// discounted positive exposure of every date to `exposures`.
+    fn path_cva(
+        &self,
+        coefficients: &[DateCoefficients],
+        netting_set: &[Swap],
+        normals: &[f64],
+        exposures: &mut Vec<f64>,
+    ) -> Number {
+        let mut x = Number::from(0.0);
+        let mut integral = Number::from(0.0);
+        let mut cva = Number::from(0.0);
+        for ((t, date), z) in self
+            .exposure_times
+            .iter()
+            .zip(coefficients)
+            .zip(normals.chunks(2))
+        {
+            let step = &date.transition;
+            (x, integral) = (
+                step.decay * x + step.state_deviation * z[0],
+                integral
+                    + step.b * x
+                    + step.integral_loading * z[0]
+                    + step.integral_deviation * z[1],
+            );
+
+            // bond prices of the date, shared by the swaps
+            let bonds: Vec<(f64, Number)> = date
+                .bonds
+                .iter()
+                .map(|(maturity, a, b)| (*maturity, *a * (-1.0 * *b * x).exp()))
+                .collect();
+            // the maturities are the coupon dates the swaps ask for, copied
+            // from the same `coupon_dates`, so they compare exactly
+            let bond = |maturity: f64| {
+                bonds
+                    .iter()
+                    .find(|(coupon_date, _)| *coupon_date == maturity)
+                    .map(|(_, bond)| *bond)
+                    .expect("bond coefficients for every remaining coupon date")
+            };
+            let value = netting_set.iter().fold(Number::from(0.0), |value, swap| {
+                value + swap.value(*t, bond)
+            });
+
+            let exposure = date.discount * (-1.0 * integral).exp() * value.max(0.0);
+            exposures.push(exposure.result);
+            cva = cva + exposure * date.default_loss;
+        }
+        cva
+    }
+}
@@ -0,0 +1,385 @@
+//! European options under the Heston stochastic volatility model
+//!
+//! ```text
+//! dS = (r - q) S dt + sqrt(v) S dW1
+//! dv = kappa (theta - v) dt + xi sqrt(v) dW2,    dW1 dW2 = rho dt
+//! ```
+//!
+//! Prices come from the Lewis (2001) single-integral formula over the
+//! characteristic function, written in the "little trap" form of Albrecher et
+//! al. so that the complex logarithm stays on its principal branch. The
+//! integral is a fixed Gauss-Legendre rule, which makes the price a plain
+//! `Number` expression: the adjoint sweep gives its sensitivities to the model
+//! parameters and the market inputs.
+//!
+//! [`calibrate`] fits the parameters to a grid of quotes by least squares.
+
+use crate::finance::black_scholes::OptionType;
+use crate::least_squares::LevenbergMarquardt;
+use crate::number::Number;
+use std::f64::consts::PI;
+use std::ops::{Add, Div, Mul, Sub};
+
+/// Gauss-Legendre nodes of the pricing integral.
+const QUADRATURE_NODES: usize = 64;
+
+/// Levenberg-Marquardt iterations of [`calibrate`].
+const MAX_ITERATIONS: usize = 200;
+
+/// [`calibrate`] stops once the root mean square error is below this.
+const PRICE_TOLERANCE: f64 = 1e-10;
+
+/// Heston model parameters: initial variance `v0`, mean reversion speed
+/// `kappa`, long run variance `theta`, volatility of variance `xi` and the
+/// correlation `rho` between the spot and the variance.
+#[derive(Debug, Clone, Copy)]
+pub struct Heston {
+    pub v0: Number,
+    pub kappa: Number,
+    pub theta: Number,
+    pub xi: Number,
+    pub rho: Number,
+}
+
+impl Heston {
+    pub fn new<V, K, T, X, R>(v0: V, kappa: K, theta: T, xi: X, rho: R) -> Self
+    where
+        V: Into<Number>,
+        K: Into<Number>,
+        T: Into<Number>,
+        X: Into<Number>,
+        R: Into<Number>,
+    {
+        Heston {
+            v0: v0.into(),
+            kappa: kappa.into(),
+            theta: theta.into(),
+            xi: xi.into(),
+            rho: rho.into(),
+        }
+    }
+
+    /// Price of a European call. `maturity` is in years, `rate` and
+    /// `dividend_yield` are continuously compounded.
+    pub fn call_price(
+        &self,
+        spot: Number,
+        strike: f64,
+        maturity: f64,
+        rate: Number,
+        dividend_yield: Number,
+    ) -> Number {
+        // C = S e^(-qT) - sqrt(S K) e^(-(r + q) T / 2) / pi
+        //     * int_0^inf Re[e^(iuk) phi(u - i/2)] / (u^2 + 1/4) du,  k = ln(S / K) + (r - q) T
+        let log_moneyness = (spot / strike).ln() + (rate - dividend_yield) * maturity;
+        let scale = self.quadrature_scale(maturity);
+
+        let mut integral = Number::from(0.0);
+        for (x, w) in gauss_legendre(QUADRATURE_NODES) {
+            // u = L t / (1 - t) maps t in (0, 1) onto (0, inf)
+            let t = 0.5 * (x + 1.0);
+            let u = scale * t / (1.0 - t);
+            let weight = 0.5 * w * scale / ((1.0 - t) * (1.0 - t));
+            integral = integral + self.integrand(u, log_moneyness, maturity) * weight;
+        }
+
+        spot * (-1.0 * dividend_yield * maturity).exp()
+            - (spot * strike).sqrt() * (-0.5 * (rate + dividend_yield) * maturity).exp() * integral
+                / PI
+    }
+
+    /// Price of a European call or put, the put by put-call parity.
+    pub fn price(
+        &self,
+        option_type: OptionType,
+        spot: Number,
+        strike: f64,
+        maturity: f64,
+        rate: Number,
+        dividend_yield: Number,
+    ) -> Number {
+        let call = self.call_price(spot, strike, maturity, rate, dividend_yield);
+        match option_type {
+            OptionType::Call => call,
+            OptionType::Put => {
+                call - spot * (-1.0 * dividend_yield * maturity).exp()
+                    + strike * (-1.0 * rate * maturity).exp()
+            }
+        }
+    }
+
+    /// Re[e^(iuk) phi(u - i/2)] / (u^2 + 1/4) for real `u`, with phi the
+    /// characteristic function of ln(S_T / S) - (r - q) T.
+    fn integrand(&self, u: f64, log_moneyness: Number, maturity: f64) -> Number {
+        let xi_squared = self.xi * self.xi;
+
+        // at u - i/2: b = kappa - rho xi i u = kappa - rho xi / 2 - i rho xi u,
+        // and i u + u^2 = u^2 + 1/4 is real
+        let b = Complex::new(
+            self.kappa - 0.5 * self.rho * self.xi,
+            -u * self.rho * self.xi,
+        );
+        let d = (b * b + xi_squared * (u * u + 0.25)).sqrt();
+        let g = (b - d) / (b + d);
+        let e = (d * -maturity).exp();
+        let ge = g * e;
+
+        let c = ((b - d) * maturity - ((1.0 - ge) / (1.0 - g)).ln() * 2.0)
+            * (self.kappa * self.theta / xi_squared);
+        let d = (b - d) / xi_squared * ((1.0 - e) / (1.0 - ge));
+        let exponent = c + d * self.v0;
+
+        // Re[e^(iuk) e^exponent] = e^(exponent.re) cos(exponent.im + u k)
+        exponent.re.exp() * (exponent.im + u * log_moneyness).cos() / (u * u + 0.25)
+    }
+
+    /// Length scale of the integration variable. For large u the integrand
+    /// decays like exp(-u sqrt(1 - rho^2) (v0 + kappa theta T) / xi).
+    fn quadrature_scale(&self, maturity: f64) -> f64 {
+        let (v0, kappa, theta, xi, rho) = (
+            self.v0.result,
+            self.kappa.result,
+            self.theta.result,
+            self.xi.result,
+            self.rho.result,
+        );
+        let decay = (1.0 - rho * rho).max(0.0).sqrt() * (v0 + kappa * theta * maturity) / xi;
+        (1.0 / decay).clamp(0.5, 200.0)
+    }
+}
+
+/// Heston parameters as plain values, the input and output of [`calibrate`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HestonParameters {
+    pub v0: f64,
+    pub kappa: f64,
+    pub theta: f64,
+    pub xi: f64,
+    pub rho: f64,
+}
+
+impl HestonParameters {
+    /// Unconstrained coordinates of the calibration: logarithms of the
+    /// positive parameters and atanh of the correlation.
+    fn to_unconstrained(self) -> [f64; 5] {
+        [
+            self.v0.ln(),
+            self.kappa.ln(),
+            self.theta.ln(),
+            self.xi.ln(),
+            self.rho.atanh(),
+        ]
+    }
+
+    fn from_unconstrained(x: &[f64]) -> Self {
+        HestonParameters {
+            v0: x[0].exp(),
+            kappa: x[1].exp(),
+            theta: x[2].exp(),
+            xi: x[3].exp(),
+            rho: x[4].tanh(),
+        }
+    }
+}
+
+/// A market price of a European option.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quote {
+    pub option_type: OptionType,
+    pub strike: f64,
+    pub maturity: f64,
+    pub price: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    pub parameters: HestonParameters,
+    /// Root mean square of the price errors at `parameters`.
+    pub root_mean_square_error: f64,
+    pub iterations: usize,
+}
+
+/// Fits the Heston parameters to `quotes` by minimising the sum of squared
+/// price errors with Levenberg-Marquardt, starting from `initial_guess`.
+///
+/// The search runs on log-parameters and atanh(rho), so the parameters stay
+/// admissible. The prices are recorded together with the unconstrained
+/// coordinates as arguments: the adjoints of each are a row of the Jacobian
+/// J of the errors r, and J^T r is the gradient of the objective.
+pub fn calibrate(
+    spot: f64,
+    rate: f64,
+    dividend_yield: f64,
+    quotes: &[Quote],
+    initial_guess: HestonParameters,
+) -> Calibration {
+    let price_errors = |args: &[Number]| {
+        let heston = Heston::new(
+            args[0].exp(),
+            args[1].exp(),
+            args[2].exp(),
+            args[3].exp(),
+            args[4].tanh(),
+        );
+        quotes
+            .iter()
+            .map(|quote| {
+                heston.price(
+                    quote.option_type,
+                    Number::from(spot),
+                    quote.strike,
+                    quote.maturity,
+                    Number::from(rate),
+                    Number::from(dividend_yield),
+                ) - quote.price
+            })
+            .collect()
+    };
+
+    let fit = LevenbergMarquardt {
+        max_iterations: MAX_ITERATIONS,
+        residual_tolerance: PRICE_TOLERANCE,
+        ..LevenbergMarquardt::new()
+    }
+    .minimize(price_errors, &initial_guess.to_unconstrained());
+
+    Calibration {
+        parameters: HestonParameters::from_unconstrained(&fit.x),
+        root_mean_square_error: (fit.sum_of_squares / quotes.len() as f64).sqrt(),
+        iterations: fit.iterations,
+    }
+}
+
+/// Nodes and weights of the `n`-point Gauss-Legendre rule on [-1, 1].
+fn gauss_legendre(n: usize) -> Vec<(f64, f64)> {
+    (1..=n)
+        .map(|i| {
+            // Newton iteration on P_n from the Chebyshev-like initial guess
+            let mut x = (PI * (i as f64 - 0.25) / (n as f64 + 0.5)).cos();
+            let mut derivative = 0.0;
+            for _ in 0..100 {
+                let (mut p0, mut p1) = (1.0, x);
+                for k in 2..=n {
+                    let k = k as f64;
+                    (p0, p1) = (p1, ((2.0 * k - 1.0) * x * p1 - (k - 1.0) * p0) / k);
+                }
+                derivative = n as f64 * (x * p1 - p0) / (x * x - 1.0);
+                let dx = p1 / derivative;
+                x -= dx;
+                if dx.abs() < 1e-15 {
+                    break;
+                }
+            }
+            (x, 2.0 / ((1.0 - x * x) * derivative * derivative))
+        })
+        .collect()
+}
+
+/// Complex number on `Number`s, enough for the characteristic function.
+#[derive(Debug, Clone, Copy)]
+struct Complex {
+    re: Number,
+    im: Number,
+}
+
+impl Complex {
+    fn new(re: Number, im: Number) -> Self {
+        Complex { re, im }
+    }
+
+    fn exp(self) -> Self {
+        let modulus = self.re.exp();
+        Complex::new(modulus * self.im.cos(), modulus * self.im.sin())
+    }
+
+    /// Principal branch.
+    fn ln(self) -> Self {
+        Complex::new(self.re.hypot(self.im).ln(), self.im.atan2(self.re))
+    }
+
+    /// Principal branch.
+    fn sqrt(self) -> Self {
+        let modulus = self.re.hypot(self.im).sqrt();
+        let half_argument = 0.5 * self.im.atan2(self.re);
+        Complex::new(modulus * half_argument.cos(), modulus * half_argument.sin())
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        let denominator = rhs.re * rhs.re + rhs.im * rhs.im;
+        Complex::new(
+            (self.re * rhs.re + self.im * rhs.im) / denominator,
+            (self.im * rhs.re - self.re * rhs.im) / denominator,
+        )
+    }
+}
+
+impl Add<Number> for Complex {
+    type Output = Complex;
+
+    fn add(self, rhs: Number) -> Self::Output {
+        Complex::new(self.re + rhs, self.im)
+    }
+}
+
+impl Sub<Complex> for f64 {
+    type Output = Complex;
+
+    fn sub(self, rhs: Complex) -> Self::Output {
+        Complex::new(self - rhs.re, -1.0 * rhs.im)
+    }
+}
+
+impl Mul<Number> for Complex {
+    type Output = Complex;
+
+    fn mul(self, rhs: Number) -> Self::Output {
+        Complex::new(self.re * rhs, self.im * rhs)
+    }
+}
+
+impl Mul<f64> for Complex {
+    type Output = Complex;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Complex::new(self.re * rhs, self.im * rhs)
+    }
+}
+
+impl Div<Number> for Complex {
+    type Output = Complex;
+
+    fn div(self, rhs: Number) -> Self::Output {
+        Complex::new(self.re / rhs, self.im / rhs)
+    }
+}
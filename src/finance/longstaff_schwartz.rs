@@ -0,0 +1,259 @@
+//! Bermudan options by Longstaff-Schwartz least-squares Monte Carlo, with
+//! adjoint greeks.
+//!
+//! A first pass without the tape regresses the discounted cash flows of the
+//! paths that are in the money on polynomials of the moneyness, backwards
+//! from maturity, and keeps the coefficients of every exercise date. A second
+//! pass on independent paths freezes those coefficients: each path is
+//! recorded with the continuation values and the exercise decisions as
+//! `Number::select` nodes, and its discounted cash flow swept for the greeks.
+//! With the regression frozen the exercise boundary does not move with the
+//! inputs, which by optimality of the boundary costs nothing to first order.
+
+use crate::automatic_differentiator::AutomaticDifferentiator;
+use crate::finance::black_scholes::OptionType;
+use crate::finance::monte_carlo::{self, Gbm, MonteCarlo, MonteCarloResult};
+use crate::linear_algebra;
+use crate::number::{Condition, Number};
+use crate::random::{NormalMethod, Rng};
+
+/// Mixed into the seed of the regression paths, so that they are independent
+/// of the pricing paths.
+const REGRESSION_STREAM: u64 = 0x5851_f42d_4c95_7f2d;
+
+/// Coefficients of the continuation value on each exercise date but the last,
+/// in discounted units: the continuation value at S is
+/// sum_j coefficients[k][j] (S / K)^j.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExerciseRegression {
+    pub coefficients: Vec<Vec<f64>>,
+}
+
+/// A Bermudan option on geometric Brownian motion, exercisable on
+/// `exercise_dates`, the last of which is the maturity. The model inputs are
+/// those of [`Gbm`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LongstaffSchwartz {
+    pub option_type: OptionType,
+    pub strike: f64,
+    /// Strictly increasing, positive exercise dates.
+    pub exercise_dates: Vec<f64>,
+    pub regression_paths: usize,
+    pub pricing_paths: usize,
+    /// Degree of the polynomial in S / K of the regression.
+    pub degree: usize,
+    pub seed: u64,
+    pub normal_method: NormalMethod,
+}
+
+impl LongstaffSchwartz {
+    pub fn new(
+        option_type: OptionType,
+        strike: f64,
+        exercise_dates: Vec<f64>,
+        paths: usize,
+        seed: u64,
+    ) -> Self {
+        assert!(
+            !exercise_dates.is_empty()
+                && exercise_dates[0] > 0.0
+                && exercise_dates.windows(2).all(|t| t[0] < t[1]),
+            "exercise dates must be positive and strictly increasing"
+        );
+        LongstaffSchwartz {
+            option_type,
+            strike,
+            exercise_dates,
+            regression_paths: paths,
+            pricing_paths: paths,
+            degree: 2,
+            seed,
+            normal_method: NormalMethod::BoxMuller,
+        }
+    }
+
+    /// The untaped first pass: regression coefficients of every exercise date
+    /// but the last from `regression_paths` paths.
+    pub fn regress(&self, inputs: &[f64]) -> ExerciseRegression {
+        crate::no_tape(|| {
+            let engine = self.engine();
+            let inputs: Vec<Number> = inputs.iter().map(|x| Number::new(*x)).collect();
+            let mut rng = Rng::new(self.seed ^ REGRESSION_STREAM);
+            let paths: Vec<Vec<Number>> = (0..self.regression_paths)
+                .map(|_| {
+                    let normals = rng.normals(self.exercise_dates.len(), self.normal_method);
+                    engine.simulate(&Gbm, &inputs, &normals)
+                })
+                .collect();
+
+            // discounted cash flow of every path, exercising at maturity
+            let last = self.exercise_dates.len();
+            let mut cash_flows: Vec<f64> = paths
+                .iter()
+                .map(|path| self.exercise_value(last, path[last], &inputs).result)
+                .collect();
+
+            let mut coefficients = vec![Vec::new(); last - 1];
+            for date in (1..last).rev() {
+                let in_the_money: Vec<usize> = (0..paths.len())
+                    .filter(|p| self.intrinsic(paths[*p][date]).result > 0.0)
+                    .collect();
+                let beta = self.fit(
+                    in_the_money.iter().map(|p| paths[*p][date]),
+                    in_the_money.iter().map(|p| cash_flows[*p]),
+                );
+                for p in in_the_money {
+                    let exercise = self.exercise_value(date, paths[p][date], &inputs).result;
+                    if exercise > self.continuation(&beta, paths[p][date]).result {
+                        cash_flows[p] = exercise;
+                    }
+                }
+                coefficients[date - 1] = beta;
+            }
+            ExerciseRegression { coefficients }
+        })
+    }
+
+    /// The untaped second pass: price of the option exercising by
+    /// `regression`, on `pricing_paths` paths.
+    pub fn price(&self, inputs: &[f64], regression: &ExerciseRegression) -> f64 {
+        crate::no_tape(|| {
+            let engine = self.engine();
+            let inputs: Vec<Number> = inputs.iter().map(|x| Number::new(*x)).collect();
+            let mut rng = Rng::new(self.seed);
+            let sum: f64 = (0..self.pricing_paths)
+                .map(|_| {
+                    let normals = rng.normals(self.exercise_dates.len(), self.normal_method);
+                    let path = engine.simulate(&Gbm, &inputs, &normals);
+                    self.discounted_cash_flow(&path, &inputs, regression).result
+                })
+                .sum();
+            sum / self.pricing_paths as f64
+        })
+    }
+
+    /// Price and greeks: the regression of [`LongstaffSchwartz::regress`],
+    /// then the paths of [`LongstaffSchwartz::price`] recorded and swept one
+    /// by one. The sensitivities are in the order of the [`Gbm`] inputs.
+    pub fn run(&self, inputs: &[f64]) -> MonteCarloResult {
+        let regression = self.regress(inputs);
+        let engine = self.engine();
+        let mut rng = Rng::new(self.seed);
+        let mut automatic_differentiator = AutomaticDifferentiator::new();
+        let arguments: Vec<Number> = inputs.iter().map(|x| Number::new(*x)).collect();
+
+        let mut sum = 0.0;
+        let mut sum_of_squares = 0.0;
+        let mut sensitivities = vec![0.0; inputs.len()];
+
+        for _ in 0..self.pricing_paths {
+            let normals = rng.normals(self.exercise_dates.len(), self.normal_method);
+            let evaluation = automatic_differentiator.derivatives(
+                |args: &[Number]| {
+                    let path = engine.simulate(&Gbm, args, &normals);
+                    self.discounted_cash_flow(&path, args, &regression)
+                },
+                &arguments,
+            );
+
+            sum += evaluation.result;
+            sum_of_squares += evaluation.result * evaluation.result;
+            for (sensitivity, argument) in sensitivities.iter_mut().zip(&arguments) {
+                *sensitivity += evaluation.derivative(*argument);
+            }
+        }
+
+        let (price, standard_error) =
+            monte_carlo::mean_and_standard_error(sum, sum_of_squares, self.pricing_paths);
+        let n = self.pricing_paths as f64;
+        MonteCarloResult {
+            price,
+            standard_error,
+            sensitivities: sensitivities.iter().map(|s| s / n).collect(),
+        }
+    }
+
+    /// Simulation on the exercise dates, one exact step per date.
+    fn engine(&self) -> MonteCarlo {
+        MonteCarlo::new(0, self.exercise_dates.clone(), self.seed)
+    }
+
+    fn intrinsic(&self, spot: Number) -> Number {
+        match self.option_type {
+            OptionType::Call => (spot - self.strike).max(0.0),
+            OptionType::Put => (self.strike - spot).max(0.0),
+        }
+    }
+
+    /// Intrinsic value at `spot` on exercise date `date`, counted from 1 as
+    /// in the simulated path, discounted to today.
+    fn exercise_value(&self, date: usize, spot: Number, inputs: &[Number]) -> Number {
+        let t = self.exercise_dates[date - 1];
+        (-1.0 * inputs[Gbm::RATE] * t).exp() * self.intrinsic(spot)
+    }
+
+    fn basis(&self, spot: Number) -> Vec<Number> {
+        let moneyness = spot / self.strike;
+        let mut basis = vec![Number::from(1.0)];
+        for j in 1..=self.degree {
+            basis.push(basis[j - 1] * moneyness);
+        }
+        basis
+    }
+
+    fn continuation(&self, coefficients: &[f64], spot: Number) -> Number {
+        self.basis(spot)
+            .iter()
+            .zip(coefficients)
+            .fold(Number::from(0.0), |sum, (basis, beta)| sum + *basis * *beta)
+    }
+
+    /// Least-squares coefficients of `cash_flows` on the basis at `spots`.
+    /// Without enough paths in the money the continuation value is zero.
+    fn fit<S, C>(&self, spots: S, cash_flows: C) -> Vec<f64>
+    where
+        S: Iterator<Item = Number>,
+        C: Iterator<Item = f64>,
+    {
+        let size = self.degree + 1;
+        let mut normal_matrix = vec![vec![0.0; size]; size];
+        let mut rhs = vec![0.0; size];
+        let mut observations = 0;
+        for (spot, cash_flow) in spots.zip(cash_flows) {
+            let basis: Vec<f64> = self.basis(spot).iter().map(|b| b.result).collect();
+            for (j, basis_j) in basis.iter().enumerate() {
+                rhs[j] += basis_j * cash_flow;
+                for (k, basis_k) in basis.iter().enumerate() {
+                    normal_matrix[j][k] += basis_j * basis_k;
+                }
+            }
+            observations += 1;
+        }
+        if observations <= size {
+            return vec![0.0; size];
+        }
+        linear_algebra::solve(normal_matrix, rhs)
+    }
+
+    /// Discounted cash flow of `path` exercising by `regression`: backwards
+    /// from maturity, the value on a date is the exercise value if the option
+    /// is in the money and that beats the continuation value, the value of the
+    /// next date otherwise.
+    fn discounted_cash_flow(
+        &self,
+        path: &[Number],
+        inputs: &[Number],
+        regression: &ExerciseRegression,
+    ) -> Number {
+        let last = self.exercise_dates.len();
+        let mut value = self.exercise_value(last, path[last], inputs);
+        for date in (1..last).rev() {
+            let spot = path[date];
+            let exercise = self.exercise_value(date, spot, inputs);
+            let continuation = self.continuation(&regression.coefficients[date - 1], spot);
+            let exercised = Number::select(Condition::gt(exercise, continuation), exercise, value);
+            value = Number::select(Condition::gt(exercise, 0.0), exercised, value);
+        }
+        value
+    }
+}
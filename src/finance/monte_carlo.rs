@@ -0,0 +1,322 @@
+//! Monte Carlo pricing with pathwise adjoint sensitivities.
+//!
+//! Every path is recorded and swept on its own: the tape holds a single path
+//! at a time, and the derivatives of the discounted payoff with respect to
+//! the model inputs are averaged over the paths like the payoff itself.
+//!
+//! Pathwise sensitivities need a payoff that is continuous in the inputs.
+//! Digitals and barriers can be smoothed with `Number::smooth_step` and friends,
+//! or priced with the likelihood-ratio estimator of a [`Payoff`], which
+//! differentiates the density of the path instead of the path itself. With
+//! the path held fixed, the derivative of the expected payoff is
+//!
+//! ```text
+//! d/dtheta E[f] = E[df/dtheta + f dlog p/dtheta]
+//! ```
+//!
+//! where p is the density of the path increments and df/dtheta the explicit
+//! dependence of the payoff on the inputs, such as its discounting. Both
+//! terms come from the same recording: the log-density of the path is
+//! recorded next to the payoff, and its adjoints are the score.
+
+use crate::automatic_differentiator::AutomaticDifferentiator;
+use crate::number::Number;
+use crate::random::{NormalMethod, Rng};
+
+/// A one-factor SDE `dX = drift(t, X) dt + diffusion(t, X) dW`, whose
+/// coefficients depend on the model inputs.
+pub trait Sde {
+    fn initial_value(&self, inputs: &[Number]) -> Number;
+
+    fn drift(&self, t: f64, x: Number, inputs: &[Number]) -> Number;
+
+    fn diffusion(&self, t: f64, x: Number, inputs: &[Number]) -> Number;
+
+    /// Advances `x` from `t` to `t + dt` given the standard normal draw `z`.
+    /// Euler-Maruyama unless the SDE knows better.
+    fn step(&self, t: f64, dt: f64, x: Number, z: f64, inputs: &[Number]) -> Number {
+        x + self.drift(t, x, inputs) * dt + self.diffusion(t, x, inputs) * (dt.sqrt() * z)
+    }
+
+    /// Log-density, up to a constant that does not depend on the inputs, of
+    /// moving from `x` at `t` to `next` at `t + dt`. Must match `step`: the
+    /// Gaussian of the Euler-Maruyama step unless the SDE knows better.
+    fn log_transition_density(
+        &self,
+        t: f64,
+        dt: f64,
+        x: Number,
+        next: f64,
+        inputs: &[Number],
+    ) -> Number {
+        let standard_deviation = self.diffusion(t, x, inputs) * dt.sqrt();
+        let u = (next - x - self.drift(t, x, inputs) * dt) / standard_deviation;
+        -0.5 * u * u - standard_deviation.abs().ln()
+    }
+}
+
+/// Geometric Brownian motion `dS = (r - q) S dt + sigma S dW` under the
+/// risk-neutral measure. Inputs are indexed by the associated constants.
+/// Steps are exact, so a single step to maturity suffices for European payoffs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Gbm;
+
+impl Gbm {
+    pub const SPOT: usize = 0;
+    pub const RATE: usize = 1;
+    pub const DIVIDEND_YIELD: usize = 2;
+    pub const VOLATILITY: usize = 3;
+}
+
+impl Sde for Gbm {
+    fn initial_value(&self, inputs: &[Number]) -> Number {
+        inputs[Gbm::SPOT]
+    }
+
+    fn drift(&self, _t: f64, x: Number, inputs: &[Number]) -> Number {
+        (inputs[Gbm::RATE] - inputs[Gbm::DIVIDEND_YIELD]) * x
+    }
+
+    fn diffusion(&self, _t: f64, x: Number, inputs: &[Number]) -> Number {
+        inputs[Gbm::VOLATILITY] * x
+    }
+
+    fn step(&self, _t: f64, dt: f64, x: Number, z: f64, inputs: &[Number]) -> Number {
+        let volatility = inputs[Gbm::VOLATILITY];
+        let log_drift =
+            inputs[Gbm::RATE] - inputs[Gbm::DIVIDEND_YIELD] - 0.5 * volatility * volatility;
+        x * (log_drift * dt + volatility * (dt.sqrt() * z)).exp()
+    }
+
+    /// Lognormal: ln(next / x) is Gaussian with mean `log_drift dt` and
+    /// standard deviation `sigma sqrt(dt)`.
+    fn log_transition_density(
+        &self,
+        _t: f64,
+        dt: f64,
+        x: Number,
+        next: f64,
+        inputs: &[Number],
+    ) -> Number {
+        let volatility = inputs[Gbm::VOLATILITY];
+        let log_drift =
+            inputs[Gbm::RATE] - inputs[Gbm::DIVIDEND_YIELD] - 0.5 * volatility * volatility;
+        let standard_deviation = volatility * dt.sqrt();
+        let u = ((next / x).ln() - log_drift * dt) / standard_deviation;
+        -0.5 * u * u - standard_deviation.ln()
+    }
+}
+
+/// Estimator of the sensitivities of a [`Payoff`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GreeksEstimator {
+    /// Derivative of the payoff along the path. The lowest variance, but
+    /// zero or wrong for payoffs that jump with the path.
+    Pathwise,
+    /// Payoff times the score of the path density, plus its explicit
+    /// dependence on the inputs. Any payoff, at the price of more variance.
+    LikelihoodRatio,
+    /// Pathwise for the continuous part of the payoff, likelihood ratio for
+    /// the discontinuous part.
+    Mixed,
+}
+
+/// A discounted payoff of a path (see [`MonteCarlo::simulate`]) and the
+/// inputs.
+type PayoffFunction<'a> = Box<dyn Fn(&[Number], &[Number]) -> Number + 'a>;
+
+/// A discounted payoff together with the estimator of its sensitivities,
+/// priced by [`MonteCarlo::run_payoff`].
+pub struct Payoff<'a> {
+    /// Part of the payoff differentiated pathwise.
+    continuous: Option<PayoffFunction<'a>>,
+    /// Part of the payoff differentiated by likelihood ratio.
+    discontinuous: Option<PayoffFunction<'a>>,
+}
+
+impl<'a> Payoff<'a> {
+    pub fn pathwise<P>(payoff: P) -> Self
+    where
+        P: Fn(&[Number], &[Number]) -> Number + 'a,
+    {
+        Payoff {
+            continuous: Some(Box::new(payoff)),
+            discontinuous: None,
+        }
+    }
+
+    pub fn likelihood_ratio<P>(payoff: P) -> Self
+    where
+        P: Fn(&[Number], &[Number]) -> Number + 'a,
+    {
+        Payoff {
+            continuous: None,
+            discontinuous: Some(Box::new(payoff)),
+        }
+    }
+
+    /// The sum of `continuous`, differentiated pathwise, and `discontinuous`,
+    /// by likelihood ratio: a call spread and its digital correction, say.
+    pub fn mixed<C, D>(continuous: C, discontinuous: D) -> Self
+    where
+        C: Fn(&[Number], &[Number]) -> Number + 'a,
+        D: Fn(&[Number], &[Number]) -> Number + 'a,
+    {
+        Payoff {
+            continuous: Some(Box::new(continuous)),
+            discontinuous: Some(Box::new(discontinuous)),
+        }
+    }
+
+    pub fn estimator(&self) -> GreeksEstimator {
+        match (&self.continuous, &self.discontinuous) {
+            (Some(_), None) => GreeksEstimator::Pathwise,
+            (None, Some(_)) => GreeksEstimator::LikelihoodRatio,
+            _ => GreeksEstimator::Mixed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonteCarloResult {
+    /// Average discounted payoff, NaN without paths.
+    pub price: f64,
+    /// Standard error of `price`, NaN with fewer than two paths.
+    pub standard_error: f64,
+    /// Derivative of `price` with respect to each model input, in input order.
+    pub sensitivities: Vec<f64>,
+}
+
+/// Monte Carlo engine simulating on the times in `time_grid`, starting from 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonteCarlo {
+    pub paths: usize,
+    /// Strictly increasing, positive simulation times.
+    pub time_grid: Vec<f64>,
+    pub seed: u64,
+    pub normal_method: NormalMethod,
+}
+
+impl MonteCarlo {
+    pub fn new(paths: usize, time_grid: Vec<f64>, seed: u64) -> Self {
+        MonteCarlo {
+            paths,
+            time_grid,
+            seed,
+            normal_method: NormalMethod::BoxMuller,
+        }
+    }
+
+    /// Values of the SDE at time 0 and at every time of the grid, driven by one
+    /// standard normal per step.
+    pub fn simulate<S: Sde>(&self, sde: &S, inputs: &[Number], normals: &[f64]) -> Vec<Number> {
+        let mut path = Vec::with_capacity(self.time_grid.len() + 1);
+        let mut x = sde.initial_value(inputs);
+        let mut t = 0.0;
+        path.push(x);
+        for (next_t, z) in self.time_grid.iter().zip(normals) {
+            x = sde.step(t, next_t - t, x, *z, inputs);
+            path.push(x);
+            t = *next_t;
+        }
+        path
+    }
+
+    /// Prices `payoff` and its sensitivities to `inputs`. `payoff` receives a
+    /// simulated path (see [`MonteCarlo::simulate`]) and the inputs, and
+    /// returns the discounted payoff of that path.
+    pub fn run<S, P>(&self, sde: &S, inputs: &[f64], payoff: P) -> MonteCarloResult
+    where
+        S: Sde,
+        P: Fn(&[Number], &[Number]) -> Number,
+    {
+        self.run_paths(inputs, |args, normals| {
+            payoff(&self.simulate(sde, args, normals), args)
+        })
+    }
+
+    /// Prices `payoff` and its sensitivities to `inputs` with the estimator
+    /// of the payoff. The likelihood-ratio part of the payoff receives the
+    /// simulated path held fixed: only its initial value depends on the inputs.
+    pub fn run_payoff<S: Sde>(&self, sde: &S, inputs: &[f64], payoff: &Payoff) -> MonteCarloResult {
+        self.run_paths(inputs, |args, normals| {
+            let path = self.simulate(sde, args, normals);
+            let mut value = Number::from(0.0);
+            if let Some(continuous) = &payoff.continuous {
+                value = value + continuous(&path, args);
+            }
+            if let Some(discontinuous) = &payoff.discontinuous {
+                let mut fixed_path = vec![path[0]];
+                fixed_path.extend(path[1..].iter().map(|x| Number::from(x.result)));
+                let payoff = discontinuous(&fixed_path, args);
+                let log_density = self.log_density(sde, args, &fixed_path);
+                // adds payoff * dlog p / dtheta to the adjoints, nothing to the value
+                value = value + payoff + payoff.result * (log_density - log_density.result);
+            }
+            value
+        })
+    }
+
+    /// Log-density of the increments of `path` under the inputs.
+    fn log_density<S: Sde>(&self, sde: &S, inputs: &[Number], path: &[Number]) -> Number {
+        let mut log_density = Number::from(0.0);
+        let mut t = 0.0;
+        for (k, next_t) in self.time_grid.iter().enumerate() {
+            log_density = log_density
+                + sde.log_transition_density(t, next_t - t, path[k], path[k + 1].result, inputs);
+            t = *next_t;
+        }
+        log_density
+    }
+
+    /// Averages over the paths the value recorded by `record` from the inputs
+    /// and the normals of the path, and its adjoints.
+    fn run_paths<R>(&self, inputs: &[f64], record: R) -> MonteCarloResult
+    where
+        R: Fn(&[Number], &[f64]) -> Number,
+    {
+        let mut rng = Rng::new(self.seed);
+        let mut automatic_differentiator = AutomaticDifferentiator::new();
+        let arguments: Vec<Number> = inputs.iter().map(|x| Number::new(*x)).collect();
+
+        let mut sum = 0.0;
+        let mut sum_of_squares = 0.0;
+        let mut sensitivities = vec![0.0; inputs.len()];
+
+        for _ in 0..self.paths {
+            let normals = rng.normals(self.time_grid.len(), self.normal_method);
+            let evaluation = automatic_differentiator
+                .derivatives(|args: &[Number]| record(args, &normals), &arguments);
+
+            sum += evaluation.result;
+            sum_of_squares += evaluation.result * evaluation.result;
+            for (sensitivity, argument) in sensitivities.iter_mut().zip(&arguments) {
+                *sensitivity += evaluation.derivative(*argument);
+            }
+        }
+
+        let (price, standard_error) = mean_and_standard_error(sum, sum_of_squares, self.paths);
+        let n = self.paths as f64;
+        MonteCarloResult {
+            price,
+            standard_error,
+            sensitivities: sensitivities.iter().map(|s| s / n).collect(),
+        }
+    }
+}
+
+/// Mean of `paths` samples from their sum and the sum of their squares, and
+/// its standard error. The mean is NaN without samples, and the standard
+/// error with fewer than two: one sample tells nothing of the spread.
+pub(crate) fn mean_and_standard_error(sum: f64, sum_of_squares: f64, paths: usize) -> (f64, f64) {
+    if paths == 0 {
+        return (f64::NAN, f64::NAN);
+    }
+    let n = paths as f64;
+    let mean = sum / n;
+    if paths == 1 {
+        return (mean, f64::NAN);
+    }
+    let variance = (sum_of_squares / n - mean * mean).max(0.0);
+    (mean, (variance / (n - 1.0)).sqrt())
+}
@@ -0,0 +1,285 @@
+//! European options under local volatility by finite differences: the
+//! Black-Scholes equation in log-spot x = ln S and time to maturity tau
+//!
+//! ```text
+//! dV/dtau = 1/2 sigma(t, S)^2 d2V/dx2 + (r - q - 1/2 sigma(t, S)^2) dV/dx - r V
+//! ```
+//!
+//! rolled back from the payoff with Crank-Nicolson steps. Each step is one
+//! recorded tridiagonal solve, see [`linear_algebra::solve_tridiagonal`], so
+//! a single sweep over the rollback gives the derivatives of the price with
+//! respect to spot, rates and every node of the volatility surface.
+
+use crate::automatic_differentiator::AutomaticDifferentiator;
+use crate::finance::black_scholes::OptionType;
+use crate::linear_algebra;
+use crate::number::Number;
+
+/// Local volatility sigma(t, S) on a grid of times and spots: constant in
+/// time from one time node to the next, linear in spot between spot nodes and
+/// flat beyond the first and last ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalVolatility {
+    /// Increasing times in years. Row `i` of `volatilities` applies to the
+    /// times after `times[i - 1]` up to `times[i]`, the last row after.
+    pub times: Vec<f64>,
+    /// Increasing spots.
+    pub spots: Vec<f64>,
+    /// One row of volatilities per time, one column per spot.
+    pub volatilities: Vec<Vec<Number>>,
+}
+
+impl LocalVolatility {
+    pub fn new(times: Vec<f64>, spots: Vec<f64>, volatilities: Vec<Vec<Number>>) -> Self {
+        assert!(
+            !times.is_empty() && !spots.is_empty(),
+            "a local volatility surface needs at least one time and one spot"
+        );
+        assert!(
+            times.windows(2).all(|t| t[0] < t[1]) && spots.windows(2).all(|s| s[0] < s[1]),
+            "times and spots must be strictly increasing"
+        );
+        assert!(
+            volatilities.len() == times.len()
+                && volatilities.iter().all(|row| row.len() == spots.len()),
+            "volatilities must have one row per time and one column per spot"
+        );
+        LocalVolatility {
+            times,
+            spots,
+            volatilities,
+        }
+    }
+
+    /// The surface of a constant volatility.
+    pub fn flat<V: Into<Number>>(volatility: V) -> Self {
+        LocalVolatility::new(vec![0.0], vec![0.0], vec![vec![volatility.into()]])
+    }
+
+    /// Volatility at time `t` and `spot`.
+    pub fn volatility(&self, t: f64, spot: Number) -> Number {
+        let row = self
+            .times
+            .iter()
+            .position(|time| *time >= t)
+            .unwrap_or(self.times.len() - 1);
+        let volatilities = &self.volatilities[row];
+
+        let last = self.spots.len() - 1;
+        if spot.result <= self.spots[0] {
+            return volatilities[0];
+        }
+        if spot.result >= self.spots[last] {
+            return volatilities[last];
+        }
+        let upper = self.spots.partition_point(|s| *s < spot.result);
+        let weight = (spot - self.spots[upper - 1]) / (self.spots[upper] - self.spots[upper - 1]);
+        volatilities[upper - 1] + weight * (volatilities[upper] - volatilities[upper - 1])
+    }
+}
+
+/// Crank-Nicolson scheme on a uniform log-spot grid centred on today's spot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CrankNicolson {
+    /// Intervals of the log-spot grid, rounded up to an even number so that
+    /// the spot falls on the middle node.
+    pub spot_steps: usize,
+    pub time_steps: usize,
+    /// Half width of the grid in ln S, for instance five standard deviations
+    /// of ln S at maturity. The grid does not depend on the inputs of the
+    /// price, so that its derivatives are those of the discrete price.
+    pub half_width: f64,
+}
+
+impl CrankNicolson {
+    pub fn new(spot_steps: usize, time_steps: usize, half_width: f64) -> Self {
+        assert!(
+            spot_steps >= 2 && time_steps >= 1 && half_width > 0.0,
+            "the grid needs at least two spot steps, one time step and a positive width"
+        );
+        CrankNicolson {
+            spot_steps: spot_steps + spot_steps % 2,
+            time_steps,
+            half_width,
+        }
+    }
+
+    /// Price of a European option struck at `strike`, expiring in `maturity`
+    /// years. The boundaries are the discounted intrinsic values of the
+    /// option, the local volatility of a step is taken at its middle. The
+    /// price is read on the middle node of the grid, at `spot`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn price(
+        &self,
+        option_type: OptionType,
+        spot: Number,
+        strike: f64,
+        maturity: f64,
+        rate: Number,
+        dividend_yield: Number,
+        surface: &LocalVolatility,
+    ) -> Number {
+        let m = self.spot_steps;
+        let dt = maturity / self.time_steps as f64;
+        let dx = 2.0 * self.half_width / m as f64;
+
+        let spots: Vec<Number> = (0..=m)
+            .map(|j| spot * ((j as f64 - 0.5 * m as f64) * dx).exp())
+            .collect();
+        // payoff averaged over the cell of each node, so that the price does
+        // not depend on where the strike falls between two nodes
+        let strike_offset = (strike / spot).ln();
+        let mut values: Vec<Number> = (0..=m)
+            .map(|j| {
+                let lower = (j as f64 - 0.5 * m as f64 - 0.5) * dx;
+                let upper = lower + dx;
+                let average_spot = spot * (upper.exp() - lower.exp()) / dx;
+                let average_call = if strike_offset.result <= lower {
+                    average_spot - strike
+                } else if strike_offset.result >= upper {
+                    Number::from(0.0)
+                } else {
+                    (spot * upper.exp() - strike - strike * (upper - strike_offset)) / dx
+                };
+                match option_type {
+                    OptionType::Call => average_call,
+                    OptionType::Put => average_call - average_spot + strike,
+                }
+            })
+            .collect();
+
+        // Rannacher start: the first step is two fully implicit half steps,
+        // which damp the oscillations Crank-Nicolson keeps from the kink of
+        // the payoff. The steps are (time to maturity at the start, length,
+        // implicit weight theta)
+        let steps = [(0.0, 0.5 * dt, 1.0), (0.5 * dt, 0.5 * dt, 1.0)]
+            .into_iter()
+            .chain((1..self.time_steps).map(|step| (step as f64 * dt, dt, 0.5)));
+
+        for (start, dt, theta) in steps {
+            let tau = start + dt;
+            let t = maturity - start - 0.5 * dt;
+
+            // L V_j = a_j V_j-1 + b_j V_j + c_j V_j+1 on the interior nodes
+            let mut a = Vec::with_capacity(m - 1);
+            let mut b = Vec::with_capacity(m - 1);
+            let mut c = Vec::with_capacity(m - 1);
+            for s in &spots[1..m] {
+                let volatility = surface.volatility(t, *s);
+                let variance = volatility * volatility;
+                let diffusion = 0.5 * variance / (dx * dx);
+                let convection = (rate - dividend_yield - 0.5 * variance) / (2.0 * dx);
+                a.push(diffusion - convection);
+                b.push(-2.0 * diffusion - rate);
+                c.push(diffusion + convection);
+            }
+
+            let discounted_spot = |s: Number| s * (-1.0 * dividend_yield * tau).exp();
+            let discounted_strike = strike * (-1.0 * rate * tau).exp();
+            let (lower_boundary, upper_boundary) = match option_type {
+                OptionType::Call => (
+                    Number::from(0.0),
+                    discounted_spot(spots[m]) - discounted_strike,
+                ),
+                OptionType::Put => (
+                    discounted_strike - discounted_spot(spots[0]),
+                    Number::from(0.0),
+                ),
+            };
+
+            // (I - theta dt L) V(tau) = (I + (1 - theta) dt L) V(tau - dt)
+            let implicit = theta * dt;
+            let explicit = (1.0 - theta) * dt;
+            let mut rhs: Vec<Number> = (1..m)
+                .map(|j| {
+                    values[j]
+                        + explicit
+                            * (a[j - 1] * values[j - 1]
+                                + b[j - 1] * values[j]
+                                + c[j - 1] * values[j + 1])
+                })
+                .collect();
+            rhs[0] = rhs[0] + implicit * a[0] * lower_boundary;
+            rhs[m - 2] = rhs[m - 2] + implicit * c[m - 2] * upper_boundary;
+
+            let lower: Vec<Number> = a[1..].iter().map(|a| -implicit * *a).collect();
+            let diagonal: Vec<Number> = b.iter().map(|b| 1.0 - implicit * *b).collect();
+            let upper: Vec<Number> = c[..m - 2].iter().map(|c| -implicit * *c).collect();
+            let interior = linear_algebra::solve_tridiagonal(&lower, &diagonal, &upper, &rhs);
+
+            values = [vec![lower_boundary], interior, vec![upper_boundary]].concat();
+        }
+
+        values[m / 2]
+    }
+}
+
+/// Price of an option and its derivatives with respect to spot, rate and
+/// each node of the local volatility surface.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdeRisk {
+    pub price: f64,
+    /// dV/dS
+    pub delta: f64,
+    /// dV/dr
+    pub rho: f64,
+    /// dV/dsigma for each node of the surface, in the layout of
+    /// [`LocalVolatility::volatilities`].
+    pub vegas: Vec<Vec<f64>>,
+}
+
+/// Price and sensitivities of a European option under `surface`, from one
+/// recording of the rollback.
+#[allow(clippy::too_many_arguments)]
+pub fn risk(
+    scheme: &CrankNicolson,
+    option_type: OptionType,
+    spot: f64,
+    strike: f64,
+    maturity: f64,
+    rate: f64,
+    dividend_yield: f64,
+    surface: &LocalVolatility,
+) -> PdeRisk {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+    let columns = surface.spots.len();
+    let arguments: Vec<Number> = [spot, rate]
+        .into_iter()
+        .chain(surface.volatilities.iter().flatten().map(|v| v.result))
+        .map(Number::new)
+        .collect();
+
+    let evaluation = automatic_differentiator.derivatives(
+        |args: &[Number]| {
+            let recorded = LocalVolatility {
+                times: surface.times.clone(),
+                spots: surface.spots.clone(),
+                volatilities: args[2..].chunks(columns).map(|row| row.to_vec()).collect(),
+            };
+            scheme.price(
+                option_type,
+                args[0],
+                strike,
+                maturity,
+                args[1],
+                Number::from(dividend_yield),
+                &recorded,
+            )
+        },
+        &arguments,
+    );
+
+    PdeRisk {
+        price: evaluation.result,
+        delta: evaluation.derivative(arguments[0]),
+        rho: evaluation.derivative(arguments[1]),
+        vegas: arguments[2..]
+            .chunks(columns)
+            .map(|row| {
+                row.iter()
+                    .map(|volatility| evaluation.derivative(*volatility))
+                    .collect()
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,116 @@
+//! Risk of a book of trades priced off shared market data.
+//!
+//! Each trade is a pricer of the market inputs. The whole book is recorded
+//! once, with the market inputs as the only arguments, and a single sweep
+//! from the sum of the trade values gives the sensitivities of the book to
+//! every input. Shared inputs, and whatever the trades build from them in
+//! their own recording, are not recorded once per trade.
+
+use std::cell::RefCell;
+
+use crate::automatic_differentiator::AutomaticDifferentiator;
+use crate::number::Number;
+
+/// Value of a trade from the market inputs.
+type Pricer<'a> = Box<dyn Fn(&[Number]) -> Number + 'a>;
+
+struct Trade<'a> {
+    name: String,
+    pricer: Pricer<'a>,
+}
+
+/// A book of trades sharing the same market inputs, passed to every pricer
+/// in the same order.
+#[derive(Default)]
+pub struct Portfolio<'a> {
+    trades: Vec<Trade<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortfolioRisk {
+    /// Weighted sum of the trade values.
+    pub value: f64,
+    /// Derivative of `value` with respect to each market input, in input
+    /// order.
+    pub sensitivities: Vec<f64>,
+    /// Value of each trade, in the order the trades were added.
+    pub present_values: Vec<f64>,
+}
+
+impl<'a> Portfolio<'a> {
+    pub fn new() -> Self {
+        Portfolio { trades: Vec::new() }
+    }
+
+    /// Adds a trade valued by `pricer` from the market inputs.
+    pub fn add<P>(&mut self, name: impl Into<String>, pricer: P) -> &mut Self
+    where
+        P: Fn(&[Number]) -> Number + 'a,
+    {
+        self.trades.push(Trade {
+            name: name.into(),
+            pricer: Box::new(pricer),
+        });
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.trades.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trades.is_empty()
+    }
+
+    /// Names of the trades, in the order they were added.
+    pub fn names(&self) -> Vec<&str> {
+        self.trades
+            .iter()
+            .map(|trade| trade.name.as_str())
+            .collect()
+    }
+
+    /// Value of the book and its sensitivities to `market`.
+    pub fn risk(&self, market: &[f64]) -> PortfolioRisk {
+        self.weighted_risk(market, &vec![1.0; self.trades.len()])
+    }
+
+    /// Weighted sum of the trade values, with one weight per trade, and its
+    /// sensitivities to `market`. The present values are those of the trades
+    /// themselves, without their weights.
+    pub fn weighted_risk(&self, market: &[f64], weights: &[f64]) -> PortfolioRisk {
+        assert_eq!(
+            weights.len(),
+            self.trades.len(),
+            "weighted_risk needs one weight per trade"
+        );
+
+        let mut automatic_differentiator = AutomaticDifferentiator::new();
+        let arguments: Vec<Number> = market.iter().map(|x| Number::new(*x)).collect();
+        let present_values = RefCell::new(Vec::with_capacity(self.trades.len()));
+
+        let evaluation = automatic_differentiator.derivatives(
+            |args: &[Number]| {
+                let mut present_values = present_values.borrow_mut();
+                present_values.clear();
+                let mut value = Number::from(0.0);
+                for (trade, weight) in self.trades.iter().zip(weights) {
+                    let present_value = (trade.pricer)(args);
+                    present_values.push(present_value.result);
+                    value = value + *weight * present_value;
+                }
+                value
+            },
+            &arguments,
+        );
+
+        PortfolioRisk {
+            value: evaluation.result,
+            sensitivities: arguments
+                .iter()
+                .map(|argument| evaluation.derivative(*argument))
+                .collect(),
+            present_values: present_values.into_inner(),
+        }
+    }
+}
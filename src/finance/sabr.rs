@@ -0,0 +1,250 @@
+//! The SABR stochastic volatility model of Hagan, Kumar, Lesniewski and
+//! Woodward (2002)
+//!
+//! ```text
+//! dF = sigma F^beta dW1
+//! dsigma = nu sigma dW2,    sigma(0) = alpha,    dW1 dW2 = rho dt
+//! ```
+//!
+//! through its asymptotic Black implied volatility. Options are priced with
+//! the Black formula on that volatility, so the adjoints of a price are its
+//! sensitivities to the SABR parameters. [`calibrate`] fits alpha, rho and
+//! nu to a smile for a given beta.
+
+use crate::automatic_differentiator::AutomaticDifferentiator;
+use crate::finance::black_scholes::{self, OptionType};
+use crate::least_squares::LevenbergMarquardt;
+use crate::number::Number;
+
+/// Below this |z| the ratio z / x(z) is evaluated from its Taylor series.
+const SERIES_THRESHOLD: f64 = 1e-4;
+
+/// Levenberg-Marquardt iterations of [`calibrate`].
+const MAX_ITERATIONS: usize = 200;
+
+/// [`calibrate`] stops once the root mean square error is below this.
+const VOLATILITY_TOLERANCE: f64 = 1e-12;
+
+/// SABR parameters: initial volatility `alpha`, CEV exponent `beta` in
+/// [0, 1], correlation `rho` and volatility of volatility `nu`.
+#[derive(Debug, Clone, Copy)]
+pub struct Sabr {
+    pub alpha: Number,
+    pub beta: Number,
+    pub rho: Number,
+    pub nu: Number,
+}
+
+impl Sabr {
+    pub fn new<A, B, R, N>(alpha: A, beta: B, rho: R, nu: N) -> Self
+    where
+        A: Into<Number>,
+        B: Into<Number>,
+        R: Into<Number>,
+        N: Into<Number>,
+    {
+        Sabr {
+            alpha: alpha.into(),
+            beta: beta.into(),
+            rho: rho.into(),
+            nu: nu.into(),
+        }
+    }
+
+    /// Hagan's Black implied volatility of the option struck at `strike`
+    /// on `forward`, expiring in `maturity` years.
+    pub fn implied_volatility(&self, forward: Number, strike: Number, maturity: f64) -> Number {
+        let (alpha, beta, rho, nu) = (self.alpha, self.beta, self.rho, self.nu);
+        let one_minus_beta = 1.0 - beta;
+        let log_moneyness = (forward / strike).ln();
+        let log_moneyness_squared = log_moneyness * log_moneyness;
+        let one_minus_beta_squared = one_minus_beta * one_minus_beta;
+        // (F K)^((1 - beta) / 2)
+        let geometric_mean = (forward * strike).powf(0.5 * one_minus_beta);
+
+        let denominator = geometric_mean
+            * (1.0
+                + one_minus_beta_squared / 24.0 * log_moneyness_squared
+                + one_minus_beta_squared * one_minus_beta_squared / 1920.0
+                    * log_moneyness_squared
+                    * log_moneyness_squared);
+        let z = nu / alpha * geometric_mean * log_moneyness;
+        let correction = 1.0
+            + (one_minus_beta_squared / 24.0 * alpha * alpha / (geometric_mean * geometric_mean)
+                + 0.25 * rho * beta * nu * alpha / geometric_mean
+                + (2.0 - 3.0 * rho * rho) / 24.0 * nu * nu)
+                * maturity;
+
+        alpha / denominator * z_over_x(z, rho) * correction
+    }
+
+    /// Black price of a European option with the SABR implied volatility,
+    /// discounted at `rate`.
+    pub fn price(
+        &self,
+        option_type: OptionType,
+        forward: Number,
+        strike: f64,
+        maturity: f64,
+        rate: Number,
+    ) -> Number {
+        let strike = Number::from(strike);
+        black_scholes::black_price(
+            option_type,
+            forward,
+            strike,
+            Number::from(maturity),
+            rate,
+            self.implied_volatility(forward, strike, maturity),
+        )
+    }
+}
+
+/// z / x(z) with x(z) = ln((sqrt(1 - 2 rho z + z^2) + z - rho) / (1 - rho)).
+/// Both vanish at the money, where the ratio tends to 1: close to it the
+/// Taylor series replaces the 0 / 0.
+fn z_over_x(z: Number, rho: Number) -> Number {
+    if z.result.abs() < SERIES_THRESHOLD {
+        let rho_squared = rho * rho;
+        return 1.0 - 0.5 * rho * z
+            + (2.0 - 3.0 * rho_squared) / 12.0 * z * z
+            + rho * (5.0 - 6.0 * rho_squared) / 24.0 * z * z * z;
+    }
+    let x = (((1.0 - 2.0 * rho * z + z * z).sqrt() + z - rho) / (1.0 - rho)).ln();
+    z / x
+}
+
+/// SABR parameters as plain values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SabrParameters {
+    pub alpha: f64,
+    pub beta: f64,
+    pub rho: f64,
+    pub nu: f64,
+}
+
+/// Price of an option and its derivatives with respect to the forward and
+/// to each SABR parameter, the vega by parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SabrSensitivities {
+    pub price: f64,
+    /// dV/dF, with the smile moving with the forward
+    pub delta: f64,
+    /// dV/dalpha
+    pub alpha: f64,
+    /// dV/dbeta
+    pub beta: f64,
+    /// dV/drho
+    pub rho: f64,
+    /// dV/dnu
+    pub nu: f64,
+}
+
+/// Price and sensitivities of a European option under SABR, from one
+/// recording of the price.
+pub fn sensitivities(
+    parameters: &SabrParameters,
+    option_type: OptionType,
+    forward: f64,
+    strike: f64,
+    maturity: f64,
+    rate: f64,
+) -> SabrSensitivities {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+    let arguments: Vec<Number> = [
+        forward,
+        parameters.alpha,
+        parameters.beta,
+        parameters.rho,
+        parameters.nu,
+    ]
+    .iter()
+    .map(|x| Number::new(*x))
+    .collect();
+
+    let evaluation = automatic_differentiator.derivatives(
+        |args: &[Number]| {
+            Sabr::new(args[1], args[2], args[3], args[4]).price(
+                option_type,
+                args[0],
+                strike,
+                maturity,
+                Number::from(rate),
+            )
+        },
+        &arguments,
+    );
+
+    SabrSensitivities {
+        price: evaluation.result,
+        delta: evaluation.derivative(arguments[0]),
+        alpha: evaluation.derivative(arguments[1]),
+        beta: evaluation.derivative(arguments[2]),
+        rho: evaluation.derivative(arguments[3]),
+        nu: evaluation.derivative(arguments[4]),
+    }
+}
+
+/// A market Black volatility of a strike.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VolatilityQuote {
+    pub strike: f64,
+    pub volatility: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmileCalibration {
+    pub parameters: SabrParameters,
+    /// Root mean square of the volatility errors at `parameters`.
+    pub root_mean_square_error: f64,
+    pub iterations: usize,
+}
+
+/// Fits alpha, rho and nu to the smile `quotes` of one maturity by least
+/// squares on implied volatilities, starting from `initial_guess` and keeping
+/// its beta. The search runs on ln(alpha), atanh(rho) and ln(nu) with the
+/// Jacobian of the volatility errors from the adjoints of each recorded
+/// volatility.
+pub fn calibrate(
+    forward: f64,
+    maturity: f64,
+    quotes: &[VolatilityQuote],
+    initial_guess: SabrParameters,
+) -> SmileCalibration {
+    let beta = initial_guess.beta;
+    let volatility_errors = |args: &[Number]| {
+        let sabr = Sabr::new(args[0].exp(), beta, args[1].tanh(), args[2].exp());
+        quotes
+            .iter()
+            .map(|quote| {
+                sabr.implied_volatility(Number::from(forward), Number::from(quote.strike), maturity)
+                    - quote.volatility
+            })
+            .collect()
+    };
+
+    let fit = LevenbergMarquardt {
+        max_iterations: MAX_ITERATIONS,
+        residual_tolerance: VOLATILITY_TOLERANCE,
+        ..LevenbergMarquardt::new()
+    }
+    .minimize(
+        volatility_errors,
+        &[
+            initial_guess.alpha.ln(),
+            initial_guess.rho.atanh(),
+            initial_guess.nu.ln(),
+        ],
+    );
+
+    SmileCalibration {
+        parameters: SabrParameters {
+            alpha: fit.x[0].exp(),
+            beta,
+            rho: fit.x[1].tanh(),
+            nu: fit.x[2].exp(),
+        },
+        root_mean_square_error: (fit.sum_of_squares / quotes.len() as f64).sqrt(),
+        iterations: fit.iterations,
+    }
+}
@@ -0,0 +1,243 @@
+//! Levenberg-Marquardt and Gauss-Newton for nonlinear least squares.
+//!
+//! The residuals r(p) are recorded once per iteration with the parameters as
+//! arguments, and a sweep from each residual gives a row of the Jacobian J.
+//! The step solves the damped normal equations
+//!
+//! ```text
+//! (J^T J + lambda diag(J^T J)) dp = -J^T r
+//! ```
+//!
+//! where J^T r is the gradient of half the sum of squares. With lambda zero
+//! this is the Gauss-Newton step. Trial steps are evaluated without the
+//! tape: the damping falls after a step that lowers the sum of squares and
+//! rises until one does.
+//!
+//! At the fit the covariance of the parameters is estimated as
+//! (J^T J)^-1 s^2, with s^2 the sum of squares over the degrees of freedom.
+
+use crate::automatic_differentiator::AutomaticDifferentiator;
+use crate::linear_algebra::{inverse, norm, solve};
+use crate::number::Number;
+
+/// Why the fit stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    /// The root mean square residual fell below the tolerance.
+    ResidualTolerance,
+    /// The gradient J^T r fell below the tolerance.
+    GradientTolerance,
+    MaxIterations,
+    /// No damping lowered the sum of squares any more.
+    NoImprovement,
+}
+
+/// Diagnostics of one iteration, at the parameters it ends with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Iteration {
+    pub sum_of_squares: f64,
+    /// Euclidean norm of J^T r at the start of the iteration.
+    pub gradient_norm: f64,
+    /// Damping of the step taken.
+    pub damping: f64,
+    /// Euclidean norm of the step taken.
+    pub step_norm: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeastSquares {
+    pub x: Vec<f64>,
+    pub residuals: Vec<f64>,
+    pub sum_of_squares: f64,
+    /// Jacobian of the residuals at `x`, one row per residual.
+    pub jacobian: Vec<Vec<f64>>,
+    /// Sum of squares over the residuals in excess of the parameters, NaN
+    /// without any.
+    pub residual_variance: f64,
+    /// (J^T J)^-1 times `residual_variance`.
+    pub covariance: Vec<Vec<f64>>,
+    pub iterations: usize,
+    pub termination: Termination,
+    pub history: Vec<Iteration>,
+}
+
+impl LeastSquares {
+    /// Square roots of the diagonal of `covariance`.
+    pub fn standard_errors(&self) -> Vec<f64> {
+        self.covariance
+            .iter()
+            .enumerate()
+            .map(|(i, row)| row[i].sqrt())
+            .collect()
+    }
+}
+
+/// A damped Gauss-Newton solver. [`LevenbergMarquardt::new`] sets the usual
+/// defaults, which can be changed through the public fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevenbergMarquardt {
+    /// Damping of the first step, zero to start from Gauss-Newton steps.
+    pub initial_damping: f64,
+    pub max_iterations: usize,
+    /// Stops once the root mean square residual is below.
+    pub residual_tolerance: f64,
+    /// Stops once the norm of J^T r is below.
+    pub gradient_tolerance: f64,
+}
+
+impl Default for LevenbergMarquardt {
+    fn default() -> Self {
+        LevenbergMarquardt::new()
+    }
+}
+
+impl LevenbergMarquardt {
+    pub fn new() -> Self {
+        LevenbergMarquardt {
+            initial_damping: 1e-3,
+            max_iterations: 100,
+            residual_tolerance: 1e-12,
+            gradient_tolerance: 1e-12,
+        }
+    }
+
+    /// Undamped steps for as long as they lower the sum of squares.
+    pub fn gauss_newton() -> Self {
+        LevenbergMarquardt {
+            initial_damping: 0.0,
+            ..LevenbergMarquardt::new()
+        }
+    }
+
+    /// Minimises the sum of the squares of `residuals`, starting from
+    /// `initial`.
+    pub fn minimize<R>(&self, residuals: R, initial: &[f64]) -> LeastSquares
+    where
+        R: Fn(&[Number]) -> Vec<Number>,
+    {
+        let sum_of_squares = |x: &[f64]| -> f64 {
+            crate::no_tape(|| {
+                let args: Vec<Number> = x.iter().map(|x| Number::new(*x)).collect();
+                residuals(&args).iter().map(|r| r.result.powi(2)).sum()
+            })
+        };
+
+        let mut automatic_differentiator = AutomaticDifferentiator::new();
+        let mut x = initial.to_vec();
+        let (mut values, mut jacobian) = linearize(&mut automatic_differentiator, &residuals, &x);
+        let count = values.len();
+        let mut cost: f64 = values.iter().map(|r| r * r).sum();
+        let mut damping = self.initial_damping;
+        let mut history = Vec::new();
+
+        let termination = loop {
+            let (normal_matrix, gradient) = normal_equations(&jacobian, &values);
+            if (cost / count as f64).sqrt() <= self.residual_tolerance {
+                break Termination::ResidualTolerance;
+            }
+            let gradient_norm = norm(&gradient);
+            if gradient_norm <= self.gradient_tolerance {
+                break Termination::GradientTolerance;
+            }
+            if history.len() == self.max_iterations {
+                break Termination::MaxIterations;
+            }
+
+            // raise the damping until the step lowers the objective
+            let mut accepted = None;
+            while damping < 1e12 {
+                let mut system = normal_matrix.clone();
+                for (j, row) in system.iter_mut().enumerate() {
+                    row[j] += damping * normal_matrix[j][j].max(1e-12);
+                }
+                let step = solve(system, gradient.iter().map(|g| -g).collect());
+                let trial: Vec<f64> = x.iter().zip(&step).map(|(x, dx)| x + dx).collect();
+                let trial_cost = sum_of_squares(&trial);
+                if trial_cost < cost {
+                    accepted = Some((trial, trial_cost, norm(&step)));
+                    break;
+                }
+                damping = if damping > 0.0 { 4.0 * damping } else { 1e-3 };
+            }
+            let Some((trial, trial_cost, step_norm)) = accepted else {
+                break Termination::NoImprovement;
+            };
+
+            history.push(Iteration {
+                sum_of_squares: trial_cost,
+                gradient_norm,
+                damping,
+                step_norm,
+            });
+            if damping > 0.0 {
+                damping = (damping / 3.0).max(1e-12);
+            }
+            x = trial;
+            cost = trial_cost;
+            (values, jacobian) = linearize(&mut automatic_differentiator, &residuals, &x);
+        };
+
+        let (normal_matrix, _) = normal_equations(&jacobian, &values);
+        let degrees_of_freedom = count.saturating_sub(x.len());
+        let residual_variance = if degrees_of_freedom > 0 {
+            cost / degrees_of_freedom as f64
+        } else {
+            f64::NAN
+        };
+        let covariance = inverse(&normal_matrix)
+            .into_iter()
+            .map(|row| row.into_iter().map(|c| c * residual_variance).collect())
+            .collect();
+
+        LeastSquares {
+            x,
+            residuals: values,
+            sum_of_squares: cost,
+            jacobian,
+            residual_variance,
+            covariance,
+            iterations: history.len(),
+            termination,
+            history,
+        }
+    }
+}
+
+/// Residuals and their Jacobian at `x`, from one recording.
+fn linearize<R>(
+    automatic_differentiator: &mut AutomaticDifferentiator,
+    residuals: &R,
+    x: &[f64],
+) -> (Vec<f64>, Vec<Vec<f64>>)
+where
+    R: Fn(&[Number]) -> Vec<Number>,
+{
+    let arguments: Vec<Number> = x.iter().map(|x| Number::new(*x)).collect();
+    automatic_differentiator
+        .jacobian(residuals, &arguments)
+        .into_iter()
+        .map(|evaluation| {
+            let row = arguments
+                .iter()
+                .map(|argument| evaluation.derivative(*argument))
+                .collect();
+            (evaluation.result, row)
+        })
+        .unzip()
+}
+
+/// J^T J and J^T r.
+fn normal_equations(jacobian: &[Vec<f64>], residuals: &[f64]) -> (Vec<Vec<f64>>, Vec<f64>) {
+    let n = jacobian.first().map_or(0, |row| row.len());
+    let mut normal_matrix = vec![vec![0.0; n]; n];
+    let mut gradient = vec![0.0; n];
+    for (row, residual) in jacobian.iter().zip(residuals) {
+        for (j, row_j) in row.iter().enumerate() {
+            gradient[j] += row_j * residual;
+            for (k, row_k) in row.iter().enumerate() {
+                normal_matrix[j][k] += row_j * row_k;
+            }
+        }
+    }
+    (normal_matrix, gradient)
+}
@@ -1,7 +1,16 @@
 pub mod automatic_differentiator;
+pub mod differential_ml;
+pub mod distributions;
+pub mod finance;
 mod global_counter;
+pub mod least_squares;
+pub mod linear_algebra;
 pub mod number;
 pub mod operation;
+pub mod optim;
+pub mod random;
+pub mod root_finding;
 mod shared_data_communication_channel;
+mod special_functions;
 
 pub use number::no_tape;
@@ -0,0 +1,276 @@
+//! Linear algebra on `Number`s recorded as block operations: a whole solve or
+//! factorization is one node on the tape with an adjoint of its own, instead
+//! of the elementwise operations of the algorithm. Dense solves on `f64`s for
+//! the solvers and regressions of the crate live here too.
+
+use crate::number::Number;
+use crate::operation::Operation;
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+/// Ids of the coefficients and the right hand side of a recorded tridiagonal
+/// system. `lower[i]` is the entry (i + 1, i) of the matrix and `upper[i]`
+/// the entry (i, i + 1).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TridiagonalSystem {
+    pub lower: Vec<i64>,
+    pub diagonal: Vec<i64>,
+    pub upper: Vec<i64>,
+    pub rhs: Vec<i64>,
+}
+
+/// Ids of the lower triangle of a recorded symmetric matrix: `matrix[i][j]`
+/// is the entry (i, j) for j <= i. The entries above the diagonal are the
+/// same numbers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CholeskyFactorization {
+    pub matrix: Vec<Vec<i64>>,
+}
+
+/// Solves the tridiagonal system A x = rhs with the Thomas algorithm.
+/// `lower` and `upper` hold the n - 1 entries below and above the diagonal.
+///
+/// The solve is recorded as one `TridiagonalSolve` node taking all the
+/// coefficients, and one `TridiagonalSolution` node per entry of x. The
+/// reverse sweep solves the transposed system once for lambda = A^-T x_,
+/// then rhs_ = lambda and A_ = -lambda x^T on the three diagonals.
+pub fn solve_tridiagonal(
+    lower: &[Number],
+    diagonal: &[Number],
+    upper: &[Number],
+    rhs: &[Number],
+) -> Vec<Number> {
+    let n = diagonal.len();
+    assert!(
+        n > 0 && lower.len() == n - 1 && upper.len() == n - 1 && rhs.len() == n,
+        "a tridiagonal system of size n has n - 1 lower and upper entries and n right hand sides"
+    );
+
+    let values = |numbers: &[Number]| -> Vec<f64> { numbers.iter().map(|x| x.result).collect() };
+    let solution = thomas(
+        &values(lower),
+        &values(diagonal),
+        &values(upper),
+        &values(rhs),
+    );
+
+    let ids = |numbers: &[Number]| -> Vec<i64> { numbers.iter().map(|x| x.id).collect() };
+    let arguments: Vec<Number> = [lower, diagonal, upper, rhs].concat();
+    let system = Number::record(0.0, &arguments, |id, res| {
+        Operation::TridiagonalSolve(
+            id,
+            Arc::new(TridiagonalSystem {
+                lower: ids(lower),
+                diagonal: ids(diagonal),
+                upper: ids(upper),
+                rhs: ids(rhs),
+            }),
+            res,
+            0.0,
+        )
+    });
+
+    solution
+        .iter()
+        .enumerate()
+        .map(|(index, x)| {
+            Number::record(*x, &[system], |id, res| {
+                Operation::TridiagonalSolution(id, system.id, index, res, 0.0)
+            })
+        })
+        .collect()
+}
+
+/// Thomas algorithm: Gaussian elimination without pivoting, stable for
+/// diagonally dominant systems.
+pub(crate) fn thomas(lower: &[f64], diagonal: &[f64], upper: &[f64], rhs: &[f64]) -> Vec<f64> {
+    let n = diagonal.len();
+    let mut modified_upper = vec![0.0; n];
+    let mut modified_rhs = vec![0.0; n];
+
+    modified_upper[0] = if n > 1 { upper[0] / diagonal[0] } else { 0.0 };
+    modified_rhs[0] = rhs[0] / diagonal[0];
+    for i in 1..n {
+        let pivot = diagonal[i] - lower[i - 1] * modified_upper[i - 1];
+        if i < n - 1 {
+            modified_upper[i] = upper[i] / pivot;
+        }
+        modified_rhs[i] = (rhs[i] - lower[i - 1] * modified_rhs[i - 1]) / pivot;
+    }
+
+    let mut x = modified_rhs;
+    for i in (0..n - 1).rev() {
+        x[i] -= modified_upper[i] * x[i + 1];
+    }
+    x
+}
+
+/// Cholesky factorization A = L L^T of the symmetric positive definite
+/// `matrix`, of which only the lower triangle is read. Returns L as a full
+/// square matrix, with zero constants above the diagonal.
+///
+/// The factorization is recorded as one `Cholesky` node taking the lower
+/// triangle of A, and one `CholeskyFactor` node per entry of the lower
+/// triangle of L. The reverse sweep forms P = Phi(L^T L_), Phi keeping the
+/// lower triangle with half the diagonal, then A_ = L^-T (P + P^T) / 2 L^-1,
+/// whose entries below the diagonal count twice as they stand for both
+/// (i, j) and (j, i).
+pub fn cholesky(matrix: &[Vec<Number>]) -> Vec<Vec<Number>> {
+    let n = matrix.len();
+    assert!(
+        matrix.iter().all(|row| row.len() == n),
+        "the matrix of a Cholesky factorization must be square"
+    );
+
+    let lower_triangle: Vec<Vec<Number>> = matrix
+        .iter()
+        .enumerate()
+        .map(|(i, row)| row[..=i].to_vec())
+        .collect();
+    let values: Vec<Vec<f64>> = lower_triangle
+        .iter()
+        .map(|row| row.iter().map(|x| x.result).collect())
+        .collect();
+    let factor = cholesky_values(&values).expect("the matrix is not positive definite");
+
+    let factorization = Number::record(0.0, &lower_triangle.concat(), |id, res| {
+        Operation::Cholesky(
+            id,
+            Arc::new(CholeskyFactorization {
+                matrix: lower_triangle
+                    .iter()
+                    .map(|row| row.iter().map(|x| x.id).collect())
+                    .collect(),
+            }),
+            res,
+            0.0,
+        )
+    });
+
+    (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| {
+                    if j > i {
+                        Number::from(0.0)
+                    } else {
+                        Number::record(factor[i][j], &[factorization], |id, res| {
+                            Operation::CholeskyFactor(id, factorization.id, i, j, res, 0.0)
+                        })
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Cholesky-Banachiewicz factorization of the matrix whose lower triangle is
+/// `lower_triangle`, row by row. The rows of L hold the entries up to the
+/// diagonal. None unless the matrix is positive definite.
+pub(crate) fn cholesky_values(lower_triangle: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let n = lower_triangle.len();
+    let mut factor: Vec<Vec<f64>> = (0..n).map(|i| vec![0.0; i + 1]).collect();
+    for i in 0..n {
+        for j in 0..=i {
+            let sum: f64 = (0..j).map(|k| factor[i][k] * factor[j][k]).sum();
+            let entry = lower_triangle[i][j] - sum;
+            if i == j {
+                if entry.is_nan() || entry <= 0.0 {
+                    return None;
+                }
+                factor[i][i] = entry.sqrt();
+            } else {
+                factor[i][j] = entry / factor[j][j];
+            }
+        }
+    }
+    Some(factor)
+}
+
+/// Adjoint of the symmetric matrix factorized into `factor`, from the adjoint
+/// of the factor, both given by their lower triangles: A_ = L^-T S L^-1 with
+/// S the symmetric part of Phi(L^T L_).
+pub(crate) fn cholesky_adjoint(factor: &[Vec<f64>], factor_adjoint: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let n = factor.len();
+    let l = |i: usize, j: usize| if j <= i { factor[i][j] } else { 0.0 };
+    let l_adjoint = |i: usize, j: usize| if j <= i { factor_adjoint[i][j] } else { 0.0 };
+
+    // P = Phi(L^T L_) and its symmetric part S
+    let p = |i: usize, j: usize| {
+        let product: f64 = (i..n).map(|k| l(k, i) * l_adjoint(k, j)).sum();
+        match j.cmp(&i) {
+            Ordering::Less => product,
+            Ordering::Equal => 0.5 * product,
+            Ordering::Greater => 0.0,
+        }
+    };
+    let s: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n).map(|j| 0.5 * (p(i, j) + p(j, i))).collect())
+        .collect();
+
+    // L^-T S, then (L^-T (L^-T S)^T)^T = L^-T S L^-1 as S is symmetric
+    let left = solve_upper_transposed(l, &s);
+    let transposed: Vec<Vec<f64>> = (0..n)
+        .map(|j| (0..n).map(|i| left[i][j]).collect())
+        .collect();
+    let adjoint = solve_upper_transposed(l, &transposed);
+    (0..n)
+        .map(|i| (0..n).map(|j| adjoint[j][i]).collect())
+        .collect()
+}
+
+/// Solves L^T X = B by back substitution, column by column.
+fn solve_upper_transposed(l: impl Fn(usize, usize) -> f64 + Copy, b: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let n = b.len();
+    let mut x = vec![vec![0.0; n]; n];
+    for column in 0..n {
+        for i in (0..n).rev() {
+            let sum: f64 = (i + 1..n).map(|k| l(k, i) * x[k][column]).sum();
+            x[i][column] = (b[i][column] - sum) / l(i, i);
+        }
+    }
+    x
+}
+
+/// Euclidean norm of `v`.
+pub(crate) fn norm(v: &[f64]) -> f64 {
+    v.iter().map(|v| v * v).sum::<f64>().sqrt()
+}
+
+/// Inverse of `a`, column by column.
+pub(crate) fn inverse(a: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let n = a.len();
+    let columns: Vec<Vec<f64>> = (0..n)
+        .map(|j| solve(a.to_vec(), (0..n).map(|i| f64::from(i == j)).collect()))
+        .collect();
+    (0..n)
+        .map(|i| columns.iter().map(|column| column[i]).collect())
+        .collect()
+}
+
+/// Solves `a x = b` by Gaussian elimination with partial pivoting.
+pub(crate) fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Vec<f64> {
+    let n = b.len();
+    for column in 0..n {
+        let pivot = (column..n)
+            .max_by(|i, j| a[*i][column].abs().total_cmp(&a[*j][column].abs()))
+            .unwrap();
+        a.swap(column, pivot);
+        b.swap(column, pivot);
+        let (upper, lower) = a.split_at_mut(column + 1);
+        let pivot_row = &upper[column];
+        for (offset, row) in lower.iter_mut().enumerate() {
+            let factor = row[column] / pivot_row[column];
+            for (value, pivot_value) in row.iter_mut().zip(pivot_row).skip(column) {
+                *value -= factor * pivot_value;
+            }
+            b[column + 1 + offset] -= factor * b[column];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let tail: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - tail) / a[row][row];
+    }
+    x
+}
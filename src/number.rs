@@ -1,20 +1,23 @@
 use crate::operation::Operation;
-use statrs::distribution::{ContinuousCDF, Normal};
+use statrs::function::{erf, gamma};
 use std::cell::Cell;
+use std::cmp::Ordering;
 use std::fmt;
 use std::fmt::Display;
 use std::ops::Add;
 use std::ops::Div;
 use std::ops::Mul;
 use std::ops::Sub;
+use std::sync::Arc;
 
 use crate::global_counter::OPERATION_ID_COUNTER;
 use crate::shared_data_communication_channel;
+use crate::special_functions;
 
 thread_local! {
     /// When `false`, all `Number` arithmetic skips tape recording and runs as
     /// plain f64.  Set via [`no_tape`].
-    static RECORDING: Cell<bool> = Cell::new(true);
+    static RECORDING: Cell<bool> = const { Cell::new(true) };
 }
 
 /// Run `f` with the AAD tape disabled for the current thread.
@@ -24,7 +27,8 @@ thread_local! {
 /// mutexes are acquired.  This gives the same speed as plain `f64` arithmetic
 /// while keeping all call sites unchanged.
 ///
-/// The tape is re-enabled when the closure returns (even if it panics).
+/// The previous recording state is restored when the closure returns (even
+/// if it panics), so calls can be nested.
 ///
 /// # Example
 /// ```ignore
@@ -35,15 +39,14 @@ pub fn no_tape<F, T>(f: F) -> T
 where
     F: FnOnce() -> T,
 {
-    RECORDING.with(|r| r.set(false));
     // Use a guard so the flag is restored even on panic.
-    struct Guard;
+    struct Guard(bool);
     impl Drop for Guard {
         fn drop(&mut self) {
-            RECORDING.with(|r| r.set(true));
+            RECORDING.with(|r| r.set(self.0));
         }
     }
-    let _guard = Guard;
+    let _guard = Guard(RECORDING.with(|r| r.replace(false)));
     f()
 }
 
@@ -319,142 +322,673 @@ impl Div<Number> for f64 {
 }
 
 impl Number {
-    pub fn ln(self) -> Number {
-        let result: Number = Number::new_non_leaf(self.result.ln());
-        let op = Operation::Ln(result.id, self.id, result.result, 0.0);
-        shared_data_communication_channel::global_register_operation(op);
+    fn untaped(val: f64) -> Self {
+        Number {
+            result: val,
+            id: 0,
+            leaf: false,
+        }
+    }
+
+    /// Records a node with primal value `val` computed from `args`.
+    ///
+    /// `op` receives the id assigned to the new node together with `val` and
+    /// builds the operation stored on the tape.  Leaf arguments get their
+    /// `Value` operation registered, exactly as the arithmetic operators do.
+    pub(crate) fn record<F>(val: f64, args: &[Number], op: F) -> Number
+    where
+        F: FnOnce(i64, f64) -> Operation,
+    {
+        if !recording() {
+            return Number::untaped(val);
+        }
+        let result: Number = Number::new_non_leaf(val);
+        shared_data_communication_channel::global_register_operation(op(result.id, result.result));
         shared_data_communication_channel::global_add_parent_child_relationship(
             result.id,
-            vec![self.id],
+            args.iter().map(|arg| arg.id).collect(),
         );
 
-        if self.leaf {
-            let val_op = Operation::Value(self.id, self.result, 0.0);
+        for arg in args.iter().filter(|arg| arg.leaf) {
+            let val_op = Operation::Value(arg.id, arg.result, 0.0);
             shared_data_communication_channel::global_register_operation(val_op);
         }
 
         result
     }
 
+    pub fn ln(self) -> Number {
+        Number::record(self.result.ln(), &[self], |id, res| {
+            Operation::Ln(id, self.id, res, 0.0)
+        })
+    }
+
     pub fn sin(self) -> Number {
-        let result: Number = Number::new_non_leaf(self.result.sin());
-        let op = Operation::Sin(result.id, self.id, result.result, 0.0);
-        shared_data_communication_channel::global_register_operation(op);
-        shared_data_communication_channel::global_add_parent_child_relationship(
-            result.id,
-            vec![self.id],
-        );
+        Number::record(self.result.sin(), &[self], |id, res| {
+            Operation::Sin(id, self.id, res, 0.0)
+        })
+    }
 
-        if self.leaf {
-            let val_op = Operation::Value(self.id, self.result, 0.0);
-            shared_data_communication_channel::global_register_operation(val_op);
-        }
+    pub fn cos(self) -> Number {
+        Number::record(self.result.cos(), &[self], |id, res| {
+            Operation::Cos(id, self.id, res, 0.0)
+        })
+    }
 
-        result
+    pub fn exp(self) -> Number {
+        Number::record(self.result.exp(), &[self], |id, res| {
+            Operation::Exp(id, self.id, res, 0.0)
+        })
     }
 
-    pub fn cos(self) -> Number {
-        let result: Number = Number::new_non_leaf(self.result.cos());
-        let op = Operation::Cos(result.id, self.id, result.result, 0.0);
-        shared_data_communication_channel::global_register_operation(op);
-        shared_data_communication_channel::global_add_parent_child_relationship(
-            result.id,
-            vec![self.id],
-        );
+    pub fn pow(self, n: f64) -> Number {
+        Number::record(self.result.powf(n), &[self], |id, res| {
+            Operation::Pow(id, self.id, n, res, 0.0)
+        })
+    }
 
-        if self.leaf {
-            let val_op = Operation::Value(self.id, self.result, 0.0);
-            shared_data_communication_channel::global_register_operation(val_op);
-        }
+    pub fn sqrt(self) -> Number {
+        Number::record(self.result.sqrt(), &[self], |id, res| {
+            Operation::Sqrt(id, self.id, res, 0.0)
+        })
+    }
 
-        result
+    pub fn log(self, b: f64) -> Number {
+        Number::record(self.result.log(b), &[self], |id, res| {
+            Operation::Log(id, self.id, b, res, 0.0)
+        })
     }
 
-    pub fn exp(self) -> Number {
-        let result: Number = Number::new_non_leaf(self.result.exp());
-        let op = Operation::Exp(result.id, self.id, result.result, 0.0);
-        shared_data_communication_channel::global_register_operation(op);
-        shared_data_communication_channel::global_add_parent_child_relationship(
-            result.id,
-            vec![self.id],
-        );
+    pub fn cdf(self) -> Number {
+        Number::record(
+            special_functions::norm_cdf(self.result),
+            &[self],
+            |id, res| Operation::Cdf(id, self.id, res, 0.0),
+        )
+    }
 
-        if self.leaf {
-            let val_op = Operation::Value(self.id, self.result, 0.0);
-            shared_data_communication_channel::global_register_operation(val_op);
-        }
+    pub fn tan(self) -> Number {
+        Number::record(self.result.tan(), &[self], |id, res| {
+            Operation::Tan(id, self.id, res, 0.0)
+        })
+    }
 
-        result
+    pub fn asin(self) -> Number {
+        Number::record(self.result.asin(), &[self], |id, res| {
+            Operation::Asin(id, self.id, res, 0.0)
+        })
     }
 
-    pub fn pow(self, n: f64) -> Number {
-        let result: Number = Number::new_non_leaf(self.result.powf(n));
-        let op = Operation::Pow(result.id, self.id, n, result.result, 0.0);
-        shared_data_communication_channel::global_register_operation(op);
-        shared_data_communication_channel::global_add_parent_child_relationship(
-            result.id,
-            vec![self.id],
-        );
+    pub fn acos(self) -> Number {
+        Number::record(self.result.acos(), &[self], |id, res| {
+            Operation::Acos(id, self.id, res, 0.0)
+        })
+    }
 
-        if self.leaf {
-            let val_op = Operation::Value(self.id, self.result, 0.0);
-            shared_data_communication_channel::global_register_operation(val_op);
-        }
+    pub fn atan(self) -> Number {
+        Number::record(self.result.atan(), &[self], |id, res| {
+            Operation::Atan(id, self.id, res, 0.0)
+        })
+    }
 
-        result
+    /// Four-quadrant arctangent of `self / x`, as [`f64::atan2`].
+    pub fn atan2<T: Into<Number>>(self, x: T) -> Number {
+        let x = x.into();
+        Number::record(self.result.atan2(x.result), &[self, x], |id, res| {
+            Operation::Atan2(id, self.id, x.id, res, 0.0)
+        })
     }
 
-    pub fn sqrt(self) -> Number {
-        let result: Number = Number::new_non_leaf(self.result.sqrt());
-        let op = Operation::Sqrt(result.id, self.id, result.result, 0.0);
-        shared_data_communication_channel::global_register_operation(op);
-        shared_data_communication_channel::global_add_parent_child_relationship(
-            result.id,
-            vec![self.id],
-        );
+    pub fn sinh(self) -> Number {
+        Number::record(self.result.sinh(), &[self], |id, res| {
+            Operation::Sinh(id, self.id, res, 0.0)
+        })
+    }
 
-        if self.leaf {
-            let val_op = Operation::Value(self.id, self.result, 0.0);
-            shared_data_communication_channel::global_register_operation(val_op);
+    pub fn cosh(self) -> Number {
+        Number::record(self.result.cosh(), &[self], |id, res| {
+            Operation::Cosh(id, self.id, res, 0.0)
+        })
+    }
+
+    pub fn tanh(self) -> Number {
+        Number::record(self.result.tanh(), &[self], |id, res| {
+            Operation::Tanh(id, self.id, res, 0.0)
+        })
+    }
+
+    pub fn asinh(self) -> Number {
+        Number::record(self.result.asinh(), &[self], |id, res| {
+            Operation::Asinh(id, self.id, res, 0.0)
+        })
+    }
+
+    pub fn acosh(self) -> Number {
+        Number::record(self.result.acosh(), &[self], |id, res| {
+            Operation::Acosh(id, self.id, res, 0.0)
+        })
+    }
+
+    pub fn atanh(self) -> Number {
+        Number::record(self.result.atanh(), &[self], |id, res| {
+            Operation::Atanh(id, self.id, res, 0.0)
+        })
+    }
+
+    pub fn exp2(self) -> Number {
+        Number::record(self.result.exp2(), &[self], |id, res| {
+            Operation::Exp2(id, self.id, res, 0.0)
+        })
+    }
+
+    pub fn exp_m1(self) -> Number {
+        Number::record(self.result.exp_m1(), &[self], |id, res| {
+            Operation::Expm1(id, self.id, res, 0.0)
+        })
+    }
+
+    pub fn ln_1p(self) -> Number {
+        Number::record(self.result.ln_1p(), &[self], |id, res| {
+            Operation::Ln1p(id, self.id, res, 0.0)
+        })
+    }
+
+    pub fn cbrt(self) -> Number {
+        Number::record(self.result.cbrt(), &[self], |id, res| {
+            Operation::Cbrt(id, self.id, res, 0.0)
+        })
+    }
+
+    /// `sqrt(self^2 + rhs^2)` without intermediate overflow. At the origin the
+    /// derivative is taken to be `0` with respect to both arguments.
+    pub fn hypot<T: Into<Number>>(self, rhs: T) -> Number {
+        let rhs = rhs.into();
+        Number::record(self.result.hypot(rhs.result), &[self, rhs], |id, res| {
+            Operation::Hypot(id, self.id, rhs.id, res, 0.0)
+        })
+    }
+
+    /// `self` raised to the power `exp`, differentiable in both arguments.
+    ///
+    /// The partial derivatives are `exp * self^(exp - 1)` and `self^exp * ln(self)`.
+    /// Where these formulas produce `0 * inf` the limit of the function along the
+    /// valid domain is used instead: the derivative with respect to `self` is `0`
+    /// when `exp == 0`, and the derivative with respect to `exp` is `0` when
+    /// `self == 0`. For `self < 0` the derivative with respect to `exp` is NaN.
+    pub fn powf<T: Into<Number>>(self, exp: T) -> Number {
+        let exp = exp.into();
+        Number::record(self.result.powf(exp.result), &[self, exp], |id, res| {
+            Operation::Powf(id, self.id, exp.id, res, 0.0)
+        })
+    }
+
+    /// Logarithm of `self` with respect to `base`, differentiable in both arguments.
+    ///
+    /// The partial derivatives are `1 / (self * ln(base))` and
+    /// `-ln(self) / (base * ln(base)^2)`, following IEEE semantics at `self == 0`.
+    pub fn log_base<T: Into<Number>>(self, base: T) -> Number {
+        let base = base.into();
+        Number::record(self.result.log(base.result), &[self, base], |id, res| {
+            Operation::LogBase(id, self.id, base.id, res, 0.0)
+        })
+    }
+
+    /// `1 / self`, recorded as a single node.
+    pub fn recip(self) -> Number {
+        Number::record(self.result.recip(), &[self], |id, res| {
+            Operation::Recip(id, self.id, res, 0.0)
+        })
+    }
+
+    /// `self` raised to an integer power, as [`f64::powi`].
+    pub fn powi(self, n: i32) -> Number {
+        Number::record(self.result.powi(n), &[self], |id, res| {
+            Operation::Powi(id, self.id, n, res, 0.0)
+        })
+    }
+}
+
+// Normal distribution family. Each function is a single node on the tape.
+impl Number {
+    /// Density of the standard normal distribution.
+    pub fn norm_pdf(self) -> Number {
+        Number::record(
+            special_functions::norm_pdf(self.result),
+            &[self],
+            |id, res| Operation::NormPdf(id, self.id, res, 0.0),
+        )
+    }
+
+    /// Inverse of the standard normal distribution function (the probit function),
+    /// with derivative `1 / pdf(norm_inv_cdf(p))`.
+    pub fn norm_inv_cdf(self) -> Number {
+        Number::record(
+            special_functions::norm_inv_cdf(self.result),
+            &[self],
+            |id, res| Operation::NormInvCdf(id, self.id, res, 0.0),
+        )
+    }
+
+    pub fn erf(self) -> Number {
+        Number::record(erf::erf(self.result), &[self], |id, res| {
+            Operation::Erf(id, self.id, res, 0.0)
+        })
+    }
+
+    pub fn erfc(self) -> Number {
+        Number::record(erf::erfc(self.result), &[self], |id, res| {
+            Operation::Erfc(id, self.id, res, 0.0)
+        })
+    }
+
+    /// Distribution function of the normal distribution with mean `mu` and standard
+    /// deviation `sigma`, differentiable in all three arguments.
+    pub fn normal_cdf<M: Into<Number>, S: Into<Number>>(self, mu: M, sigma: S) -> Number {
+        let mu = mu.into();
+        let sigma = sigma.into();
+        let z = (self.result - mu.result) / sigma.result;
+        Number::record(
+            special_functions::norm_cdf(z),
+            &[self, mu, sigma],
+            |id, res| Operation::NormalCdf(id, self.id, mu.id, sigma.id, res, 0.0),
+        )
+    }
+}
+
+// Special functions. The partials with respect to shape parameters and degrees
+// of freedom have no closed form: the reverse sweep takes a central difference
+// of the untaped function in the parameter, good to about eight digits.
+impl Number {
+    /// Gamma function, with derivative `gamma(x) * digamma(x)`.
+    pub fn gamma(self) -> Number {
+        Number::record(gamma::gamma(self.result), &[self], |id, res| {
+            Operation::Gamma(id, self.id, res, 0.0)
+        })
+    }
+
+    /// Natural logarithm of the gamma function for `self > 0`, with derivative `digamma(x)`.
+    pub fn lgamma(self) -> Number {
+        Number::record(gamma::ln_gamma(self.result), &[self], |id, res| {
+            Operation::LnGamma(id, self.id, res, 0.0)
+        })
+    }
+
+    /// Digamma function, with derivative `trigamma(x)`.
+    pub fn digamma(self) -> Number {
+        Number::record(gamma::digamma(self.result), &[self], |id, res| {
+            Operation::Digamma(id, self.id, res, 0.0)
+        })
+    }
+
+    /// Beta function `B(self, b)` for positive arguments, differentiable in both.
+    pub fn beta<T: Into<Number>>(self, b: T) -> Number {
+        let b = b.into();
+        Number::record(
+            special_functions::beta(self.result, b.result),
+            &[self, b],
+            |id, res| Operation::Beta(id, self.id, b.id, res, 0.0),
+        )
+    }
+
+    /// Regularized lower incomplete gamma function `P(a, self)`, differentiable
+    /// in both; the partial in `a` is a central difference.
+    pub fn regularized_gamma_p<A: Into<Number>>(self, a: A) -> Number {
+        let a = a.into();
+        Number::record(
+            special_functions::gamma_p(a.result, self.result),
+            &[self, a],
+            |id, res| Operation::GammaP(id, self.id, a.id, res, 0.0),
+        )
+    }
+
+    /// Regularized upper incomplete gamma function `Q(a, self) = 1 - P(a, self)`,
+    /// differentiable in both; the partial in `a` is a central difference.
+    pub fn regularized_gamma_q<A: Into<Number>>(self, a: A) -> Number {
+        let a = a.into();
+        Number::record(
+            special_functions::gamma_q(a.result, self.result),
+            &[self, a],
+            |id, res| Operation::GammaQ(id, self.id, a.id, res, 0.0),
+        )
+    }
+
+    /// Regularized incomplete beta function `I_self(a, b)`, differentiable in all
+    /// three; the partials in `a` and `b` are central differences.
+    pub fn regularized_beta<A: Into<Number>, B: Into<Number>>(self, a: A, b: B) -> Number {
+        let a = a.into();
+        let b = b.into();
+        Number::record(
+            special_functions::beta_reg(a.result, b.result, self.result),
+            &[self, a, b],
+            |id, res| Operation::BetaReg(id, self.id, a.id, b.id, res, 0.0),
+        )
+    }
+
+    /// Distribution function of Student's t distribution with `nu` degrees of
+    /// freedom, differentiable in both; the partial in `nu` is a central difference.
+    pub fn students_t_cdf<N: Into<Number>>(self, nu: N) -> Number {
+        let nu = nu.into();
+        Number::record(
+            special_functions::students_t_cdf(self.result, nu.result),
+            &[self, nu],
+            |id, res| Operation::StudentsTCdf(id, self.id, nu.id, res, 0.0),
+        )
+    }
+
+    /// Distribution function of the chi-squared distribution with `k` degrees of
+    /// freedom, differentiable in both; the partial in `k` is a central difference.
+    pub fn chi_squared_cdf<K: Into<Number>>(self, k: K) -> Number {
+        let k = k.into();
+        Number::record(
+            special_functions::chi_squared_cdf(self.result, k.result),
+            &[self, k],
+            |id, res| Operation::ChiSquaredCdf(id, self.id, k.id, res, 0.0),
+        )
+    }
+}
+
+// Non-smooth functions. The reverse sweep uses the subgradient conventions
+// documented on each method; at a kink the one-sided derivatives are averaged.
+impl Number {
+    /// Absolute value. The derivative is `signum(x)` away from zero and `0` at `x = 0`.
+    pub fn abs(self) -> Number {
+        Number::record(self.result.abs(), &[self], |id, res| {
+            Operation::Abs(id, self.id, res, 0.0)
+        })
+    }
+
+    /// Larger of `self` and `rhs`. The larger argument receives the full
+    /// derivative; on a tie each argument receives one half.
+    pub fn max<T: Into<Number>>(self, rhs: T) -> Number {
+        let rhs = rhs.into();
+        Number::record(self.result.max(rhs.result), &[self, rhs], |id, res| {
+            Operation::Max(id, self.id, rhs.id, res, 0.0)
+        })
+    }
+
+    /// Smaller of `self` and `rhs`. The smaller argument receives the full
+    /// derivative; on a tie each argument receives one half.
+    pub fn min<T: Into<Number>>(self, rhs: T) -> Number {
+        let rhs = rhs.into();
+        Number::record(self.result.min(rhs.result), &[self, rhs], |id, res| {
+            Operation::Min(id, self.id, rhs.id, res, 0.0)
+        })
+    }
+
+    /// Restricts `self` to `[lo, hi]`, recorded as a single node.
+    ///
+    /// The derivative is `1` strictly inside the interval, `0` outside it and
+    /// `1/2` on either bound (`0` if `lo == hi`). Panics if `lo > hi` or either
+    /// bound is NaN, like [`f64::clamp`].
+    pub fn clamp(self, lo: f64, hi: f64) -> Number {
+        Number::record(self.result.clamp(lo, hi), &[self], |id, res| {
+            Operation::Clamp(id, self.id, lo, hi, res, 0.0)
+        })
+    }
+
+    /// Largest integer less than or equal to `self`. The derivative is `0`
+    /// everywhere, including at the jumps.
+    pub fn floor(self) -> Number {
+        Number::record(self.result.floor(), &[self], |id, res| {
+            Operation::Floor(id, self.id, res, 0.0)
+        })
+    }
+
+    /// Smallest integer greater than or equal to `self`. The derivative is `0`
+    /// everywhere, including at the jumps.
+    pub fn ceil(self) -> Number {
+        Number::record(self.result.ceil(), &[self], |id, res| {
+            Operation::Ceil(id, self.id, res, 0.0)
+        })
+    }
+
+    /// Sign of `self` with the semantics of [`f64::signum`] (`+0.0` maps to `1.0`).
+    /// The derivative is `0` everywhere, including at the jump.
+    pub fn signum(self) -> Number {
+        Number::record(self.result.signum(), &[self], |id, res| {
+            Operation::Signum(id, self.id, res, 0.0)
+        })
+    }
+}
+
+// Smooth approximations of the non-smooth functions above, for payoffs whose
+// pathwise derivative vanishes almost everywhere (digitals, barriers). `eps` is
+// the width of the smoothing and must be positive; each converges to its
+// non-smooth counterpart as `eps` goes to zero.
+impl Number {
+    /// Sigmoid approximation `1 / (1 + e^(-x/eps))` of the step function
+    /// `x > 0`, with derivative `s (1 - s) / eps`.
+    pub fn smooth_step(self, eps: f64) -> Number {
+        Number::record(
+            special_functions::sigmoid(self.result / eps),
+            &[self],
+            |id, res| Operation::SmoothStep(id, self.id, eps, res, 0.0),
+        )
+    }
+
+    /// Softplus approximation `b + eps ln(1 + e^((a-b)/eps))` of `max(a, b)`.
+    /// The derivative with respect to `a` is `sigmoid((a-b)/eps)`, the one with
+    /// respect to `b` its complement.
+    pub fn smooth_max<T: Into<Number>>(self, rhs: T, eps: f64) -> Number {
+        let rhs = rhs.into();
+        let val = rhs.result + eps * special_functions::softplus((self.result - rhs.result) / eps);
+        Number::record(val, &[self, rhs], |id, res| {
+            Operation::SmoothMax(id, self.id, rhs.id, eps, res, 0.0)
+        })
+    }
+
+    /// Approximation `sqrt(x^2 + eps^2)` of `|x|`, with derivative
+    /// `x / sqrt(x^2 + eps^2)`.
+    pub fn smooth_abs(self, eps: f64) -> Number {
+        Number::record(self.result.hypot(eps), &[self], |id, res| {
+            Operation::SmoothAbs(id, self.id, eps, res, 0.0)
+        })
+    }
+
+    /// Approximation `sigmoid((x-lo)/eps) - sigmoid((x-hi)/eps)` of the
+    /// indicator of `lo < x < hi`, i.e. a difference of two [`Number::smooth_step`]s.
+    pub fn smooth_indicator_between(self, lo: f64, hi: f64, eps: f64) -> Number {
+        let val = special_functions::sigmoid((self.result - lo) / eps)
+            - special_functions::sigmoid((self.result - hi) / eps);
+        Number::record(val, &[self], |id, res| {
+            Operation::SmoothIndicator(id, self.id, lo, hi, eps, res, 0.0)
+        })
+    }
+}
+
+impl From<f64> for Number {
+    /// Creates a constant on the tape. Constants are not leaves, so no
+    /// derivative is reported for them.
+    fn from(val: f64) -> Self {
+        if !recording() {
+            return Number::untaped(val);
         }
+        let constant = Number::new_non_leaf(val);
+        let val_op = Operation::Value(constant.id, constant.result, 0.0);
+        shared_data_communication_channel::global_register_operation(val_op);
+        constant
+    }
+}
 
-        result
+/// How the two sides of a [`Condition`] are compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+impl Comparison {
+    pub fn holds(&self, lhs: f64, rhs: f64) -> bool {
+        match self {
+            Comparison::Greater => lhs > rhs,
+            Comparison::GreaterOrEqual => lhs >= rhs,
+            Comparison::Less => lhs < rhs,
+            Comparison::LessOrEqual => lhs <= rhs,
+        }
     }
+}
 
-    pub fn log(self, b: f64) -> Number {
-        let result: Number = Number::new_non_leaf(self.result.log(b));
-        let op = Operation::Log(result.id, self.id, b, result.result, 0.0);
-        shared_data_communication_channel::global_register_operation(op);
-        shared_data_communication_channel::global_add_parent_child_relationship(
-            result.id,
-            vec![self.id],
-        );
+/// A comparison of two `Number`s, used to pick a branch in [`Number::select`].
+/// Unlike comparing `result`s directly, the comparison is recorded on the tape.
+#[derive(Debug, Clone, Copy)]
+pub struct Condition {
+    pub lhs: Number,
+    pub comparison: Comparison,
+    pub rhs: Number,
+}
 
-        if self.leaf {
-            let val_op = Operation::Value(self.id, self.result, 0.0);
-            shared_data_communication_channel::global_register_operation(val_op);
+impl Condition {
+    pub fn new<L: Into<Number>, R: Into<Number>>(lhs: L, comparison: Comparison, rhs: R) -> Self {
+        Condition {
+            lhs: lhs.into(),
+            comparison,
+            rhs: rhs.into(),
         }
+    }
 
-        result
+    /// `lhs > rhs`
+    pub fn gt<L: Into<Number>, R: Into<Number>>(lhs: L, rhs: R) -> Self {
+        Condition::new(lhs, Comparison::Greater, rhs)
     }
 
-    pub fn cdf(self) -> Number {
-        let norm = Normal::new(0.0, 1.0).unwrap();
+    /// `lhs >= rhs`
+    pub fn ge<L: Into<Number>, R: Into<Number>>(lhs: L, rhs: R) -> Self {
+        Condition::new(lhs, Comparison::GreaterOrEqual, rhs)
+    }
 
-        let result: Number = Number::new_non_leaf(norm.cdf(self.result));
-        let op = Operation::Cdf(result.id, self.id, result.result, 0.0);
-        shared_data_communication_channel::global_register_operation(op);
-        shared_data_communication_channel::global_add_parent_child_relationship(
-            result.id,
-            vec![self.id],
-        );
+    /// `lhs < rhs`
+    pub fn lt<L: Into<Number>, R: Into<Number>>(lhs: L, rhs: R) -> Self {
+        Condition::new(lhs, Comparison::Less, rhs)
+    }
 
-        if self.leaf {
-            let val_op = Operation::Value(self.id, self.result, 0.0);
-            shared_data_communication_channel::global_register_operation(val_op);
-        }
+    /// `lhs <= rhs`
+    pub fn le<L: Into<Number>, R: Into<Number>>(lhs: L, rhs: R) -> Self {
+        Condition::new(lhs, Comparison::LessOrEqual, rhs)
+    }
 
-        result
+    /// Evaluates the comparison on the current values.
+    pub fn holds(&self) -> bool {
+        self.comparison.holds(self.lhs.result, self.rhs.result)
+    }
+}
+
+// Conditional selection. Both branches are evaluated and recorded; the select
+// node remembers the condition, so a replay of the tape with new inputs
+// (`AutomaticDifferentiator::replay`) re-evaluates it and follows the branch
+// the new inputs lead to.
+impl Number {
+    /// `a` if `condition` holds, `b` otherwise. The selected branch receives the
+    /// full derivative, the other branch and the condition receive nothing.
+    pub fn select<A: Into<Number>, B: Into<Number>>(condition: Condition, a: A, b: B) -> Number {
+        let a = a.into();
+        let b = b.into();
+        let Condition {
+            lhs,
+            comparison,
+            rhs,
+        } = condition;
+        let holds = condition.holds();
+        let val = if holds { a.result } else { b.result };
+        Number::record(val, &[lhs, rhs, a, b], |id, res| {
+            Operation::Select(id, lhs.id, comparison, rhs.id, a.id, b.id, holds, res, 0.0)
+        })
+    }
+
+    /// `a` if `self > k`, `b` otherwise. Shorthand for
+    /// `Number::select(Condition::gt(self, k), a, b)`.
+    pub fn where_gt<K: Into<Number>, A: Into<Number>, B: Into<Number>>(
+        self,
+        k: K,
+        a: A,
+        b: B,
+    ) -> Number {
+        Number::select(Condition::gt(self, k), a, b)
+    }
+}
+
+/// Value of an implicit function and its partials with respect to each of its
+/// arguments, at the given values of the arguments. Stored with the node so
+/// that a replay of the tape can solve again.
+#[derive(Clone)]
+pub struct ImplicitSolver(pub(crate) Arc<Solve>);
+
+type Solve = dyn Fn(&[f64]) -> (f64, Vec<f64>) + Send + Sync;
+
+impl fmt::Debug for ImplicitSolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ImplicitSolver")
+    }
+}
+
+// Implicit functions. A value defined by an equation in its arguments, such as
+// an implied volatility, is solved for without the tape and recorded as a
+// single node holding its partials, computed once from the implicit function
+// theorem. A replay of the tape (`AutomaticDifferentiator::replay`) calls the
+// solver again with the new arguments.
+impl Number {
+    /// The implicit function of `args` given by `solver`, which returns the
+    /// value and its partials with respect to each argument.
+    pub(crate) fn implicit_function<S>(args: &[Number], solver: S) -> Number
+    where
+        S: Fn(&[f64]) -> (f64, Vec<f64>) + Send + Sync + 'static,
+    {
+        let values: Vec<f64> = args.iter().map(|arg| arg.result).collect();
+        if !recording() {
+            return Number::untaped(solver(&values).0);
+        }
+        let (val, partials) = solver(&values);
+        let solver = ImplicitSolver(Arc::new(solver));
+        Number::record(val, args, |id, res| {
+            Operation::ImplicitFunction(
+                id,
+                solver,
+                args.iter().map(|arg| arg.id).collect(),
+                partials,
+                res,
+                0.0,
+            )
+        })
+    }
+}
+
+// Comparisons only look at the primal value; the id of a Number plays no part.
+// Nothing is recorded on the tape, so a branch taken on a comparison is not
+// visible to the reverse sweep. Use `Number::select` for a recorded branch.
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        self.result == other.result
+    }
+}
+
+impl PartialEq<f64> for Number {
+    fn eq(&self, other: &f64) -> bool {
+        self.result == *other
+    }
+}
+
+impl PartialEq<Number> for f64 {
+    fn eq(&self, other: &Number) -> bool {
+        *self == other.result
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.result.partial_cmp(&other.result)
+    }
+}
+
+impl PartialOrd<f64> for Number {
+    fn partial_cmp(&self, other: &f64) -> Option<Ordering> {
+        self.result.partial_cmp(other)
+    }
+}
+
+impl PartialOrd<Number> for f64 {
+    fn partial_cmp(&self, other: &Number) -> Option<Ordering> {
+        self.partial_cmp(&other.result)
     }
 }
 
@@ -1,6 +1,9 @@
 use std::fmt::Display;
+use std::sync::Arc;
 
-use crate::operation;
+use crate::distributions::Family;
+use crate::linear_algebra::{CholeskyFactorization, TridiagonalSystem};
+use crate::number::{Comparison, ImplicitSolver};
 
 #[derive(Debug, Clone)]
 pub enum Operation {
@@ -16,7 +19,60 @@ pub enum Operation {
     Sqrt(i64, i64, f64, f64),     // id, arg_id, result, adjoint
     Log(i64, i64, f64, f64, f64), // id, arg_id, base, result, adjoint
     Cdf(i64, i64, f64, f64),      // id, arg_id, result, adjoint
-    Value(i64, f64, f64),         // id, result, adjoint
+    Abs(i64, i64, f64, f64),      // id, arg_id, result, adjoint
+    Max(i64, i64, i64, f64, f64), // id, lhs_id, rhs_id, result, adjoint
+    Min(i64, i64, i64, f64, f64), // id, lhs_id, rhs_id, result, adjoint
+    Clamp(i64, i64, f64, f64, f64, f64), // id, arg_id, lo, hi, result, adjoint
+    Floor(i64, i64, f64, f64),    // id, arg_id, result, adjoint
+    Ceil(i64, i64, f64, f64),     // id, arg_id, result, adjoint
+    Signum(i64, i64, f64, f64),   // id, arg_id, result, adjoint
+    Tan(i64, i64, f64, f64),      // id, arg_id, result, adjoint
+    Asin(i64, i64, f64, f64),     // id, arg_id, result, adjoint
+    Acos(i64, i64, f64, f64),     // id, arg_id, result, adjoint
+    Atan(i64, i64, f64, f64),     // id, arg_id, result, adjoint
+    Atan2(i64, i64, i64, f64, f64), // id, y_id, x_id, result, adjoint
+    Sinh(i64, i64, f64, f64),     // id, arg_id, result, adjoint
+    Cosh(i64, i64, f64, f64),     // id, arg_id, result, adjoint
+    Tanh(i64, i64, f64, f64),     // id, arg_id, result, adjoint
+    Asinh(i64, i64, f64, f64),    // id, arg_id, result, adjoint
+    Acosh(i64, i64, f64, f64),    // id, arg_id, result, adjoint
+    Atanh(i64, i64, f64, f64),    // id, arg_id, result, adjoint
+    Exp2(i64, i64, f64, f64),     // id, arg_id, result, adjoint
+    Expm1(i64, i64, f64, f64),    // id, arg_id, result, adjoint
+    Ln1p(i64, i64, f64, f64),     // id, arg_id, result, adjoint
+    Cbrt(i64, i64, f64, f64),     // id, arg_id, result, adjoint
+    Hypot(i64, i64, i64, f64, f64), // id, lhs_id, rhs_id, result, adjoint
+    Recip(i64, i64, f64, f64),    // id, arg_id, result, adjoint
+    Powi(i64, i64, i32, f64, f64), // id, base_id, exp, result, adjoint
+    Powf(i64, i64, i64, f64, f64), // id, base_id, exp_id, result, adjoint
+    LogBase(i64, i64, i64, f64, f64), // id, arg_id, base_id, result, adjoint
+    NormPdf(i64, i64, f64, f64),  // id, arg_id, result, adjoint
+    NormInvCdf(i64, i64, f64, f64), // id, arg_id, result, adjoint
+    Erf(i64, i64, f64, f64),      // id, arg_id, result, adjoint
+    Erfc(i64, i64, f64, f64),     // id, arg_id, result, adjoint
+    NormalCdf(i64, i64, i64, i64, f64, f64), // id, arg_id, mu_id, sigma_id, result, adjoint
+    Gamma(i64, i64, f64, f64),    // id, arg_id, result, adjoint
+    LnGamma(i64, i64, f64, f64),  // id, arg_id, result, adjoint
+    Digamma(i64, i64, f64, f64),  // id, arg_id, result, adjoint
+    Beta(i64, i64, i64, f64, f64), // id, a_id, b_id, result, adjoint
+    GammaP(i64, i64, i64, f64, f64), // id, arg_id, a_id, result, adjoint
+    GammaQ(i64, i64, i64, f64, f64), // id, arg_id, a_id, result, adjoint
+    BetaReg(i64, i64, i64, i64, f64, f64), // id, arg_id, a_id, b_id, result, adjoint
+    StudentsTCdf(i64, i64, i64, f64, f64), // id, arg_id, nu_id, result, adjoint
+    ChiSquaredCdf(i64, i64, i64, f64, f64), // id, arg_id, k_id, result, adjoint
+    LogDensity(i64, Family, Vec<i64>, f64, f64), // id, family, [x_id, param_ids..], result, adjoint
+    LogLikelihood(i64, Family, Arc<[f64]>, Vec<i64>, f64, f64), // id, family, data, param_ids, result, adjoint
+    SmoothStep(i64, i64, f64, f64, f64),                        // id, arg_id, eps, result, adjoint
+    SmoothMax(i64, i64, i64, f64, f64, f64), // id, lhs_id, rhs_id, eps, result, adjoint
+    SmoothAbs(i64, i64, f64, f64, f64),      // id, arg_id, eps, result, adjoint
+    SmoothIndicator(i64, i64, f64, f64, f64, f64, f64), // id, arg_id, lo, hi, eps, result, adjoint
+    Select(i64, i64, Comparison, i64, i64, i64, bool, f64, f64), // id, lhs_id, comparison, rhs_id, a_id, b_id, holds, result, adjoint
+    ImplicitFunction(i64, ImplicitSolver, Vec<i64>, Vec<f64>, f64, f64), // id, solver, arg_ids, partials, result, adjoint
+    TridiagonalSolve(i64, Arc<TridiagonalSystem>, f64, f64), // id, coefficient and right hand side ids, result, adjoint
+    TridiagonalSolution(i64, i64, usize, f64, f64),          // id, solve_id, index, result, adjoint
+    Cholesky(i64, Arc<CholeskyFactorization>, f64, f64), // id, ids of the lower triangle of the matrix, result, adjoint
+    CholeskyFactor(i64, i64, usize, usize, f64, f64), // id, factorization_id, row, column, result, adjoint
+    Value(i64, f64, f64),                             // id, result, adjoint
 }
 
 #[derive(Debug, Clone)]
@@ -121,6 +177,392 @@ impl Display for Operation {
                     id, arg_id, result, adjoint
                 )
             }
+            Operation::Abs(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Abs(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Max(id, lhs_id, rhs_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Max(lhs_id: {}, rhs_id: {}, res: {}, adjoint {})",
+                    id, lhs_id, rhs_id, result, adjoint
+                )
+            }
+            Operation::Min(id, lhs_id, rhs_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Min(lhs_id: {}, rhs_id: {}, res: {}, adjoint {})",
+                    id, lhs_id, rhs_id, result, adjoint
+                )
+            }
+            Operation::Clamp(id, arg_id, _lo, _hi, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Clamp(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Floor(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Floor(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Ceil(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Ceil(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Signum(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Signum(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Tan(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Tan(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Asin(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Asin(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Acos(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Acos(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Atan(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Atan(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Atan2(id, y_id, x_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Atan2(y_id: {}, x_id: {}, res: {}, adjoint {})",
+                    id, y_id, x_id, result, adjoint
+                )
+            }
+            Operation::Sinh(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Sinh(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Cosh(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Cosh(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Tanh(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Tanh(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Asinh(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Asinh(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Acosh(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Acosh(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Atanh(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Atanh(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Exp2(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Exp2(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Expm1(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Expm1(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Ln1p(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Ln1p(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Cbrt(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Cbrt(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Hypot(id, lhs_id, rhs_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Hypot(lhs_id: {}, rhs_id: {}, res: {}, adjoint {})",
+                    id, lhs_id, rhs_id, result, adjoint
+                )
+            }
+            Operation::Recip(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Recip(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Powi(id, base_id, _exp, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Powi(base_id: {}, res: {}, adjoint {})",
+                    id, base_id, result, adjoint
+                )
+            }
+            Operation::Powf(id, base_id, exp_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Powf(base_id: {}, exp_id: {}, res: {}, adjoint {})",
+                    id, base_id, exp_id, result, adjoint
+                )
+            }
+            Operation::LogBase(id, arg_id, base_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: LogBase(arg_id: {}, base_id: {}, res: {}, adjoint {})",
+                    id, arg_id, base_id, result, adjoint
+                )
+            }
+            Operation::NormPdf(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: NormPdf(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::NormInvCdf(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: NormInvCdf(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Erf(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Erf(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Erfc(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Erfc(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::NormalCdf(id, arg_id, mu_id, sigma_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: NormalCdf(arg_id: {}, mu_id: {}, sigma_id: {}, res: {}, adjoint {})",
+                    id, arg_id, mu_id, sigma_id, result, adjoint
+                )
+            }
+            Operation::Gamma(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Gamma(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::LnGamma(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: LnGamma(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Digamma(id, arg_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Digamma(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Beta(id, a_id, b_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Beta(a_id: {}, b_id: {}, res: {}, adjoint {})",
+                    id, a_id, b_id, result, adjoint
+                )
+            }
+            Operation::GammaP(id, arg_id, a_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: GammaP(arg_id: {}, a_id: {}, res: {}, adjoint {})",
+                    id, arg_id, a_id, result, adjoint
+                )
+            }
+            Operation::GammaQ(id, arg_id, a_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: GammaQ(arg_id: {}, a_id: {}, res: {}, adjoint {})",
+                    id, arg_id, a_id, result, adjoint
+                )
+            }
+            Operation::BetaReg(id, arg_id, a_id, b_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: BetaReg(arg_id: {}, a_id: {}, b_id: {}, res: {}, adjoint {})",
+                    id, arg_id, a_id, b_id, result, adjoint
+                )
+            }
+            Operation::StudentsTCdf(id, arg_id, nu_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: StudentsTCdf(arg_id: {}, nu_id: {}, res: {}, adjoint {})",
+                    id, arg_id, nu_id, result, adjoint
+                )
+            }
+            Operation::ChiSquaredCdf(id, arg_id, k_id, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: ChiSquaredCdf(arg_id: {}, k_id: {}, res: {}, adjoint {})",
+                    id, arg_id, k_id, result, adjoint
+                )
+            }
+            Operation::LogDensity(id, family, arg_ids, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: LogDensity({:?}, arg_ids: {:?}, res: {}, adjoint {})",
+                    id, family, arg_ids, result, adjoint
+                )
+            }
+            Operation::LogLikelihood(id, family, data, param_ids, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: LogLikelihood({:?}, observations: {}, param_ids: {:?}, res: {}, adjoint {})",
+                    id,
+                    family,
+                    data.len(),
+                    param_ids,
+                    result,
+                    adjoint
+                )
+            }
+            Operation::SmoothStep(id, arg_id, _eps, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: SmoothStep(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::SmoothMax(id, lhs_id, rhs_id, _eps, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: SmoothMax(lhs_id: {}, rhs_id: {}, res: {}, adjoint {})",
+                    id, lhs_id, rhs_id, result, adjoint
+                )
+            }
+            Operation::SmoothAbs(id, arg_id, _eps, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: SmoothAbs(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::SmoothIndicator(id, arg_id, _lo, _hi, _eps, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: SmoothIndicator(arg_id: {}, res: {}, adjoint {})",
+                    id, arg_id, result, adjoint
+                )
+            }
+            Operation::Select(
+                id,
+                lhs_id,
+                comparison,
+                rhs_id,
+                a_id,
+                b_id,
+                holds,
+                result,
+                adjoint,
+            ) => {
+                write!(
+                    f,
+                    "id {}: Select(lhs_id: {}, {:?}, rhs_id: {}, a_id: {}, b_id: {}, holds: {}, res: {}, adjoint {})",
+                    id, lhs_id, comparison, rhs_id, a_id, b_id, holds, result, adjoint
+                )
+            }
+            Operation::ImplicitFunction(id, _solver, arg_ids, partials, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: ImplicitFunction(arg_ids: {:?}, partials: {:?}, res: {}, adjoint {})",
+                    id, arg_ids, partials, result, adjoint
+                )
+            }
+            Operation::TridiagonalSolve(id, system, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: TridiagonalSolve(system: {:?}, res: {}, adjoint {})",
+                    id, system, result, adjoint
+                )
+            }
+            Operation::TridiagonalSolution(id, solve_id, index, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: TridiagonalSolution(solve_id: {}, index: {}, res: {}, adjoint {})",
+                    id, solve_id, index, result, adjoint
+                )
+            }
+            Operation::Cholesky(id, factorization, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: Cholesky(factorization: {:?}, res: {}, adjoint {})",
+                    id, factorization, result, adjoint
+                )
+            }
+            Operation::CholeskyFactor(id, factorization_id, row, column, result, adjoint) => {
+                write!(
+                    f,
+                    "id {}: CholeskyFactor(factorization_id: {}, row: {}, column: {}, res: {}, adjoint {})",
+                    id, factorization_id, row, column, result, adjoint
+                )
+            }
             Operation::Value(id, value, adjoint) => {
                 write!(f, "id: {}: Value({}, adjoint {})", id, value, adjoint)
             }
@@ -143,6 +585,59 @@ impl Operation {
             | Operation::Sqrt(id, _, _, _)
             | Operation::Log(id, _, _, _, _)
             | Operation::Cdf(id, _, _, _)
+            | Operation::Abs(id, _, _, _)
+            | Operation::Max(id, _, _, _, _)
+            | Operation::Min(id, _, _, _, _)
+            | Operation::Clamp(id, _, _, _, _, _)
+            | Operation::Floor(id, _, _, _)
+            | Operation::Ceil(id, _, _, _)
+            | Operation::Signum(id, _, _, _)
+            | Operation::Tan(id, _, _, _)
+            | Operation::Asin(id, _, _, _)
+            | Operation::Acos(id, _, _, _)
+            | Operation::Atan(id, _, _, _)
+            | Operation::Atan2(id, _, _, _, _)
+            | Operation::Sinh(id, _, _, _)
+            | Operation::Cosh(id, _, _, _)
+            | Operation::Tanh(id, _, _, _)
+            | Operation::Asinh(id, _, _, _)
+            | Operation::Acosh(id, _, _, _)
+            | Operation::Atanh(id, _, _, _)
+            | Operation::Exp2(id, _, _, _)
+            | Operation::Expm1(id, _, _, _)
+            | Operation::Ln1p(id, _, _, _)
+            | Operation::Cbrt(id, _, _, _)
+            | Operation::Hypot(id, _, _, _, _)
+            | Operation::Recip(id, _, _, _)
+            | Operation::Powi(id, _, _, _, _)
+            | Operation::Powf(id, _, _, _, _)
+            | Operation::LogBase(id, _, _, _, _)
+            | Operation::NormPdf(id, _, _, _)
+            | Operation::NormInvCdf(id, _, _, _)
+            | Operation::Erf(id, _, _, _)
+            | Operation::Erfc(id, _, _, _)
+            | Operation::NormalCdf(id, _, _, _, _, _)
+            | Operation::Gamma(id, _, _, _)
+            | Operation::LnGamma(id, _, _, _)
+            | Operation::Digamma(id, _, _, _)
+            | Operation::Beta(id, _, _, _, _)
+            | Operation::GammaP(id, _, _, _, _)
+            | Operation::GammaQ(id, _, _, _, _)
+            | Operation::BetaReg(id, _, _, _, _, _)
+            | Operation::StudentsTCdf(id, _, _, _, _)
+            | Operation::ChiSquaredCdf(id, _, _, _, _)
+            | Operation::LogDensity(id, _, _, _, _)
+            | Operation::LogLikelihood(id, _, _, _, _, _)
+            | Operation::SmoothStep(id, _, _, _, _)
+            | Operation::SmoothMax(id, _, _, _, _, _)
+            | Operation::SmoothAbs(id, _, _, _, _)
+            | Operation::SmoothIndicator(id, _, _, _, _, _, _)
+            | Operation::Select(id, _, _, _, _, _, _, _, _)
+            | Operation::ImplicitFunction(id, _, _, _, _, _)
+            | Operation::TridiagonalSolve(id, _, _, _)
+            | Operation::TridiagonalSolution(id, _, _, _, _)
+            | Operation::Cholesky(id, _, _, _)
+            | Operation::CholeskyFactor(id, _, _, _, _, _)
             | Operation::Value(id, _, _) => *id,
         }
     }
@@ -161,10 +656,134 @@ impl Operation {
             | Operation::Sqrt(_, _, res, _)
             | Operation::Log(_, _, _, res, _)
             | Operation::Cdf(_, _, res, _)
+            | Operation::Abs(_, _, res, _)
+            | Operation::Max(_, _, _, res, _)
+            | Operation::Min(_, _, _, res, _)
+            | Operation::Clamp(_, _, _, _, res, _)
+            | Operation::Floor(_, _, res, _)
+            | Operation::Ceil(_, _, res, _)
+            | Operation::Signum(_, _, res, _)
+            | Operation::Tan(_, _, res, _)
+            | Operation::Asin(_, _, res, _)
+            | Operation::Acos(_, _, res, _)
+            | Operation::Atan(_, _, res, _)
+            | Operation::Atan2(_, _, _, res, _)
+            | Operation::Sinh(_, _, res, _)
+            | Operation::Cosh(_, _, res, _)
+            | Operation::Tanh(_, _, res, _)
+            | Operation::Asinh(_, _, res, _)
+            | Operation::Acosh(_, _, res, _)
+            | Operation::Atanh(_, _, res, _)
+            | Operation::Exp2(_, _, res, _)
+            | Operation::Expm1(_, _, res, _)
+            | Operation::Ln1p(_, _, res, _)
+            | Operation::Cbrt(_, _, res, _)
+            | Operation::Hypot(_, _, _, res, _)
+            | Operation::Recip(_, _, res, _)
+            | Operation::Powi(_, _, _, res, _)
+            | Operation::Powf(_, _, _, res, _)
+            | Operation::LogBase(_, _, _, res, _)
+            | Operation::NormPdf(_, _, res, _)
+            | Operation::NormInvCdf(_, _, res, _)
+            | Operation::Erf(_, _, res, _)
+            | Operation::Erfc(_, _, res, _)
+            | Operation::NormalCdf(_, _, _, _, res, _)
+            | Operation::Gamma(_, _, res, _)
+            | Operation::LnGamma(_, _, res, _)
+            | Operation::Digamma(_, _, res, _)
+            | Operation::Beta(_, _, _, res, _)
+            | Operation::GammaP(_, _, _, res, _)
+            | Operation::GammaQ(_, _, _, res, _)
+            | Operation::BetaReg(_, _, _, _, res, _)
+            | Operation::StudentsTCdf(_, _, _, res, _)
+            | Operation::ChiSquaredCdf(_, _, _, res, _)
+            | Operation::LogDensity(_, _, _, res, _)
+            | Operation::LogLikelihood(_, _, _, _, res, _)
+            | Operation::SmoothStep(_, _, _, res, _)
+            | Operation::SmoothMax(_, _, _, _, res, _)
+            | Operation::SmoothAbs(_, _, _, res, _)
+            | Operation::SmoothIndicator(_, _, _, _, _, res, _)
+            | Operation::Select(_, _, _, _, _, _, _, res, _)
+            | Operation::ImplicitFunction(_, _, _, _, res, _)
+            | Operation::TridiagonalSolve(_, _, res, _)
+            | Operation::TridiagonalSolution(_, _, _, res, _)
+            | Operation::Cholesky(_, _, res, _)
+            | Operation::CholeskyFactor(_, _, _, _, res, _)
             | Operation::Value(_, res, _) => *res,
         }
     }
 
+    pub fn set_result(&mut self, val: f64) {
+        match self {
+            Operation::Add(_, _, _, res, _)
+            | Operation::Sub(_, _, _, res, _)
+            | Operation::Mul(_, _, _, res, _)
+            | Operation::Div(_, _, _, res, _)
+            | Operation::Ln(_, _, res, _)
+            | Operation::Sin(_, _, res, _)
+            | Operation::Cos(_, _, res, _)
+            | Operation::Exp(_, _, res, _)
+            | Operation::Pow(_, _, _, res, _)
+            | Operation::Sqrt(_, _, res, _)
+            | Operation::Log(_, _, _, res, _)
+            | Operation::Cdf(_, _, res, _)
+            | Operation::Abs(_, _, res, _)
+            | Operation::Max(_, _, _, res, _)
+            | Operation::Min(_, _, _, res, _)
+            | Operation::Clamp(_, _, _, _, res, _)
+            | Operation::Floor(_, _, res, _)
+            | Operation::Ceil(_, _, res, _)
+            | Operation::Signum(_, _, res, _)
+            | Operation::Tan(_, _, res, _)
+            | Operation::Asin(_, _, res, _)
+            | Operation::Acos(_, _, res, _)
+            | Operation::Atan(_, _, res, _)
+            | Operation::Atan2(_, _, _, res, _)
+            | Operation::Sinh(_, _, res, _)
+            | Operation::Cosh(_, _, res, _)
+            | Operation::Tanh(_, _, res, _)
+            | Operation::Asinh(_, _, res, _)
+            | Operation::Acosh(_, _, res, _)
+            | Operation::Atanh(_, _, res, _)
+            | Operation::Exp2(_, _, res, _)
+            | Operation::Expm1(_, _, res, _)
+            | Operation::Ln1p(_, _, res, _)
+            | Operation::Cbrt(_, _, res, _)
+            | Operation::Hypot(_, _, _, res, _)
+            | Operation::Recip(_, _, res, _)
+            | Operation::Powi(_, _, _, res, _)
+            | Operation::Powf(_, _, _, res, _)
+            | Operation::LogBase(_, _, _, res, _)
+            | Operation::NormPdf(_, _, res, _)
+            | Operation::NormInvCdf(_, _, res, _)
+            | Operation::Erf(_, _, res, _)
+            | Operation::Erfc(_, _, res, _)
+            | Operation::NormalCdf(_, _, _, _, res, _)
+            | Operation::Gamma(_, _, res, _)
+            | Operation::LnGamma(_, _, res, _)
+            | Operation::Digamma(_, _, res, _)
+            | Operation::Beta(_, _, _, res, _)
+            | Operation::GammaP(_, _, _, res, _)
+            | Operation::GammaQ(_, _, _, res, _)
+            | Operation::BetaReg(_, _, _, _, res, _)
+            | Operation::StudentsTCdf(_, _, _, res, _)
+            | Operation::ChiSquaredCdf(_, _, _, res, _)
+            | Operation::LogDensity(_, _, _, res, _)
+            | Operation::LogLikelihood(_, _, _, _, res, _)
+            | Operation::SmoothStep(_, _, _, res, _)
+            | Operation::SmoothMax(_, _, _, _, res, _)
+            | Operation::SmoothAbs(_, _, _, res, _)
+            | Operation::SmoothIndicator(_, _, _, _, _, res, _)
+            | Operation::Select(_, _, _, _, _, _, _, res, _)
+            | Operation::ImplicitFunction(_, _, _, _, res, _)
+            | Operation::TridiagonalSolve(_, _, res, _)
+            | Operation::TridiagonalSolution(_, _, _, res, _)
+            | Operation::Cholesky(_, _, res, _)
+            | Operation::CholeskyFactor(_, _, _, _, res, _)
+            | Operation::Value(_, res, _) => *res = val,
+        }
+    }
+
     pub fn get_adjoint(&self) -> f64 {
         match self {
             Operation::Add(_, _, _, _, adj)
@@ -179,6 +798,59 @@ impl Operation {
             | Operation::Sqrt(_, _, _, adj)
             | Operation::Log(_, _, _, _, adj)
             | Operation::Cdf(_, _, _, adj)
+            | Operation::Abs(_, _, _, adj)
+            | Operation::Max(_, _, _, _, adj)
+            | Operation::Min(_, _, _, _, adj)
+            | Operation::Clamp(_, _, _, _, _, adj)
+            | Operation::Floor(_, _, _, adj)
+            | Operation::Ceil(_, _, _, adj)
+            | Operation::Signum(_, _, _, adj)
+            | Operation::Tan(_, _, _, adj)
+            | Operation::Asin(_, _, _, adj)
+            | Operation::Acos(_, _, _, adj)
+            | Operation::Atan(_, _, _, adj)
+            | Operation::Atan2(_, _, _, _, adj)
+            | Operation::Sinh(_, _, _, adj)
+            | Operation::Cosh(_, _, _, adj)
+            | Operation::Tanh(_, _, _, adj)
+            | Operation::Asinh(_, _, _, adj)
+            | Operation::Acosh(_, _, _, adj)
+            | Operation::Atanh(_, _, _, adj)
+            | Operation::Exp2(_, _, _, adj)
+            | Operation::Expm1(_, _, _, adj)
+            | Operation::Ln1p(_, _, _, adj)
+            | Operation::Cbrt(_, _, _, adj)
+            | Operation::Hypot(_, _, _, _, adj)
+            | Operation::Recip(_, _, _, adj)
+            | Operation::Powi(_, _, _, _, adj)
+            | Operation::Powf(_, _, _, _, adj)
+            | Operation::LogBase(_, _, _, _, adj)
+            | Operation::NormPdf(_, _, _, adj)
+            | Operation::NormInvCdf(_, _, _, adj)
+            | Operation::Erf(_, _, _, adj)
+            | Operation::Erfc(_, _, _, adj)
+            | Operation::NormalCdf(_, _, _, _, _, adj)
+            | Operation::Gamma(_, _, _, adj)
+            | Operation::LnGamma(_, _, _, adj)
+            | Operation::Digamma(_, _, _, adj)
+            | Operation::Beta(_, _, _, _, adj)
+            | Operation::GammaP(_, _, _, _, adj)
+            | Operation::GammaQ(_, _, _, _, adj)
+            | Operation::BetaReg(_, _, _, _, _, adj)
+            | Operation::StudentsTCdf(_, _, _, _, adj)
+            | Operation::ChiSquaredCdf(_, _, _, _, adj)
+            | Operation::LogDensity(_, _, _, _, adj)
+            | Operation::LogLikelihood(_, _, _, _, _, adj)
+            | Operation::SmoothStep(_, _, _, _, adj)
+            | Operation::SmoothMax(_, _, _, _, _, adj)
+            | Operation::SmoothAbs(_, _, _, _, adj)
+            | Operation::SmoothIndicator(_, _, _, _, _, _, adj)
+            | Operation::Select(_, _, _, _, _, _, _, _, adj)
+            | Operation::ImplicitFunction(_, _, _, _, _, adj)
+            | Operation::TridiagonalSolve(_, _, _, adj)
+            | Operation::TridiagonalSolution(_, _, _, _, adj)
+            | Operation::Cholesky(_, _, _, adj)
+            | Operation::CholeskyFactor(_, _, _, _, _, adj)
             | Operation::Value(_, _, adj) => *adj,
         }
     }
@@ -197,6 +869,59 @@ impl Operation {
             | Operation::Sqrt(_, _, _, adj)
             | Operation::Log(_, _, _, _, adj)
             | Operation::Cdf(_, _, _, adj)
+            | Operation::Abs(_, _, _, adj)
+            | Operation::Max(_, _, _, _, adj)
+            | Operation::Min(_, _, _, _, adj)
+            | Operation::Clamp(_, _, _, _, _, adj)
+            | Operation::Floor(_, _, _, adj)
+            | Operation::Ceil(_, _, _, adj)
+            | Operation::Signum(_, _, _, adj)
+            | Operation::Tan(_, _, _, adj)
+            | Operation::Asin(_, _, _, adj)
+            | Operation::Acos(_, _, _, adj)
+            | Operation::Atan(_, _, _, adj)
+            | Operation::Atan2(_, _, _, _, adj)
+            | Operation::Sinh(_, _, _, adj)
+            | Operation::Cosh(_, _, _, adj)
+            | Operation::Tanh(_, _, _, adj)
+            | Operation::Asinh(_, _, _, adj)
+            | Operation::Acosh(_, _, _, adj)
+            | Operation::Atanh(_, _, _, adj)
+            | Operation::Exp2(_, _, _, adj)
+            | Operation::Expm1(_, _, _, adj)
+            | Operation::Ln1p(_, _, _, adj)
+            | Operation::Cbrt(_, _, _, adj)
+            | Operation::Hypot(_, _, _, _, adj)
+            | Operation::Recip(_, _, _, adj)
+            | Operation::Powi(_, _, _, _, adj)
+            | Operation::Powf(_, _, _, _, adj)
+            | Operation::LogBase(_, _, _, _, adj)
+            | Operation::NormPdf(_, _, _, adj)
+            | Operation::NormInvCdf(_, _, _, adj)
+            | Operation::Erf(_, _, _, adj)
+            | Operation::Erfc(_, _, _, adj)
+            | Operation::NormalCdf(_, _, _, _, _, adj)
+            | Operation::Gamma(_, _, _, adj)
+            | Operation::LnGamma(_, _, _, adj)
+            | Operation::Digamma(_, _, _, adj)
+            | Operation::Beta(_, _, _, _, adj)
+            | Operation::GammaP(_, _, _, _, adj)
+            | Operation::GammaQ(_, _, _, _, adj)
+            | Operation::BetaReg(_, _, _, _, _, adj)
+            | Operation::StudentsTCdf(_, _, _, _, adj)
+            | Operation::ChiSquaredCdf(_, _, _, _, adj)
+            | Operation::LogDensity(_, _, _, _, adj)
+            | Operation::LogLikelihood(_, _, _, _, _, adj)
+            | Operation::SmoothStep(_, _, _, _, adj)
+            | Operation::SmoothMax(_, _, _, _, _, adj)
+            | Operation::SmoothAbs(_, _, _, _, adj)
+            | Operation::SmoothIndicator(_, _, _, _, _, _, adj)
+            | Operation::Select(_, _, _, _, _, _, _, _, adj)
+            | Operation::ImplicitFunction(_, _, _, _, _, adj)
+            | Operation::TridiagonalSolve(_, _, _, adj)
+            | Operation::TridiagonalSolution(_, _, _, _, adj)
+            | Operation::Cholesky(_, _, _, adj)
+            | Operation::CholeskyFactor(_, _, _, _, _, adj)
             | Operation::Value(_, _, adj) => *adj = val,
         }
     }
@@ -215,6 +940,59 @@ impl Operation {
             | Operation::Sqrt(_, _, _, adj)
             | Operation::Log(_, _, _, _, adj)
             | Operation::Cdf(_, _, _, adj)
+            | Operation::Abs(_, _, _, adj)
+            | Operation::Max(_, _, _, _, adj)
+            | Operation::Min(_, _, _, _, adj)
+            | Operation::Clamp(_, _, _, _, _, adj)
+            | Operation::Floor(_, _, _, adj)
+            | Operation::Ceil(_, _, _, adj)
+            | Operation::Signum(_, _, _, adj)
+            | Operation::Tan(_, _, _, adj)
+            | Operation::Asin(_, _, _, adj)
+            | Operation::Acos(_, _, _, adj)
+            | Operation::Atan(_, _, _, adj)
+            | Operation::Atan2(_, _, _, _, adj)
+            | Operation::Sinh(_, _, _, adj)
+            | Operation::Cosh(_, _, _, adj)
+            | Operation::Tanh(_, _, _, adj)
+            | Operation::Asinh(_, _, _, adj)
+            | Operation::Acosh(_, _, _, adj)
+            | Operation::Atanh(_, _, _, adj)
+            | Operation::Exp2(_, _, _, adj)
+            | Operation::Expm1(_, _, _, adj)
+            | Operation::Ln1p(_, _, _, adj)
+            | Operation::Cbrt(_, _, _, adj)
+            | Operation::Hypot(_, _, _, _, adj)
+            | Operation::Recip(_, _, _, adj)
+            | Operation::Powi(_, _, _, _, adj)
+            | Operation::Powf(_, _, _, _, adj)
+            | Operation::LogBase(_, _, _, _, adj)
+            | Operation::NormPdf(_, _, _, adj)
+            | Operation::NormInvCdf(_, _, _, adj)
+            | Operation::Erf(_, _, _, adj)
+            | Operation::Erfc(_, _, _, adj)
+            | Operation::NormalCdf(_, _, _, _, _, adj)
+            | Operation::Gamma(_, _, _, adj)
+            | Operation::LnGamma(_, _, _, adj)
+            | Operation::Digamma(_, _, _, adj)
+            | Operation::Beta(_, _, _, _, adj)
+            | Operation::GammaP(_, _, _, _, adj)
+            | Operation::GammaQ(_, _, _, _, adj)
+            | Operation::BetaReg(_, _, _, _, _, adj)
+            | Operation::StudentsTCdf(_, _, _, _, adj)
+            | Operation::ChiSquaredCdf(_, _, _, _, adj)
+            | Operation::LogDensity(_, _, _, _, adj)
+            | Operation::LogLikelihood(_, _, _, _, _, adj)
+            | Operation::SmoothStep(_, _, _, _, adj)
+            | Operation::SmoothMax(_, _, _, _, _, adj)
+            | Operation::SmoothAbs(_, _, _, _, adj)
+            | Operation::SmoothIndicator(_, _, _, _, _, _, adj)
+            | Operation::Select(_, _, _, _, _, _, _, _, adj)
+            | Operation::ImplicitFunction(_, _, _, _, _, adj)
+            | Operation::TridiagonalSolve(_, _, _, adj)
+            | Operation::TridiagonalSolution(_, _, _, _, adj)
+            | Operation::Cholesky(_, _, _, adj)
+            | Operation::CholeskyFactor(_, _, _, _, _, adj)
             | Operation::Value(_, _, adj) => *adj += val,
         }
     }
@@ -277,6 +1055,309 @@ impl Operation {
                 let s = std::format!("\"id {} Cdf res {:.5} adj {:.5}\"", id, result, adjoint);
                 s
             }
+            Operation::Abs(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Abs res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Max(id, _lhs_id, _rhs_id, result, adjoint) => {
+                let s = std::format!("\"id {} Max res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Min(id, _lhs_id, _rhs_id, result, adjoint) => {
+                let s = std::format!("\"id {} Min res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Clamp(id, _arg_id, _lo, _hi, result, adjoint) => {
+                let s = std::format!("\"id {} Clamp res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Floor(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Floor res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Ceil(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Ceil res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Signum(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Signum res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Tan(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Tan res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Asin(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Asin res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Acos(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Acos res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Atan(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Atan res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Atan2(id, _y_id, _x_id, result, adjoint) => {
+                let s = std::format!("\"id {} Atan2 res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Sinh(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Sinh res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Cosh(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Cosh res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Tanh(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Tanh res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Asinh(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Asinh res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Acosh(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Acosh res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Atanh(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Atanh res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Exp2(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Exp2 res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Expm1(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Expm1 res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Ln1p(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Ln1p res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Cbrt(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Cbrt res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Hypot(id, _lhs_id, _rhs_id, result, adjoint) => {
+                let s = std::format!("\"id {} Hypot res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Recip(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Recip res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Powi(id, _base_id, exp, result, adjoint) => {
+                let s = std::format!(
+                    "\"id {} Powi exp {} res {:.5} adj {:.5}\"",
+                    id,
+                    exp,
+                    result,
+                    adjoint
+                );
+                s
+            }
+            Operation::Powf(id, _base_id, _exp_id, result, adjoint) => {
+                let s = std::format!("\"id {} Powf res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::LogBase(id, _arg_id, _base_id, result, adjoint) => {
+                let s = std::format!("\"id {} LogBase res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::NormPdf(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} NormPdf res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::NormInvCdf(id, _arg_id, result, adjoint) => {
+                let s = std::format!(
+                    "\"id {} NormInvCdf res {:.5} adj {:.5}\"",
+                    id,
+                    result,
+                    adjoint
+                );
+                s
+            }
+            Operation::Erf(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Erf res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Erfc(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Erfc res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::NormalCdf(id, _arg_id, _mu_id, _sigma_id, result, adjoint) => {
+                let s = std::format!(
+                    "\"id {} NormalCdf res {:.5} adj {:.5}\"",
+                    id,
+                    result,
+                    adjoint
+                );
+                s
+            }
+            Operation::Gamma(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Gamma res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::LnGamma(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} LnGamma res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Digamma(id, _arg_id, result, adjoint) => {
+                let s = std::format!("\"id {} Digamma res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::Beta(id, _a_id, _b_id, result, adjoint) => {
+                let s = std::format!("\"id {} Beta res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::GammaP(id, _arg_id, _a_id, result, adjoint) => {
+                let s = std::format!("\"id {} GammaP res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::GammaQ(id, _arg_id, _a_id, result, adjoint) => {
+                let s = std::format!("\"id {} GammaQ res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::BetaReg(id, _arg_id, _a_id, _b_id, result, adjoint) => {
+                let s = std::format!("\"id {} BetaReg res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::StudentsTCdf(id, _arg_id, _nu_id, result, adjoint) => {
+                let s = std::format!(
+                    "\"id {} StudentsTCdf res {:.5} adj {:.5}\"",
+                    id,
+                    result,
+                    adjoint
+                );
+                s
+            }
+            Operation::ChiSquaredCdf(id, _arg_id, _k_id, result, adjoint) => {
+                let s = std::format!(
+                    "\"id {} ChiSquaredCdf res {:.5} adj {:.5}\"",
+                    id,
+                    result,
+                    adjoint
+                );
+                s
+            }
+            Operation::LogDensity(id, _family, _arg_ids, result, adjoint) => {
+                let s = std::format!(
+                    "\"id {} LogDensity res {:.5} adj {:.5}\"",
+                    id,
+                    result,
+                    adjoint
+                );
+                s
+            }
+            Operation::LogLikelihood(id, _family, _data, _param_ids, result, adjoint) => {
+                let s = std::format!(
+                    "\"id {} LogLikelihood res {:.5} adj {:.5}\"",
+                    id,
+                    result,
+                    adjoint
+                );
+                s
+            }
+            Operation::SmoothStep(id, _arg_id, _eps, result, adjoint) => {
+                let s = std::format!(
+                    "\"id {} SmoothStep res {:.5} adj {:.5}\"",
+                    id,
+                    result,
+                    adjoint
+                );
+                s
+            }
+            Operation::SmoothMax(id, _lhs_id, _rhs_id, _eps, result, adjoint) => {
+                let s = std::format!(
+                    "\"id {} SmoothMax res {:.5} adj {:.5}\"",
+                    id,
+                    result,
+                    adjoint
+                );
+                s
+            }
+            Operation::SmoothAbs(id, _arg_id, _eps, result, adjoint) => {
+                let s = std::format!(
+                    "\"id {} SmoothAbs res {:.5} adj {:.5}\"",
+                    id,
+                    result,
+                    adjoint
+                );
+                s
+            }
+            Operation::SmoothIndicator(id, _arg_id, _lo, _hi, _eps, result, adjoint) => {
+                let s = std::format!(
+                    "\"id {} SmoothIndicator res {:.5} adj {:.5}\"",
+                    id,
+                    result,
+                    adjoint
+                );
+                s
+            }
+            Operation::Select(
+                id,
+                _lhs_id,
+                _comparison,
+                _rhs_id,
+                _a_id,
+                _b_id,
+                _holds,
+                result,
+                adjoint,
+            ) => {
+                let s = std::format!("\"id {} Select res {:.5} adj {:.5}\"", id, result, adjoint);
+                s
+            }
+            Operation::ImplicitFunction(id, _solver, _arg_ids, _partials, result, adjoint) => {
+                let s = std::format!(
+                    "\"id {} ImplicitFunction res {:.5} adj {:.5}\"",
+                    id,
+                    result,
+                    adjoint
+                );
+                s
+            }
+            Operation::TridiagonalSolve(id, _system, result, adjoint) => {
+                let s = std::format!(
+                    "\"id {} TridiagonalSolve res {:.5} adj {:.5}\"",
+                    id,
+                    result,
+                    adjoint
+                );
+                s
+            }
+            Operation::TridiagonalSolution(id, _solve_id, _index, result, adjoint) => {
+                let s = std::format!(
+                    "\"id {} TridiagonalSolution res {:.5} adj {:.5}\"",
+                    id,
+                    result,
+                    adjoint
+                );
+                s
+            }
+            Operation::Cholesky(id, _factorization, result, adjoint) => {
+                let s = std::format!(
+                    "\"id {} Cholesky res {:.5} adj {:.5}\"",
+                    id,
+                    result,
+                    adjoint
+                );
+                s
+            }
+            Operation::CholeskyFactor(id, _factorization_id, _row, _column, result, adjoint) => {
+                let s = std::format!(
+                    "\"id {} CholeskyFactor res {:.5} adj {:.5}\"",
+                    id,
+                    result,
+                    adjoint
+                );
+                s
+            }
             Operation::Value(id, value, adjoint) => {
                 let s = std::format!("\"id {} Val {:.5} adj {:.5}\"", id, value, adjoint);
                 s
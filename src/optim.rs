@@ -0,0 +1,444 @@
+//! First-order minimization of an objective recorded on `Number`.
+//!
+//! Every iterate is recorded once and swept for the gradient. A method picks
+//! a descent direction from the gradients seen so far, and a line search
+//! picks the step along it:
+//!
+//! - gradient descent steps along -g,
+//! - momentum along the heavy-ball velocity v = beta v + g,
+//! - Adam along the bias-corrected first moment over the root of the second,
+//! - L-BFGS along the two-loop recursion over the last `memory` steps.
+//!
+//! Should a direction not descend, the iteration falls back to -g. The
+//! Armijo search backtracks on values evaluated without the tape; the Wolfe
+//! search also asks for the curvature condition, which needs the gradients of
+//! the trial points.
+
+use crate::automatic_differentiator::AutomaticDifferentiator;
+use crate::linear_algebra::norm;
+use crate::number::Number;
+
+pub mod constrained;
+
+/// How the descent direction is chosen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    GradientDescent,
+    /// Heavy ball with velocity decay `beta`.
+    Momentum {
+        beta: f64,
+    },
+    /// Adam with decay rates `beta1` and `beta2` of the moments of the
+    /// gradient, and `epsilon` added to the root of the second moment.
+    Adam {
+        beta1: f64,
+        beta2: f64,
+        epsilon: f64,
+    },
+    /// Limited-memory BFGS keeping the last `memory` steps.
+    Lbfgs {
+        memory: usize,
+    },
+}
+
+/// How the step along the direction is chosen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineSearch {
+    /// Backtracking until f(x + a d) <= f(x) + c1 a g.d, halving the step.
+    Armijo { c1: f64 },
+    /// Strong Wolfe conditions: sufficient decrease with `c1` and
+    /// |g(x + a d).d| <= c2 |g.d|.
+    Wolfe { c1: f64, c2: f64 },
+}
+
+/// Why the minimization stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    /// The gradient norm fell below the tolerance.
+    GradientTolerance,
+    /// The objective stopped decreasing by more than the tolerance.
+    ObjectiveTolerance,
+    MaxIterations,
+    /// No step along the direction satisfied the line search.
+    LineSearchFailed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptimizationResult {
+    pub x: Vec<f64>,
+    pub value: f64,
+    pub gradient: Vec<f64>,
+    /// Euclidean norm of `gradient`.
+    pub gradient_norm: f64,
+    pub iterations: usize,
+    /// Evaluations of the objective, with or without the tape.
+    pub evaluations: usize,
+    pub termination: Termination,
+    /// Objective at the initial point and after every iteration.
+    pub objective_history: Vec<f64>,
+    /// Gradient norm at the initial point and after every iteration.
+    pub gradient_norm_history: Vec<f64>,
+}
+
+impl OptimizationResult {
+    pub fn converged(&self) -> bool {
+        matches!(
+            self.termination,
+            Termination::GradientTolerance | Termination::ObjectiveTolerance
+        )
+    }
+}
+
+/// A first-order minimizer. [`Optimizer::new`] sets the usual defaults for
+/// the method, which can be changed through the public fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Optimizer {
+    pub method: Method,
+    pub line_search: LineSearch,
+    /// First step tried by the line search, as a multiple of the direction.
+    pub initial_step: f64,
+    pub max_iterations: usize,
+    /// Stops once the gradient norm is below.
+    pub gradient_tolerance: f64,
+    /// Stops once an iteration lowers the objective by less than this,
+    /// relative to the objective. Zero to disable.
+    pub objective_tolerance: f64,
+}
+
+struct Point {
+    x: Vec<f64>,
+    value: f64,
+    gradient: Vec<f64>,
+}
+
+impl Optimizer {
+    pub fn new(method: Method) -> Self {
+        let initial_step = match method {
+            Method::Adam { .. } => 0.1,
+            _ => 1.0,
+        };
+        Optimizer {
+            method,
+            line_search: LineSearch::Wolfe { c1: 1e-4, c2: 0.9 },
+            initial_step,
+            max_iterations: 1000,
+            gradient_tolerance: 1e-8,
+            objective_tolerance: 0.0,
+        }
+    }
+
+    pub fn gradient_descent() -> Self {
+        Optimizer::new(Method::GradientDescent)
+    }
+
+    pub fn momentum() -> Self {
+        Optimizer::new(Method::Momentum { beta: 0.9 })
+    }
+
+    pub fn adam() -> Self {
+        Optimizer::new(Method::Adam {
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+        })
+    }
+
+    pub fn lbfgs() -> Self {
+        Optimizer::new(Method::Lbfgs { memory: 10 })
+    }
+
+    /// Minimizes `objective` from `initial`.
+    pub fn minimize<F>(&self, objective: F, initial: &[f64]) -> OptimizationResult
+    where
+        F: Fn(&[Number]) -> Number,
+    {
+        let mut automatic_differentiator = AutomaticDifferentiator::new();
+        let mut evaluations = 0;
+        let mut evaluate = |x: &[f64]| -> Point {
+            evaluations += 1;
+            let arguments: Vec<Number> = x.iter().map(|x| Number::new(*x)).collect();
+            let evaluation = automatic_differentiator.derivatives(&objective, &arguments);
+            Point {
+                x: x.to_vec(),
+                value: evaluation.result,
+                gradient: arguments
+                    .iter()
+                    .map(|argument| evaluation.derivative(*argument))
+                    .collect(),
+            }
+        };
+
+        let mut point = evaluate(initial);
+        let mut objective_history = vec![point.value];
+        let mut gradient_norm_history = vec![norm(&point.gradient)];
+        let mut state = DirectionState::new(initial.len());
+        let mut untaped_evaluations = 0;
+        let mut termination = Termination::MaxIterations;
+        let mut iterations = 0;
+
+        while iterations < self.max_iterations {
+            if norm(&point.gradient) <= self.gradient_tolerance {
+                termination = Termination::GradientTolerance;
+                break;
+            }
+            let mut direction = state.direction(self.method, &point.gradient);
+            if dot(&direction, &point.gradient) >= 0.0 {
+                direction = point.gradient.iter().map(|g| -g).collect();
+                state.reset();
+            }
+
+            let next = match self.line_search {
+                LineSearch::Armijo { c1 } => {
+                    let value = |x: &[f64]| {
+                        untaped_evaluations += 1;
+                        crate::no_tape(|| {
+                            let args: Vec<Number> = x.iter().map(|x| Number::new(*x)).collect();
+                            objective(&args).result
+                        })
+                    };
+                    armijo(&point, &direction, self.initial_step, c1, value).map(|x| evaluate(&x))
+                }
+                LineSearch::Wolfe { c1, c2 } => {
+                    wolfe(&point, &direction, self.initial_step, c1, c2, &mut evaluate)
+                }
+            };
+            let Some(next) = next else {
+                termination = Termination::LineSearchFailed;
+                break;
+            };
+
+            iterations += 1;
+            let decrease = point.value - next.value;
+            state.update(self.method, &point, &next);
+            point = next;
+            objective_history.push(point.value);
+            gradient_norm_history.push(norm(&point.gradient));
+            if self.objective_tolerance > 0.0
+                && decrease <= self.objective_tolerance * point.value.abs()
+            {
+                termination = Termination::ObjectiveTolerance;
+                break;
+            }
+        }
+        if termination == Termination::MaxIterations
+            && norm(&point.gradient) <= self.gradient_tolerance
+        {
+            termination = Termination::GradientTolerance;
+        }
+
+        OptimizationResult {
+            gradient_norm: norm(&point.gradient),
+            x: point.x,
+            value: point.value,
+            gradient: point.gradient,
+            iterations,
+            evaluations: evaluations + untaped_evaluations,
+            termination,
+            objective_history,
+            gradient_norm_history,
+        }
+    }
+}
+
+/// What the methods remember between iterations.
+struct DirectionState {
+    /// Momentum velocity, or Adam first moment.
+    first: Vec<f64>,
+    /// Adam second moment.
+    second: Vec<f64>,
+    /// Adam steps since the last reset, for the bias correction.
+    steps: i32,
+    /// L-BFGS pairs of steps s and gradient changes y, oldest first.
+    pairs: Vec<(Vec<f64>, Vec<f64>)>,
+}
+
+impl DirectionState {
+    fn new(n: usize) -> Self {
+        DirectionState {
+            first: vec![0.0; n],
+            second: vec![0.0; n],
+            steps: 0,
+            pairs: Vec::new(),
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = DirectionState::new(self.first.len());
+    }
+
+    fn direction(&mut self, method: Method, gradient: &[f64]) -> Vec<f64> {
+        match method {
+            Method::GradientDescent => gradient.iter().map(|g| -g).collect(),
+            Method::Momentum { beta } => {
+                for (v, g) in self.first.iter_mut().zip(gradient) {
+                    *v = beta * *v + g;
+                }
+                self.first.iter().map(|v| -v).collect()
+            }
+            Method::Adam {
+                beta1,
+                beta2,
+                epsilon,
+            } => {
+                self.steps += 1;
+                for ((m, v), g) in self.first.iter_mut().zip(&mut self.second).zip(gradient) {
+                    *m = beta1 * *m + (1.0 - beta1) * g;
+                    *v = beta2 * *v + (1.0 - beta2) * g * g;
+                }
+                let first_correction = 1.0 - beta1.powi(self.steps);
+                let second_correction = 1.0 - beta2.powi(self.steps);
+                self.first
+                    .iter()
+                    .zip(&self.second)
+                    .map(|(m, v)| {
+                        -(m / first_correction) / ((v / second_correction).sqrt() + epsilon)
+                    })
+                    .collect()
+            }
+            Method::Lbfgs { .. } => self.two_loop(gradient),
+        }
+    }
+
+    /// -H g with H the L-BFGS inverse Hessian of the stored pairs.
+    fn two_loop(&self, gradient: &[f64]) -> Vec<f64> {
+        let mut q = gradient.to_vec();
+        let mut alphas = Vec::with_capacity(self.pairs.len());
+        for (s, y) in self.pairs.iter().rev() {
+            let alpha = dot(s, &q) / dot(y, s);
+            for (q, y) in q.iter_mut().zip(y) {
+                *q -= alpha * y;
+            }
+            alphas.push(alpha);
+        }
+        if let Some((s, y)) = self.pairs.last() {
+            let gamma = dot(s, y) / dot(y, y);
+            for q in q.iter_mut() {
+                *q *= gamma;
+            }
+        }
+        for ((s, y), alpha) in self.pairs.iter().zip(alphas.iter().rev()) {
+            let beta = dot(y, &q) / dot(y, s);
+            for (q, s) in q.iter_mut().zip(s) {
+                *q += (alpha - beta) * s;
+            }
+        }
+        q.iter().map(|q| -q).collect()
+    }
+
+    fn update(&mut self, method: Method, previous: &Point, next: &Point) {
+        if let Method::Lbfgs { memory } = method {
+            let s: Vec<f64> = next.x.iter().zip(&previous.x).map(|(a, b)| a - b).collect();
+            let y: Vec<f64> = next
+                .gradient
+                .iter()
+                .zip(&previous.gradient)
+                .map(|(a, b)| a - b)
+                .collect();
+            // keeps the inverse Hessian positive definite
+            if dot(&s, &y) > 1e-12 * norm(&s) * norm(&y) {
+                if self.pairs.len() == memory {
+                    self.pairs.remove(0);
+                }
+                self.pairs.push((s, y));
+            }
+        }
+    }
+}
+
+/// Backtracks from `initial_step` along `direction` until the sufficient
+/// decrease condition holds.
+fn armijo<V>(
+    point: &Point,
+    direction: &[f64],
+    initial_step: f64,
+    c1: f64,
+    mut value: V,
+) -> Option<Vec<f64>>
+where
+    V: FnMut(&[f64]) -> f64,
+{
+    let slope = dot(&point.gradient, direction);
+    let mut step = initial_step;
+    for _ in 0..60 {
+        let x = along(&point.x, direction, step);
+        let trial = value(&x);
+        if trial <= point.value + c1 * step * slope {
+            return Some(x);
+        }
+        step *= 0.5;
+    }
+    None
+}
+
+/// Strong Wolfe line search of Nocedal and Wright, algorithms 3.5 and 3.6:
+/// the step grows until it brackets an acceptable step, which bisection then
+/// finds.
+fn wolfe<E>(
+    point: &Point,
+    direction: &[f64],
+    initial_step: f64,
+    c1: f64,
+    c2: f64,
+    evaluate: &mut E,
+) -> Option<Point>
+where
+    E: FnMut(&[f64]) -> Point,
+{
+    let slope = dot(&point.gradient, direction);
+    let sufficient = |step: f64, value: f64| value <= point.value + c1 * step * slope;
+    let curvature = |trial: &Point| dot(&trial.gradient, direction).abs() <= -c2 * slope;
+
+    let zoom = |mut low: (f64, Point), mut high: f64, evaluate: &mut E| -> Option<Point> {
+        for _ in 0..60 {
+            let step = 0.5 * (low.0 + high);
+            let trial = evaluate(&along(&point.x, direction, step));
+            if !sufficient(step, trial.value) || trial.value >= low.1.value {
+                high = step;
+            } else {
+                if curvature(&trial) {
+                    return Some(trial);
+                }
+                if dot(&trial.gradient, direction) * (high - low.0) >= 0.0 {
+                    high = low.0;
+                }
+                low = (step, trial);
+            }
+        }
+        // the bracket collapsed: settle for sufficient decrease
+        (low.0 > 0.0).then_some(low.1)
+    };
+
+    let mut previous = (
+        0.0,
+        Point {
+            x: point.x.clone(),
+            value: point.value,
+            gradient: point.gradient.clone(),
+        },
+    );
+    let mut step = initial_step;
+    for i in 0..60 {
+        let trial = evaluate(&along(&point.x, direction, step));
+        if !sufficient(step, trial.value) || (i > 0 && trial.value >= previous.1.value) {
+            return zoom(previous, step, evaluate);
+        }
+        if curvature(&trial) {
+            return Some(trial);
+        }
+        if dot(&trial.gradient, direction) >= 0.0 {
+            let high = previous.0;
+            return zoom((step, trial), high, evaluate);
+        }
+        previous = (step, trial);
+        step *= 2.0;
+    }
+    None
+}
+
+fn along(x: &[f64], direction: &[f64], step: f64) -> Vec<f64> {
+    x.iter().zip(direction).map(|(x, d)| x + step * d).collect()
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
@@ -0,0 +1,452 @@
+//! Minimization under equality, inequality and box constraints.
+//!
+//! The augmented Lagrangian method of Powell, Hestenes and Rockafellar
+//! replaces the equalities c(x) = 0 and inequalities g(x) >= 0 by the
+//! penalized objective
+//!
+//! ```text
+//! f(x) + rho/2 sum_i (c_i(x) - lambda_i/rho)^2 + rho/2 sum_j max(0, mu_j/rho - g_j(x))^2
+//! ```
+//!
+//! which is minimized over the box alone, then updates the multipliers as
+//! lambda_i - rho c_i(x) and max(0, mu_j - rho g_j(x)). The penalty rho
+//! grows whenever the constraints are not satisfied markedly better than
+//! after the previous minimization.
+//!
+//! The bounds are never relaxed: the subproblems are solved by the spectral
+//! projected gradient method of Birgin, Martinez and Raydan, with every
+//! iterate projected onto the box. The penalized objective is recorded with
+//! the constraints, so one sweep gives its gradient.
+
+use crate::automatic_differentiator::{AutomaticDifferentiator, Evaluation};
+use crate::number::Number;
+
+use super::dot;
+
+/// A function of the variables recorded on `Number`.
+type Function<'a> = Box<dyn Fn(&[Number]) -> Number + 'a>;
+
+/// An objective with its constraints, added one by one. Equalities are
+/// satisfied at zero, inequalities at zero or above.
+pub struct ConstrainedProblem<'a> {
+    objective: Function<'a>,
+    equalities: Vec<Function<'a>>,
+    inequalities: Vec<Function<'a>>,
+    lower: Vec<f64>,
+    upper: Vec<f64>,
+}
+
+impl<'a> ConstrainedProblem<'a> {
+    pub fn new<F>(objective: F) -> Self
+    where
+        F: Fn(&[Number]) -> Number + 'a,
+    {
+        ConstrainedProblem {
+            objective: Box::new(objective),
+            equalities: Vec::new(),
+            inequalities: Vec::new(),
+            lower: Vec::new(),
+            upper: Vec::new(),
+        }
+    }
+
+    /// Adds the constraint `constraint(x) = 0`.
+    pub fn equality<C>(&mut self, constraint: C) -> &mut Self
+    where
+        C: Fn(&[Number]) -> Number + 'a,
+    {
+        self.equalities.push(Box::new(constraint));
+        self
+    }
+
+    /// Adds the constraint `constraint(x) >= 0`.
+    pub fn inequality<C>(&mut self, constraint: C) -> &mut Self
+    where
+        C: Fn(&[Number]) -> Number + 'a,
+    {
+        self.inequalities.push(Box::new(constraint));
+        self
+    }
+
+    /// Keeps every variable between its bounds, which may be infinite.
+    /// Without bounds the variables are free.
+    pub fn bounds(&mut self, lower: Vec<f64>, upper: Vec<f64>) -> &mut Self {
+        assert!(
+            lower.len() == upper.len() && lower.iter().zip(&upper).all(|(lo, hi)| lo <= hi),
+            "the lower and upper bounds of the variables must pair up"
+        );
+        self.lower = lower;
+        self.upper = upper;
+        self
+    }
+
+    /// Values of the equalities followed by the inequalities.
+    fn constraints(&self, x: &[Number]) -> Vec<Number> {
+        self.equalities
+            .iter()
+            .chain(&self.inequalities)
+            .map(|constraint| constraint(x))
+            .collect()
+    }
+
+    fn project(&self, x: &mut [f64]) {
+        for ((x, lo), hi) in x.iter_mut().zip(&self.lower).zip(&self.upper) {
+            *x = x.clamp(*lo, *hi);
+        }
+    }
+}
+
+/// Why the minimization stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    /// The constraints and the first-order optimality conditions hold within
+    /// the tolerances.
+    Converged,
+    MaxIterations,
+}
+
+/// Diagnostics of one minimization of the penalized objective.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OuterIteration {
+    pub objective: f64,
+    /// Largest violation of a constraint, or of the complementarity of an
+    /// inequality with its multiplier.
+    pub constraint_violation: f64,
+    /// Projected gradient of the Lagrangian, in the maximum norm.
+    pub optimality: f64,
+    /// Penalty the subproblem was solved with.
+    pub penalty: f64,
+    pub inner_iterations: usize,
+    /// The projected gradient minimization stopped early as backtracking
+    /// found no step lowering the objective, so its minimum is only as good
+    /// as the last accepted iterate.
+    pub line_search_failed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstrainedResult {
+    pub x: Vec<f64>,
+    pub value: f64,
+    pub equality_multipliers: Vec<f64>,
+    pub inequality_multipliers: Vec<f64>,
+    /// Jacobian of the equalities followed by the inequalities at `x`, one row
+    /// per constraint.
+    pub constraint_jacobian: Vec<Vec<f64>>,
+    pub constraint_violation: f64,
+    /// Projected gradient of the Lagrangian f - lambda.c - mu.g at `x`, in the
+    /// maximum norm.
+    pub optimality: f64,
+    pub iterations: usize,
+    pub termination: Termination,
+    pub history: Vec<OuterIteration>,
+}
+
+impl ConstrainedResult {
+    pub fn converged(&self) -> bool {
+        self.termination == Termination::Converged
+    }
+}
+
+/// The augmented Lagrangian method. [`AugmentedLagrangian::new`] sets the
+/// usual defaults, which can be changed through the public fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AugmentedLagrangian {
+    pub initial_penalty: f64,
+    /// Factor the penalty grows by.
+    pub penalty_growth: f64,
+    /// Fraction of the previous constraint violation below which the penalty
+    /// is kept.
+    pub required_decrease: f64,
+    pub max_iterations: usize,
+    /// Iterations of each projected gradient minimization.
+    pub max_inner_iterations: usize,
+    pub feasibility_tolerance: f64,
+    pub optimality_tolerance: f64,
+}
+
+impl Default for AugmentedLagrangian {
+    fn default() -> Self {
+        AugmentedLagrangian::new()
+    }
+}
+
+impl AugmentedLagrangian {
+    pub fn new() -> Self {
+        AugmentedLagrangian {
+            initial_penalty: 10.0,
+            penalty_growth: 10.0,
+            required_decrease: 0.5,
+            max_iterations: 50,
+            max_inner_iterations: 10000,
+            feasibility_tolerance: 1e-8,
+            optimality_tolerance: 1e-8,
+        }
+    }
+
+    /// Minimizes the objective of `problem` from `initial`, projected onto
+    /// the bounds first.
+    pub fn minimize(&self, problem: &ConstrainedProblem, initial: &[f64]) -> ConstrainedResult {
+        assert!(
+            problem.lower.is_empty() || problem.lower.len() == initial.len(),
+            "the bounds need one entry per variable"
+        );
+
+        let equalities = problem.equalities.len();
+        let mut equality_multipliers = vec![0.0; equalities];
+        let mut inequality_multipliers = vec![0.0; problem.inequalities.len()];
+        let mut penalty = self.initial_penalty;
+        let mut x = initial.to_vec();
+        problem.project(&mut x);
+        let mut history: Vec<OuterIteration> = Vec::new();
+        let mut termination = Termination::MaxIterations;
+
+        for iteration in 0..self.max_iterations {
+            // loose subproblems while the multipliers are far off
+            let tolerance = self
+                .optimality_tolerance
+                .max(0.1f64.powi(iteration as i32 + 1));
+            let augmented_lagrangian = |x: &[Number]| {
+                let mut value = (problem.objective)(x);
+                for (constraint, multiplier) in problem.equalities.iter().zip(&equality_multipliers)
+                {
+                    let shifted = constraint(x) - multiplier / penalty;
+                    value = value + 0.5 * penalty * shifted * shifted;
+                }
+                for (constraint, multiplier) in
+                    problem.inequalities.iter().zip(&inequality_multipliers)
+                {
+                    let shortfall = (multiplier / penalty - constraint(x)).max(0.0);
+                    value = value + 0.5 * penalty * shortfall * shortfall;
+                }
+                value
+            };
+            let (minimum, inner_iterations, line_search_failed) = spectral_projected_gradient(
+                augmented_lagrangian,
+                problem,
+                x,
+                tolerance,
+                self.max_inner_iterations,
+            );
+            x = minimum;
+
+            let (constraints, objective) = crate::no_tape(|| {
+                let args: Vec<Number> = x.iter().map(|x| Number::new(*x)).collect();
+                let values: Vec<f64> = problem
+                    .constraints(&args)
+                    .iter()
+                    .map(|c| c.result)
+                    .collect();
+                (values, (problem.objective)(&args).result)
+            });
+            let (equality_values, inequality_values) = constraints.split_at(equalities);
+            let violation = equality_values
+                .iter()
+                .map(|c| c.abs())
+                .chain(
+                    inequality_values
+                        .iter()
+                        .zip(&inequality_multipliers)
+                        .map(|(g, mu)| g.min(mu / penalty).abs()),
+                )
+                .fold(0.0, f64::max);
+
+            for (multiplier, c) in equality_multipliers.iter_mut().zip(equality_values) {
+                *multiplier -= penalty * c;
+            }
+            for (multiplier, g) in inequality_multipliers.iter_mut().zip(inequality_values) {
+                *multiplier = (*multiplier - penalty * g).max(0.0);
+            }
+
+            // the gradient of the penalized objective is that of the
+            // Lagrangian with the updated multipliers
+            let (optimality, _) =
+                lagrangian_optimality(problem, &x, &equality_multipliers, &inequality_multipliers);
+            history.push(OuterIteration {
+                objective,
+                constraint_violation: violation,
+                optimality,
+                penalty,
+                inner_iterations,
+                line_search_failed,
+            });
+            if violation <= self.feasibility_tolerance && optimality <= self.optimality_tolerance {
+                termination = Termination::Converged;
+                break;
+            }
+            let previous = history
+                .iter()
+                .rev()
+                .nth(1)
+                .map_or(f64::INFINITY, |h| h.constraint_violation);
+            if violation > self.feasibility_tolerance
+                && violation > self.required_decrease * previous
+            {
+                penalty *= self.penalty_growth;
+            }
+        }
+
+        let (optimality, constraint_jacobian) =
+            lagrangian_optimality(problem, &x, &equality_multipliers, &inequality_multipliers);
+        let last = history.last();
+        ConstrainedResult {
+            value: last.map_or(f64::NAN, |h| h.objective),
+            constraint_violation: last.map_or(f64::NAN, |h| h.constraint_violation),
+            x,
+            equality_multipliers,
+            inequality_multipliers,
+            constraint_jacobian,
+            optimality,
+            iterations: history.len(),
+            termination,
+            history,
+        }
+    }
+}
+
+/// Projected gradient of the Lagrangian at `x` in the maximum norm, from the
+/// gradient of the objective and the Jacobian of the constraints, which is
+/// returned as well.
+fn lagrangian_optimality(
+    problem: &ConstrainedProblem,
+    x: &[f64],
+    equality_multipliers: &[f64],
+    inequality_multipliers: &[f64],
+) -> (f64, Vec<Vec<f64>>) {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+    let arguments: Vec<Number> = x.iter().map(|x| Number::new(*x)).collect();
+    let gradient_of = |evaluation: &Evaluation| -> Vec<f64> {
+        arguments
+            .iter()
+            .map(|argument| evaluation.derivative(*argument))
+            .collect()
+    };
+
+    let mut gradient =
+        gradient_of(&automatic_differentiator.derivatives(&problem.objective, &arguments));
+    let jacobian: Vec<Vec<f64>> = automatic_differentiator
+        .jacobian(|args: &[Number]| problem.constraints(args), &arguments)
+        .iter()
+        .map(gradient_of)
+        .collect();
+    let multipliers = equality_multipliers.iter().chain(inequality_multipliers);
+    for (row, multiplier) in jacobian.iter().zip(multipliers) {
+        for (g, partial) in gradient.iter_mut().zip(row) {
+            *g -= multiplier * partial;
+        }
+    }
+    (projected_gradient_norm(problem, x, &gradient), jacobian)
+}
+
+/// |P(x - g) - x| in the maximum norm, zero at a stationary point of the box.
+fn projected_gradient_norm(problem: &ConstrainedProblem, x: &[f64], gradient: &[f64]) -> f64 {
+    let mut projected: Vec<f64> = x.iter().zip(gradient).map(|(x, g)| x - g).collect();
+    problem.project(&mut projected);
+    projected
+        .iter()
+        .zip(x)
+        .map(|(p, x)| (p - x).abs())
+        .fold(0.0, f64::max)
+}
+
+/// Minimizes `objective` over the bounds of `problem` from `initial`, which
+/// lies within them, until the projected gradient norm is below `tolerance`.
+/// Returns the minimum, the iterations taken and whether it stopped because
+/// backtracking found no acceptable step.
+///
+/// Each iteration moves to the projection of x - g / alpha, with the
+/// Barzilai-Borwein curvature alpha = s.y / s.s of the last step, and
+/// backtracks along that move until it lowers the objective below the
+/// largest of the last ten values.
+fn spectral_projected_gradient<F>(
+    objective: F,
+    problem: &ConstrainedProblem,
+    initial: Vec<f64>,
+    tolerance: f64,
+    max_iterations: usize,
+) -> (Vec<f64>, usize, bool)
+where
+    F: Fn(&[Number]) -> Number,
+{
+    const MEMORY: usize = 10;
+    const SUFFICIENT_DECREASE: f64 = 1e-4;
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+    let mut evaluate = |x: &[f64]| -> (f64, Vec<f64>) {
+        let arguments: Vec<Number> = x.iter().map(|x| Number::new(*x)).collect();
+        let evaluation = automatic_differentiator.derivatives(&objective, &arguments);
+        let gradient = arguments
+            .iter()
+            .map(|argument| evaluation.derivative(*argument))
+            .collect();
+        (evaluation.result, gradient)
+    };
+    let value = |x: &[f64]| {
+        crate::no_tape(|| {
+            let args: Vec<Number> = x.iter().map(|x| Number::new(*x)).collect();
+            objective(&args).result
+        })
+    };
+
+    let mut x = initial;
+    let (mut f, mut gradient) = evaluate(&x);
+    let mut recent = vec![f];
+    let first = projected_gradient_norm(problem, &x, &gradient);
+    let mut step = if first > 0.0 { 1.0 / first } else { 1.0 };
+
+    for iteration in 0..max_iterations {
+        if projected_gradient_norm(problem, &x, &gradient) <= tolerance {
+            return (x, iteration, false);
+        }
+        let mut target: Vec<f64> = x.iter().zip(&gradient).map(|(x, g)| x - step * g).collect();
+        problem.project(&mut target);
+        let direction: Vec<f64> = target.iter().zip(&x).map(|(t, x)| t - x).collect();
+        let slope = dot(&gradient, &direction);
+        let reference = recent.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        // nonmonotone backtracking with safeguarded quadratic interpolation
+        let mut lambda = 1.0;
+        let mut trial: Vec<f64>;
+        loop {
+            trial = x
+                .iter()
+                .zip(&direction)
+                .map(|(x, d)| x + lambda * d)
+                .collect();
+            let trial_value = value(&trial);
+            if trial_value <= reference + SUFFICIENT_DECREASE * lambda * slope {
+                break;
+            }
+            if lambda < 1e-16 {
+                return (x, iteration, true);
+            }
+            let interpolated = -0.5 * lambda * lambda * slope / (trial_value - f - lambda * slope);
+            lambda = if (0.1 * lambda..=0.9 * lambda).contains(&interpolated) {
+                interpolated
+            } else {
+                0.5 * lambda
+            };
+        }
+
+        let (next_f, next_gradient) = evaluate(&trial);
+        let s: Vec<f64> = trial.iter().zip(&x).map(|(a, b)| a - b).collect();
+        let y: Vec<f64> = next_gradient
+            .iter()
+            .zip(&gradient)
+            .map(|(a, b)| a - b)
+            .collect();
+        let curvature = dot(&s, &y);
+        step = if curvature > 0.0 {
+            (dot(&s, &s) / curvature).clamp(1e-10, 1e10)
+        } else {
+            1e10
+        };
+
+        x = trial;
+        f = next_f;
+        gradient = next_gradient;
+        if recent.len() == MEMORY {
+            recent.remove(0);
+        }
+        recent.push(f);
+    }
+    (x, max_iterations, false)
+}
@@ -0,0 +1,87 @@
+//! Seeded pseudo-random numbers without external dependencies, so that Monte
+//! Carlo results are reproducible from a single `u64` seed.
+
+use crate::special_functions;
+
+/// 2^-53, the spacing of the uniforms returned by [`Rng::uniform`].
+const UNIFORM_SPACING: f64 = 1.0 / (1u64 << 53) as f64;
+
+/// How standard normal draws are made from uniforms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalMethod {
+    /// Box-Muller transform of two uniforms, giving two normals per pair.
+    BoxMuller,
+    /// Inverse of the normal distribution function at one uniform.
+    InverseCdf,
+}
+
+/// xoshiro256** generator. The 256 bits of state are filled from the seed by
+/// SplitMix64, as recommended by the authors of xoshiro.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: [u64; 4],
+    spare_normal: Option<f64>,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut splitmix = seed;
+        let mut next = || {
+            splitmix = splitmix.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = splitmix;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+        Rng {
+            state: [next(), next(), next(), next()],
+            spare_normal: None,
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Uniform on the open interval (0, 1), so its logarithm and inverse normal
+    /// distribution function are finite.
+    pub fn uniform(&mut self) -> f64 {
+        ((self.next_u64() >> 11) as f64 + 0.5) * UNIFORM_SPACING
+    }
+
+    /// Standard normal by the Box-Muller transform. Each transform gives two
+    /// normals; the second is returned by the next call.
+    pub fn normal(&mut self) -> f64 {
+        if let Some(normal) = self.spare_normal.take() {
+            return normal;
+        }
+        let radius = (-2.0 * self.uniform().ln()).sqrt();
+        let angle = std::f64::consts::TAU * self.uniform();
+        self.spare_normal = Some(radius * angle.sin());
+        radius * angle.cos()
+    }
+
+    /// Standard normal by inverting the normal distribution function.
+    pub fn normal_inverse_cdf(&mut self) -> f64 {
+        special_functions::norm_inv_cdf(self.uniform())
+    }
+
+    /// `n` standard normals drawn with `method`.
+    pub fn normals(&mut self, n: usize, method: NormalMethod) -> Vec<f64> {
+        (0..n)
+            .map(|_| match method {
+                NormalMethod::BoxMuller => self.normal(),
+                NormalMethod::InverseCdf => self.normal_inverse_cdf(),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,341 @@
+//! Newton's method for systems of equations and for single equations.
+//!
+//! For a system F(x) = 0 of as many equations as unknowns the residuals are
+//! recorded once per iteration and a sweep from each gives a row of the
+//! Jacobian J. The Newton step solves J dx = -F. It is globalized by
+//! halving the fraction t of the step taken until the residual norm falls to
+//! (1 - c t) times its value, where the linearization predicts (1 - t).
+//!
+//! A single equation f(x) = 0 takes the Newton step -f / f' or Halley's step
+//! -2 f f' / (2 f'^2 - f f''), backtracking on |f| in the same way. The tape
+//! gives f' in one sweep; f'' is either supplied by the caller or the central
+//! difference of the swept f'.
+
+use crate::automatic_differentiator::AutomaticDifferentiator;
+use crate::linear_algebra::{self, norm};
+use crate::number::Number;
+
+/// Why the iteration stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    /// The residual norm fell below the tolerance.
+    Converged,
+    MaxIterations,
+    /// The Jacobian, or the derivative, has no inverse at the last iterate.
+    SingularJacobian,
+    /// No step along the Newton direction decreased the residual norm.
+    LineSearchFailed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewtonResult {
+    pub x: Vec<f64>,
+    pub residuals: Vec<f64>,
+    /// Euclidean norm of `residuals`.
+    pub residual_norm: f64,
+    /// Jacobian of the residuals at `x`, one row per residual.
+    pub jacobian: Vec<Vec<f64>>,
+    pub iterations: usize,
+    pub termination: Termination,
+    /// Residual norm at the initial point and after every iteration.
+    pub residual_norm_history: Vec<f64>,
+}
+
+impl NewtonResult {
+    pub fn converged(&self) -> bool {
+        self.termination == Termination::Converged
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalarRoot {
+    pub x: f64,
+    /// f at `x`.
+    pub value: f64,
+    /// f' at `x`.
+    pub derivative: f64,
+    pub iterations: usize,
+    pub termination: Termination,
+    /// |f| at the initial point and after every iteration.
+    pub residual_history: Vec<f64>,
+}
+
+impl ScalarRoot {
+    pub fn converged(&self) -> bool {
+        self.termination == Termination::Converged
+    }
+}
+
+/// Newton's method with a backtracking line search. [`NewtonSolver::new`]
+/// sets the usual defaults, which can be changed through the public fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewtonSolver {
+    pub max_iterations: usize,
+    /// Stops once the residual norm is below.
+    pub tolerance: f64,
+    /// Fraction of the decrease predicted by the linearization a step must
+    /// achieve.
+    pub sufficient_decrease: f64,
+    /// Halvings of the step before the line search gives up.
+    pub max_backtracks: usize,
+}
+
+impl Default for NewtonSolver {
+    fn default() -> Self {
+        NewtonSolver::new()
+    }
+}
+
+/// Solves `residual(x) = 0` from `x0` with the defaults of [`NewtonSolver`].
+pub fn newton_solve<R>(residual: R, x0: &[f64]) -> NewtonResult
+where
+    R: Fn(&[Number]) -> Vec<Number>,
+{
+    NewtonSolver::new().solve(residual, x0)
+}
+
+/// Solves `f(x) = 0` from `x0` by Newton's method with the defaults of
+/// [`NewtonSolver`].
+pub fn newton<F>(f: F, x0: f64) -> ScalarRoot
+where
+    F: Fn(Number) -> Number,
+{
+    NewtonSolver::new().newton(f, x0)
+}
+
+/// Solves `f(x) = 0` from `x0` by Halley's method with the defaults of
+/// [`NewtonSolver`].
+pub fn halley<F>(f: F, x0: f64) -> ScalarRoot
+where
+    F: Fn(Number) -> Number,
+{
+    NewtonSolver::new().halley(f, x0)
+}
+
+/// Solves `f(x) = 0` from `x0` by Halley's method with the given f'' and the
+/// defaults of [`NewtonSolver`].
+pub fn halley_with_second_derivative<F, S>(f: F, second_derivative: S, x0: f64) -> ScalarRoot
+where
+    F: Fn(Number) -> Number,
+    S: Fn(f64) -> f64,
+{
+    NewtonSolver::new().halley_with_second_derivative(f, second_derivative, x0)
+}
+
+/// Where the scalar iteration takes f'' from.
+enum Curvature<'a> {
+    /// Newton's step, which needs none.
+    None,
+    /// Central difference of f' from sweeps at two nearby points.
+    Differenced,
+    Given(&'a dyn Fn(f64) -> f64),
+}
+
+impl NewtonSolver {
+    pub fn new() -> Self {
+        NewtonSolver {
+            max_iterations: 50,
+            tolerance: 1e-12,
+            sufficient_decrease: 1e-4,
+            max_backtracks: 40,
+        }
+    }
+
+    /// Solves the square system `residual(x) = 0` from `x0`.
+    pub fn solve<R>(&self, residual: R, x0: &[f64]) -> NewtonResult
+    where
+        R: Fn(&[Number]) -> Vec<Number>,
+    {
+        let norm_at = |x: &[f64]| -> f64 {
+            crate::no_tape(|| {
+                let args: Vec<Number> = x.iter().map(|x| Number::new(*x)).collect();
+                norm(&residual(&args).iter().map(|r| r.result).collect::<Vec<_>>())
+            })
+        };
+
+        let mut automatic_differentiator = AutomaticDifferentiator::new();
+        let mut linearize = |x: &[f64]| -> (Vec<f64>, Vec<Vec<f64>>) {
+            let arguments: Vec<Number> = x.iter().map(|x| Number::new(*x)).collect();
+            automatic_differentiator
+                .jacobian(&residual, &arguments)
+                .into_iter()
+                .map(|evaluation| {
+                    let row = arguments
+                        .iter()
+                        .map(|argument| evaluation.derivative(*argument))
+                        .collect();
+                    (evaluation.result, row)
+                })
+                .unzip()
+        };
+
+        let mut x = x0.to_vec();
+        let (mut residuals, mut jacobian) = linearize(&x);
+        assert_eq!(
+            residuals.len(),
+            x.len(),
+            "newton_solve needs as many residuals as unknowns"
+        );
+        let mut residual_norm = norm(&residuals);
+        let mut residual_norm_history = vec![residual_norm];
+
+        let termination = loop {
+            if residual_norm <= self.tolerance {
+                break Termination::Converged;
+            }
+            if residual_norm_history.len() > self.max_iterations {
+                break Termination::MaxIterations;
+            }
+            let step =
+                linear_algebra::solve(jacobian.clone(), residuals.iter().map(|r| -r).collect());
+            if !step.iter().all(|dx| dx.is_finite()) {
+                break Termination::SingularJacobian;
+            }
+            let Some(next) = self.backtrack(residual_norm, |fraction| {
+                let trial: Vec<f64> = x
+                    .iter()
+                    .zip(&step)
+                    .map(|(x, dx)| x + fraction * dx)
+                    .collect();
+                (norm_at(&trial), trial)
+            }) else {
+                break Termination::LineSearchFailed;
+            };
+
+            x = next;
+            (residuals, jacobian) = linearize(&x);
+            residual_norm = norm(&residuals);
+            residual_norm_history.push(residual_norm);
+        };
+
+        NewtonResult {
+            x,
+            residuals,
+            residual_norm,
+            jacobian,
+            iterations: residual_norm_history.len() - 1,
+            termination,
+            residual_norm_history,
+        }
+    }
+
+    /// Solves `f(x) = 0` from `x0` by Newton's method.
+    pub fn newton<F>(&self, f: F, x0: f64) -> ScalarRoot
+    where
+        F: Fn(Number) -> Number,
+    {
+        self.scalar(f, x0, Curvature::None)
+    }
+
+    /// Solves `f(x) = 0` from `x0` by Halley's method. The tape gives f' but
+    /// not f'', which is taken as the central difference of f' swept at
+    /// `x +- h` with `h = 1e-5 max(|x|, 1)`: two more sweeps per iteration,
+    /// and an f'' good to about ten digits, so convergence is cubic only
+    /// until that error dominates. Use
+    /// [`halley_with_second_derivative`](Self::halley_with_second_derivative)
+    /// when f'' is known.
+    pub fn halley<F>(&self, f: F, x0: f64) -> ScalarRoot
+    where
+        F: Fn(Number) -> Number,
+    {
+        self.scalar(f, x0, Curvature::Differenced)
+    }
+
+    /// Solves `f(x) = 0` from `x0` by Halley's method with the f'' given by
+    /// `second_derivative`, which converges cubically near a simple root.
+    pub fn halley_with_second_derivative<F, S>(
+        &self,
+        f: F,
+        second_derivative: S,
+        x0: f64,
+    ) -> ScalarRoot
+    where
+        F: Fn(Number) -> Number,
+        S: Fn(f64) -> f64,
+    {
+        self.scalar(f, x0, Curvature::Given(&second_derivative))
+    }
+
+    fn scalar<F>(&self, f: F, x0: f64, curvature: Curvature) -> ScalarRoot
+    where
+        F: Fn(Number) -> Number,
+    {
+        let mut automatic_differentiator = AutomaticDifferentiator::new();
+        let mut evaluate = |x: f64| -> (f64, f64) {
+            let argument = Number::new(x);
+            let evaluation =
+                automatic_differentiator.derivatives(|args: &[Number]| f(args[0]), &[argument]);
+            (evaluation.result, evaluation.derivative(argument))
+        };
+        let value_at = |x: f64| crate::no_tape(|| f(Number::new(x)).result);
+
+        let mut x = x0;
+        let (mut value, mut derivative) = evaluate(x);
+        let mut residual_history = vec![value.abs()];
+
+        let termination = loop {
+            if value.abs() <= self.tolerance {
+                break Termination::Converged;
+            }
+            if residual_history.len() > self.max_iterations {
+                break Termination::MaxIterations;
+            }
+            if derivative == 0.0 || !derivative.is_finite() {
+                break Termination::SingularJacobian;
+            }
+            let mut step = -value / derivative;
+            let second = match curvature {
+                Curvature::None => None,
+                Curvature::Differenced => {
+                    let h = 1e-5 * x.abs().max(1.0);
+                    Some((evaluate(x + h).1 - evaluate(x - h).1) / (2.0 * h))
+                }
+                Curvature::Given(second_derivative) => Some(second_derivative(x)),
+            };
+            if let Some(second) = second {
+                let denominator = 2.0 * derivative * derivative - value * second;
+                // far from the root Halley's step may point the wrong way
+                if denominator > 0.0 {
+                    step = -2.0 * value * derivative / denominator;
+                }
+            }
+            let Some(next) = self.backtrack(value.abs(), |fraction| {
+                let trial = x + fraction * step;
+                (value_at(trial).abs(), trial)
+            }) else {
+                break Termination::LineSearchFailed;
+            };
+
+            x = next;
+            (value, derivative) = evaluate(x);
+            residual_history.push(value.abs());
+        };
+
+        ScalarRoot {
+            x,
+            value,
+            derivative,
+            iterations: residual_history.len() - 1,
+            termination,
+            residual_history,
+        }
+    }
+
+    /// Halves the fraction of the step from one until the residual norm
+    /// `trial(fraction)` reports falls to (1 - c fraction) times `norm`, and
+    /// returns the point it reports with it.
+    fn backtrack<T, P>(&self, norm: f64, mut trial: T) -> Option<P>
+    where
+        T: FnMut(f64) -> (f64, P),
+    {
+        let mut fraction = 1.0;
+        for _ in 0..=self.max_backtracks {
+            let (trial_norm, point) = trial(fraction);
+            if trial_norm <= (1.0 - self.sufficient_decrease * fraction) * norm {
+                return Some(point);
+            }
+            fraction *= 0.5;
+        }
+        None
+    }
+}
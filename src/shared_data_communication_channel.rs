@@ -23,8 +23,9 @@ pub fn global_add_parent_child_relationship(parent: i64, children: Vec<i64>) {
         if !child_map.contains_key(child) {
             child_map.insert(*child, vec![parent]);
         } else if let Some(parents) = child_map.get_mut(child) {
-            // avoid adding the same parent twice
-            if !parents.contains(&parent) {
+            // avoid adding the same parent twice. A parent registers all of its
+            // children in a single call, so a duplicate can only be the last entry
+            if parents.last() != Some(&parent) {
                 parents.push(parent);
             }
         }
@@ -38,31 +39,13 @@ pub fn global_add_parent_child_relationship(parent: i64, children: Vec<i64>) {
 
 pub fn global_register_operation(op: Operation) {
     let mut record = RECORD.lock().unwrap();
-    let id = match op {
-        Operation::Add(id, _, _, _, _)
-        | Operation::Sub(id, _, _, _, _)
-        | Operation::Mul(id, _, _, _, _)
-        | Operation::Div(id, _, _, _, _)
-        | Operation::Ln(id, _, _, _)
-        | Operation::Sin(id, _, _, _)
-        | Operation::Cos(id, _, _, _)
-        | Operation::Exp(id, _, _, _)
-        | Operation::Pow(id, _, _, _, _)
-        | Operation::Sqrt(id, _, _, _)
-        | Operation::Log(id, _, _, _, _)
-        | Operation::Cdf(id, _, _, _)
-        | Operation::Value(id, _, _) => id,
-    };
+    let id = op.get_id();
     record.insert(id, op);
     let mut node_list = NODE_LIST.lock().unwrap();
 
-    // Make sure each record apears exactly once.
-    // Remove and re-insert in order to guarantee valid ordering
-    if node_list.contains(&id) {
-        node_list.remove_item(&id);
-    }
-
-    node_list.push(id);
+    // Make sure each record apears exactly once. The list is sorted, so the
+    // position of an id is fixed and a binary search finds an existing entry
+    node_list.find_or_push(id);
 }
 
 pub fn global_record_clone() -> HashMap<i64, Operation> {
@@ -0,0 +1,155 @@
+use statrs::function::{beta, erf, gamma};
+use std::f64::consts::{FRAC_1_SQRT_2, PI, SQRT_2};
+
+/// 1 / sqrt(2 * PI)
+const FRAC_1_SQRT_2PI: f64 = 0.398_942_280_401_432_7;
+
+/// Density of the standard normal distribution.
+pub fn norm_pdf(x: f64) -> f64 {
+    FRAC_1_SQRT_2PI * (-0.5 * x * x).exp()
+}
+
+/// Distribution function of the standard normal distribution.
+pub fn norm_cdf(x: f64) -> f64 {
+    0.5 * erf::erfc(-x * FRAC_1_SQRT_2)
+}
+
+/// Inverse of [`norm_cdf`] (the probit function).
+pub fn norm_inv_cdf(p: f64) -> f64 {
+    -SQRT_2 * erf::erfc_inv(2.0 * p)
+}
+
+/// Trigamma function, the derivative of [`gamma::digamma`].
+///
+/// Uses the reflection formula for negative arguments, the recurrence
+/// `trigamma(x) = trigamma(x + 1) + 1/x^2` to shift the argument above 10 and
+/// the asymptotic expansion from there.
+pub fn trigamma(x: f64) -> f64 {
+    if x.is_nan() || x == f64::NEG_INFINITY {
+        return f64::NAN;
+    }
+    if x <= 0.0 && x.floor() == x {
+        return f64::INFINITY;
+    }
+    if x < 0.0 {
+        let s = (PI * x).sin();
+        return PI * PI / (s * s) - trigamma(1.0 - x);
+    }
+
+    let mut result = 0.0;
+    let mut z = x;
+    while z < 10.0 {
+        result += 1.0 / (z * z);
+        z += 1.0;
+    }
+    let r = 1.0 / (z * z);
+    result
+        + 1.0 / z
+        + r / 2.0
+        + r / z
+            * (1.0 / 6.0 - r * (1.0 / 30.0 - r * (1.0 / 42.0 - r * (1.0 / 30.0 - r * 5.0 / 66.0))))
+}
+
+/// Beta function `B(a, b)` for `a, b > 0`, NaN outside that domain.
+pub fn beta(a: f64, b: f64) -> f64 {
+    beta::checked_ln_beta(a, b).map_or(f64::NAN, f64::exp)
+}
+
+/// Regularized lower incomplete gamma function `P(a, x)`, extended by `0` for `x <= 0`.
+pub fn gamma_p(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x == f64::INFINITY {
+        return 1.0;
+    }
+    gamma::checked_gamma_lr(a, x).unwrap_or(f64::NAN)
+}
+
+/// Regularized upper incomplete gamma function `Q(a, x)`, extended by `1` for
+/// `x <= 0`. Computed directly rather than as `1 - P(a, x)`, which cancels to
+/// zero in the upper tail.
+pub fn gamma_q(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    if x == f64::INFINITY {
+        return 0.0;
+    }
+    gamma::checked_gamma_ur(a, x).unwrap_or(f64::NAN)
+}
+
+/// Central difference of `f` at `a`, for the partials of the special functions
+/// with respect to their shape parameters, which have no closed form.
+pub(crate) fn parameter_derivative<F: Fn(f64) -> f64>(f: F, a: f64) -> f64 {
+    let h = 1e-5 * a.abs().max(1.0);
+    (f(a + h) - f(a - h)) / (2.0 * h)
+}
+
+/// Derivative of [`gamma_p`] with respect to `x`: `x^(a-1) e^(-x) / Gamma(a)`.
+pub fn gamma_p_density(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    ((a - 1.0) * x.ln() - x - gamma::ln_gamma(a)).exp()
+}
+
+/// Regularized incomplete beta function `I_x(a, b)`, extended by `0` below
+/// `x = 0` and by `1` above `x = 1`.
+pub fn beta_reg(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    beta::checked_beta_reg(a, b, x).unwrap_or(f64::NAN)
+}
+
+/// Derivative of [`beta_reg`] with respect to `x`: `x^(a-1) (1-x)^(b-1) / B(a, b)`.
+pub fn beta_reg_density(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 || x >= 1.0 {
+        return 0.0;
+    }
+    ((a - 1.0) * x.ln() + (b - 1.0) * (-x).ln_1p() - beta::ln_beta(a, b)).exp()
+}
+
+/// Distribution function of Student's t distribution with `nu` degrees of freedom.
+pub fn students_t_cdf(t: f64, nu: f64) -> f64 {
+    let tail = 0.5 * beta_reg(0.5 * nu, 0.5, nu / (nu + t * t));
+    if t > 0.0 { 1.0 - tail } else { tail }
+}
+
+/// Density of Student's t distribution with `nu` degrees of freedom.
+pub fn students_t_pdf(t: f64, nu: f64) -> f64 {
+    (gamma::ln_gamma(0.5 * (nu + 1.0))
+        - gamma::ln_gamma(0.5 * nu)
+        - 0.5 * (nu * PI).ln()
+        - 0.5 * (nu + 1.0) * (t * t / nu).ln_1p())
+    .exp()
+}
+
+/// Distribution function of the chi-squared distribution with `k` degrees of freedom.
+pub fn chi_squared_cdf(x: f64, k: f64) -> f64 {
+    gamma_p(0.5 * k, 0.5 * x)
+}
+
+/// Density of the chi-squared distribution with `k` degrees of freedom.
+pub fn chi_squared_pdf(x: f64, k: f64) -> f64 {
+    0.5 * gamma_p_density(0.5 * k, 0.5 * x)
+}
+
+/// Logistic sigmoid `1 / (1 + e^(-x))`, evaluated without overflow for large `|x|`.
+pub fn sigmoid(x: f64) -> f64 {
+    if x >= 0.0 {
+        1.0 / (1.0 + (-x).exp())
+    } else {
+        let e = x.exp();
+        e / (1.0 + e)
+    }
+}
+
+/// Softplus `ln(1 + e^x)`, evaluated without overflow for large `x`.
+pub fn softplus(x: f64) -> f64 {
+    x.max(0.0) + (-x.abs()).exp().ln_1p()
+}
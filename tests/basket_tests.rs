@@ -0,0 +1,264 @@
+use aad::automatic_differentiator::AutomaticDifferentiator;
+use aad::finance::basket::{BasketMarket, BasketMonteCarlo, BasketOption, BasketPayoff};
+use aad::finance::black_scholes::{EuropeanOption, OptionType};
+use aad::linear_algebra;
+use aad::number::Number;
+
+mod common;
+
+/// Lower triangle of a positive definite matrix, row by row.
+const MATRIX: [f64; 10] = [4.0, 1.2, 3.0, -0.6, 0.5, 2.5, 0.3, -0.4, 0.8, 2.0];
+
+fn square(lower: &[Number]) -> Vec<Vec<Number>> {
+    let n = ((8 * lower.len() + 1) as f64).sqrt() as usize / 2;
+    let entry = |i: usize, j: usize| lower[i * (i + 1) / 2 + j];
+    (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| if j <= i { entry(i, j) } else { entry(j, i) })
+                .collect()
+        })
+        .collect()
+}
+
+/// A weighted sum of the entries of the Cholesky factor.
+fn weighted_factor(args: &[Number]) -> Number {
+    let factor = linear_algebra::cholesky(&square(args));
+    let mut sum = Number::from(0.0);
+    for (i, row) in factor.iter().enumerate() {
+        for (j, entry) in row.iter().enumerate() {
+            sum = sum + (1.0 + i as f64 - 0.5 * j as f64) * *entry;
+        }
+    }
+    // something nonlinear on top of the factorization
+    sum * factor[3][1]
+}
+
+fn market() -> BasketMarket {
+    BasketMarket {
+        spots: vec![100.0, 90.0, 110.0],
+        volatilities: vec![0.2, 0.3, 0.25],
+        dividend_yields: vec![0.01, 0.0, 0.02],
+        rate: 0.03,
+        correlation: vec![
+            vec![1.0, 0.5, 0.3],
+            vec![0.5, 1.0, 0.4],
+            vec![0.3, 0.4, 1.0],
+        ],
+    }
+}
+
+fn flatten_sensitivities(result: &aad::finance::basket::BasketResult) -> Vec<f64> {
+    let sensitivities = &result.sensitivities;
+    let mut flat = [
+        sensitivities.spots.as_slice(),
+        &sensitivities.volatilities,
+        &sensitivities.dividend_yields,
+    ]
+    .concat();
+    flat.push(sensitivities.rate);
+    for (i, row) in sensitivities.correlation.iter().enumerate() {
+        flat.extend_from_slice(&row[..i]);
+    }
+    flat
+}
+
+fn assert_against_bump_and_revalue(option: &BasketOption) {
+    let market = market();
+    let engine = BasketMonteCarlo::new(500, 13);
+    let result = engine.run(&market, option);
+    assert!((result.price - engine.value(&market, option)).abs() < 1e-12);
+
+    common::assert_against_bump_and_revalue(
+        &flatten_sensitivities(&result),
+        &market.inputs(),
+        1e-7,
+        |inputs| engine.value(&market.with_inputs(inputs), option),
+    );
+}
+
+#[test]
+fn test_cholesky_factorizes() {
+    aad::no_tape(|| {
+        let matrix = square(&MATRIX.map(Number::new));
+        let factor = linear_algebra::cholesky(&matrix);
+        for i in 0..4 {
+            assert!(factor[i][i].result > 0.0);
+            for j in 0..4 {
+                if j > i {
+                    assert_eq!(factor[i][j].result, 0.0);
+                }
+                let product: f64 = (0..4)
+                    .map(|k| factor[i][k].result * factor[j][k].result)
+                    .sum();
+                assert!((product - matrix[i][j].result).abs() < 1e-14);
+            }
+        }
+    });
+}
+
+#[test]
+fn test_cholesky_adjoints_against_finite_differences() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+    let arguments: Vec<Number> = MATRIX.iter().map(|x| Number::new(*x)).collect();
+    let evaluation = automatic_differentiator.derivatives(weighted_factor, &arguments);
+
+    // each entry below the diagonal moves with its mirror image
+    let untaped = |point: &[f64]| {
+        aad::no_tape(|| {
+            let args: Vec<Number> = point.iter().map(|x| Number::new(*x)).collect();
+            weighted_factor(&args).result
+        })
+    };
+    assert!((evaluation.result - untaped(&MATRIX)).abs() < 1e-15);
+
+    let bump = 1e-6;
+    for (i, argument) in arguments.iter().enumerate() {
+        let mut up = MATRIX;
+        up[i] += bump;
+        let mut down = MATRIX;
+        down[i] -= bump;
+        let difference = (untaped(&up) - untaped(&down)) / (2.0 * bump);
+        assert!(
+            (evaluation.derivative(*argument) - difference).abs() < 1e-8,
+            "entry {}: adjoint {} finite difference {}",
+            i,
+            evaluation.derivative(*argument),
+            difference
+        );
+    }
+}
+
+#[test]
+fn test_cholesky_replay() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+    let arguments: Vec<Number> = MATRIX.iter().map(|x| Number::new(*x)).collect();
+    automatic_differentiator.derivatives(weighted_factor, &arguments);
+
+    let mut shifted = MATRIX;
+    shifted[0] += 0.5;
+    shifted[7] -= 0.2;
+    let replay = automatic_differentiator.replay(&shifted);
+
+    let mut fresh = AutomaticDifferentiator::new();
+    let arguments: Vec<Number> = shifted.iter().map(|x| Number::new(*x)).collect();
+    let evaluation = fresh.derivatives(weighted_factor, &arguments);
+
+    assert!((replay.evaluation.result - evaluation.result).abs() < 1e-14);
+    for (replayed, recorded) in replay
+        .evaluation
+        .derivatives
+        .iter()
+        .zip(&evaluation.derivatives)
+    {
+        assert!((replayed.derivative - recorded.derivative).abs() < 1e-12);
+    }
+}
+
+#[test]
+#[should_panic(expected = "not positive definite")]
+fn test_cholesky_of_an_indefinite_matrix() {
+    aad::no_tape(|| {
+        let matrix = square(&[1.0, 2.0, 1.0].map(Number::new));
+        linear_algebra::cholesky(&matrix);
+    });
+}
+
+#[test]
+fn test_single_asset_basket_is_black_scholes() {
+    let market = market();
+    let option = BasketOption {
+        option_type: OptionType::Call,
+        strike: 95.0,
+        maturity: 1.0,
+        payoff: BasketPayoff::Basket(vec![0.0, 1.0, 0.0]),
+    };
+    let result = BasketMonteCarlo::new(2000, 7).run(&market, &option);
+
+    let greeks = EuropeanOption {
+        option_type: OptionType::Call,
+        spot: 90.0,
+        strike: 95.0,
+        maturity: 1.0,
+        rate: 0.03,
+        dividend_yield: 0.0,
+        volatility: 0.3,
+    }
+    .greeks();
+    assert!(
+        (result.price - greeks.price).abs() < 3.0 * result.standard_error,
+        "basket {} black scholes {}",
+        result.price,
+        greeks.price
+    );
+    let sensitivities = &result.sensitivities;
+    assert!((sensitivities.spots[1] - greeks.delta).abs() < 0.03);
+    assert!((sensitivities.volatilities[1] - greeks.vega).abs() < 0.1 * greeks.vega);
+    // nothing else matters
+    assert_eq!(sensitivities.spots[0], 0.0);
+    assert_eq!(sensitivities.volatilities[2], 0.0);
+    // the correlations of the third asset do not enter the path of the second,
+    // its correlation with the first only does path by path
+    assert!(sensitivities.correlation[2][0].abs() < 1e-12);
+    assert!(sensitivities.correlation[2][1].abs() < 1e-12);
+    assert!(sensitivities.correlation[1][0].abs() < 0.1 * greeks.vega);
+}
+
+#[test]
+fn test_basket_against_bump_and_revalue() {
+    assert_against_bump_and_revalue(&BasketOption {
+        option_type: OptionType::Call,
+        strike: 100.0,
+        maturity: 1.0,
+        payoff: BasketPayoff::Basket(vec![0.4, 0.3, 0.3]),
+    });
+}
+
+#[test]
+fn test_worst_of_against_bump_and_revalue() {
+    assert_against_bump_and_revalue(&BasketOption {
+        option_type: OptionType::Put,
+        strike: 0.9,
+        maturity: 2.0,
+        payoff: BasketPayoff::WorstOf,
+    });
+}
+
+#[test]
+fn test_correlation_sensitivity_signs() {
+    // a basket call loses value with diversification, so gains with the
+    // correlation; a worst-of call is worth more when the assets move together
+    let market = market();
+    let engine = BasketMonteCarlo::new(1000, 3);
+    let basket = engine.run(
+        &market,
+        &BasketOption {
+            option_type: OptionType::Call,
+            strike: 100.0,
+            maturity: 1.0,
+            payoff: BasketPayoff::Basket(vec![1.0 / 3.0; 3]),
+        },
+    );
+    let worst_of = engine.run(
+        &market,
+        &BasketOption {
+            option_type: OptionType::Call,
+            strike: 1.0,
+            maturity: 1.0,
+            payoff: BasketPayoff::WorstOf,
+        },
+    );
+    for i in 0..3 {
+        for j in 0..3 {
+            if i != j {
+                assert!(basket.sensitivities.correlation[i][j] > 0.0);
+                assert!(worst_of.sensitivities.correlation[i][j] > 0.0);
+                assert_eq!(
+                    basket.sensitivities.correlation[i][j],
+                    basket.sensitivities.correlation[j][i]
+                );
+            }
+        }
+        assert_eq!(basket.sensitivities.correlation[i][i], 0.0);
+    }
+}
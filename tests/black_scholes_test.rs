@@ -1,135 +1,256 @@
 use aad::automatic_differentiator::AutomaticDifferentiator;
+use aad::finance::black_scholes::{self, EuropeanOption, Greeks, OptionType};
 use aad::number::Number;
-use statrs::distribution::{Normal};
+use statrs::distribution::{Continuous, ContinuousCDF, Normal};
 
-fn f_call(args: &[Number]) -> Number {
-    let s = args[0]; // Current stock price
-    let k = args[1]; // Strike price
-    let t = args[2]; // Time to maturity in years
-    let r = args[3]; // Risk-free interest rate
-    let sigma = args[4]; // Volatility
+/// Closed-form Black-Scholes-Merton greeks, the reference for the adjoint ones.
+fn closed_form_greeks(option: &EuropeanOption) -> Greeks {
+    let norm = Normal::standard();
+    let (s, k, t, r, q, sigma) = (
+        option.spot,
+        option.strike,
+        option.maturity,
+        option.rate,
+        option.dividend_yield,
+        option.volatility,
+    );
 
-    let d1 = ((s / k).ln() + (r + 0.5 * sigma * sigma) * t) / (sigma * t.sqrt());
+    let d1 = ((s / k).ln() + (r - q + 0.5 * sigma * sigma) * t) / (sigma * t.sqrt());
     let d2 = d1 - sigma * t.sqrt();
+    let (dividend_discount, discount) = ((-q * t).exp(), (-r * t).exp());
+    let density = norm.pdf(d1);
+
+    let gamma = dividend_discount * density / (s * sigma * t.sqrt());
+    let vega = s * dividend_discount * density * t.sqrt();
+    let vanna = -dividend_discount * density * d2 / sigma;
+    let volga = vega * d1 * d2 / sigma;
+    let time_decay = -s * dividend_discount * density * sigma / (2.0 * t.sqrt());
 
-    let norm = Normal::new(0.0, 1.0).unwrap();
+    match option.option_type {
+        OptionType::Call => Greeks {
+            price: s * dividend_discount * norm.cdf(d1) - k * discount * norm.cdf(d2),
+            delta: dividend_discount * norm.cdf(d1),
+            gamma,
+            vega,
+            theta: time_decay - r * k * discount * norm.cdf(d2)
+                + q * s * dividend_discount * norm.cdf(d1),
+            rho: k * t * discount * norm.cdf(d2),
+            vanna,
+            volga,
+        },
+        OptionType::Put => Greeks {
+            price: k * discount * norm.cdf(-d2) - s * dividend_discount * norm.cdf(-d1),
+            delta: -dividend_discount * norm.cdf(-d1),
+            gamma,
+            vega,
+            theta: time_decay + r * k * discount * norm.cdf(-d2)
+                - q * s * dividend_discount * norm.cdf(-d1),
+            rho: -k * t * discount * norm.cdf(-d2),
+            vanna,
+            volga,
+        },
+    }
+}
 
-    s * d1.cdf() - k * (-1.0 * r * t).exp() * d2.cdf()
+fn assert_close(name: &str, value: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (value - expected).abs() < tolerance,
+        "{}: {} != {}",
+        name,
+        value,
+        expected
+    );
+}
 
-    // Call
-    //s * Number::new(norm.cdf(d1.result))
-    //    - k * (-1.0 * r * t).exp() * Number::new(norm.cdf(d2.result))
+/// Compares all greeks with a tolerance relative to the size of the expected value.
+fn assert_greeks(greeks: &Greeks, expected: &Greeks, tolerance: f64) {
+    let pairs = [
+        ("price", greeks.price, expected.price),
+        ("delta", greeks.delta, expected.delta),
+        ("gamma", greeks.gamma, expected.gamma),
+        ("vega", greeks.vega, expected.vega),
+        ("theta", greeks.theta, expected.theta),
+        ("rho", greeks.rho, expected.rho),
+        ("vanna", greeks.vanna, expected.vanna),
+        ("volga", greeks.volga, expected.volga),
+    ];
+    for (name, value, expected) in pairs {
+        assert_close(name, value, expected, tolerance * expected.abs().max(1.0));
+    }
 }
 
 #[test]
 fn black_scholes_test() {
-    let s = 100.0; // underlying stock price
-    let k = 100.0; // strike
-    let t = 1.0; // time to maturity
-    let r = 0.05; // Risk free interest rate
-    let sigma = 0.2; // Volatility
-
-    let mut ad = AutomaticDifferentiator::new();
-
-    let s = Number::new(s);
-    let k = Number::new(k);
-    let t = Number::new(t);
-    let r = Number::new(r);
-    let sigma = Number::new(sigma);
-
-    let arguments = vec![s, k, t, r, sigma];
-
-    let numerical_evaluation = ad.derivatives(f_call, &arguments);
-    let call_price = numerical_evaluation.result;
-    let call_delta = numerical_evaluation // dOptionPrice/dStockPrice
-        .derivatives
-        .iter()
-        .filter(|d| d.input.id == s.id)
-        .map(|x| x.derivative)
-        .next()
-        .unwrap();
+    let option = EuropeanOption {
+        option_type: OptionType::Call,
+        spot: 100.0,
+        strike: 100.0,
+        maturity: 1.0,
+        rate: 0.05,
+        dividend_yield: 0.0,
+        volatility: 0.2,
+    };
 
-    let call_vega = numerical_evaluation // dOptionPrice/dSigma (and sigma is volatility)
-        .derivatives
-        .iter()
-        .filter(|d| d.input.id == sigma.id)
-        .map(|x| x.derivative)
-        .next()
-        .unwrap();
+    let greeks = option.greeks();
 
-    let call_theta = numerical_evaluation // dOptionPrice/dt
-        .derivatives
-        .iter()
-        .filter(|d| d.input.id == t.id)
-        .map(|x| x.derivative)
-        .next()
-        .unwrap();
+    let epsilon = 1e-5;
+    assert!((greeks.price - 10.45058).abs() < epsilon);
+    assert!((greeks.delta - 0.63683).abs() < epsilon);
+    assert!((greeks.rho - 53.23248).abs() < epsilon);
+    assert!((greeks.gamma - 0.01876).abs() < epsilon);
+    assert!((greeks.vega - 37.52403).abs() < epsilon);
+    assert!((greeks.theta + 6.41403).abs() < epsilon);
+}
 
-    let call_rho = numerical_evaluation // dOptionPrice/dr
-        .derivatives
-        .iter()
-        .filter(|d| d.input.id == r.id)
-        .map(|x| x.derivative)
-        .next()
-        .unwrap();
+#[test]
+fn test_textbook_call() {
+    // Hull, Options, Futures, and Other Derivatives: S = 49, K = 50, r = 5%,
+    // sigma = 20%, 20 weeks to maturity
+    let option = EuropeanOption {
+        option_type: OptionType::Call,
+        spot: 49.0,
+        strike: 50.0,
+        maturity: 20.0 / 52.0,
+        rate: 0.05,
+        dividend_yield: 0.0,
+        volatility: 0.2,
+    };
+
+    let greeks = option.greeks();
 
-    let call_strike = numerical_evaluation // dOptionPrice/dStockPrice^2 (aka. stock price acceleration)
-        .derivatives
+    assert_close("price", greeks.price, 2.40, 5e-3);
+    assert_close("delta", greeks.delta, 0.522, 5e-4);
+    assert_close("gamma", greeks.gamma, 0.066, 5e-4);
+    assert_close("vega", greeks.vega, 12.1, 5e-2);
+    assert_close("theta", greeks.theta, -4.31, 5e-3);
+    assert_close("rho", greeks.rho, 8.91, 5e-3);
+}
+
+#[test]
+fn test_greeks_against_closed_form() {
+    for option_type in [OptionType::Call, OptionType::Put] {
+        for (spot, maturity, dividend_yield, volatility) in [
+            (100.0, 1.0, 0.0, 0.2),
+            (90.0, 0.25, 0.03, 0.35),
+            (120.0, 2.5, 0.01, 0.15),
+            (100.0, 0.05, 0.06, 0.6),
+        ] {
+            let option = EuropeanOption {
+                option_type,
+                spot,
+                strike: 100.0,
+                maturity,
+                rate: 0.04,
+                dividend_yield,
+                volatility,
+            };
+            assert_greeks(&option.greeks(), &closed_form_greeks(&option), 1e-6);
+            assert_close(
+                "price",
+                option.price(),
+                closed_form_greeks(&option).price,
+                1e-10,
+            );
+        }
+    }
+}
+
+#[test]
+fn test_gamma_against_closed_form_within_the_bump_error() {
+    // gamma is a central difference of deltas over a relative spot bump of
+    // 1e-4, off by about (1e-4 / (sigma sqrt(T)))^2 / 6 of its value
+    for (spot, maturity, volatility) in [
+        (100.0, 1.0, 0.2),
+        (90.0, 0.25, 0.35),
+        (120.0, 2.5, 0.15),
+        (100.0, 0.01, 0.1),
+    ] {
+        let option = EuropeanOption {
+            option_type: OptionType::Call,
+            spot,
+            strike: 100.0,
+            maturity,
+            rate: 0.04,
+            dividend_yield: 0.02,
+            volatility,
+        };
+        let expected = closed_form_greeks(&option).gamma;
+        let bump_error = (1e-4 / (volatility * f64::sqrt(maturity))).powi(2);
+        assert_close(
+            "gamma",
+            option.greeks().gamma,
+            expected,
+            bump_error * expected,
+        );
+    }
+}
+
+#[test]
+fn test_put_call_parity() {
+    // C - P = S e^(-qT) - K e^(-rT), so d(C - P)/dS = e^(-qT) and d(C - P)/dK = -e^(-rT)
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    fn parity(args: &[Number]) -> Number {
+        let (s, k, t, r, q, sigma) = (args[0], args[1], args[2], args[3], args[4], args[5]);
+        black_scholes::call_price(s, k, t, r, q, sigma)
+            - black_scholes::put_price(s, k, t, r, q, sigma)
+    }
+
+    let arguments: Vec<Number> = [105.0, 100.0, 0.75, 0.03, 0.02, 0.25]
         .iter()
-        .filter(|d| d.input.id == k.id) // This is the strike, not twice the price derivative
-        .map(|x| x.derivative)
-        .next()
-        .unwrap();
-
-    println!(
-        "s (price) has id {} and derivative (delta) {}",
-        s.id, call_delta
+        .map(|x| Number::new(*x))
+        .collect();
+    let evaluation = automatic_differentiator.derivatives(parity, &arguments);
+
+    let (dividend_discount, discount) = ((-0.02_f64 * 0.75).exp(), (-0.03_f64 * 0.75).exp());
+    let epsilon = 1e-12;
+    assert_close(
+        "parity",
+        evaluation.result,
+        105.0 * dividend_discount - 100.0 * discount,
+        epsilon,
     );
-    println!(
-        "t (time to maturity) has id {} and derivative (theta) {}",
-        t.id, call_theta
+    assert_close(
+        "d/dS",
+        evaluation.derivative(arguments[0]),
+        dividend_discount,
+        epsilon,
     );
-    println!(
-        "r (interest rate) has id {} and derivative (rho) {}",
-        r.id, call_rho
+    assert_close(
+        "d/dK",
+        evaluation.derivative(arguments[1]),
+        -discount,
+        epsilon,
     );
-    println!(
-        "sigma (volatility) has id {} and derivative (vega) {}",
-        sigma.id, call_vega
+    assert_close(
+        "d/dsigma",
+        evaluation.derivative(arguments[5]),
+        0.0,
+        epsilon,
     );
+}
 
-    let epsilon = 1e-5;
-    assert!((call_price - 10.45058).abs() < epsilon);
-    assert!((call_delta - 0.63683).abs() < epsilon);
-    assert!((call_rho - 53.23248).abs() < epsilon);
-    // Gamma is the second derivative, and we only support first
-    // derivative for now. So we will skip it until further notice
-    // assert!((call_gamma - 0.01876).abs() < epsilon);
-    assert!((call_vega - 37.52403).abs() < epsilon);
-    assert!((call_theta - 6.41403).abs() < epsilon);
-
-    // TODO:
-    // Run a second derivative, this time with delta in place of price to obtain the second derivative of the price
-    let s = Number::new(call_delta);
-    let sigma = Number::new(call_vega);
-    let k = Number::new(call_strike);
-    let t = Number::new(call_theta);
-    let r = Number::new(call_rho);
-
-    let arguments = vec![s, k, t, r, sigma];
-
-    let numerical_evaluation = ad.derivatives(f_call, &arguments);
-
-    let call_delta_second = numerical_evaluation // dOptionPrice/dStockPrice
-        .derivatives
-        .iter()
-        .filter(|d| d.input.id == s.id)
-        .map(|x| x.derivative)
-        .next()
-        .unwrap();
-
-    println!(
-        "s (price) has id {} and has second derivative (gamma) {}",
-        s.id, call_delta_second
-    );
+#[test]
+fn test_dividend_sensitivity() {
+    // dC/dq = -T S e^(-qT) N(d1)
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    fn call(args: &[Number]) -> Number {
+        black_scholes::price(
+            OptionType::Call,
+            Number::from(100.0),
+            Number::from(95.0),
+            Number::from(0.5),
+            Number::from(0.03),
+            args[0],
+            Number::from(0.3),
+        )
+    }
+
+    let q = Number::new(0.04);
+    let evaluation = automatic_differentiator.derivatives(call, &[q]);
+
+    let d1 = ((100.0_f64 / 95.0).ln() + (0.03 - 0.04 + 0.5 * 0.09) * 0.5) / (0.3 * 0.5_f64.sqrt());
+    let expected = -0.5 * 100.0 * (-0.04_f64 * 0.5).exp() * Normal::standard().cdf(d1);
+    assert_close("dC/dq", evaluation.derivative(q), expected, 1e-10);
 }
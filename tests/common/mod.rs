@@ -0,0 +1,50 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use aad::number::Number;
+
+/// Central finite difference of `func` in argument `i`, evaluated without the tape.
+pub fn finite_difference<F>(func: &F, point: &[f64], i: usize) -> f64
+where
+    F: Fn(&[Number]) -> Number,
+{
+    let h = 1e-6 * point[i].abs().max(1.0);
+    let evaluate = |shift: f64| {
+        aad::no_tape(|| {
+            let args: Vec<Number> = point
+                .iter()
+                .enumerate()
+                .map(|(j, x)| Number::new(if i == j { x + shift } else { *x }))
+                .collect();
+            func(&args).result
+        })
+    };
+    (evaluate(h) - evaluate(-h)) / (2.0 * h)
+}
+
+/// Asserts that each of `sensitivities` matches the central difference of
+/// `value` around `inputs` with a step of `bump`, to a relative 1e-5.
+pub fn assert_against_bump_and_revalue<V>(
+    sensitivities: &[f64],
+    inputs: &[f64],
+    bump: f64,
+    value: V,
+) where
+    V: Fn(&[f64]) -> f64,
+{
+    assert_eq!(sensitivities.len(), inputs.len());
+    for (i, sensitivity) in sensitivities.iter().enumerate() {
+        let mut up = inputs.to_vec();
+        up[i] += bump;
+        let mut down = inputs.to_vec();
+        down[i] -= bump;
+        let difference = (value(&up) - value(&down)) / (2.0 * bump);
+        assert!(
+            (sensitivity - difference).abs() <= 1e-5 * difference.abs().max(1.0),
+            "input {}: adjoint {} bump and revalue {}",
+            i,
+            sensitivity,
+            difference
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use aad::number::Number;
+use aad::optim::constrained::{AugmentedLagrangian, ConstrainedProblem, Termination};
+
+fn distance_to_two_one(x: &[Number]) -> Number {
+    (x[0] - 2.0) * (x[0] - 2.0) + (x[1] - 1.0) * (x[1] - 1.0)
+}
+
+#[test]
+fn test_hock_schittkowski_71() {
+    let mut problem =
+        ConstrainedProblem::new(|x: &[Number]| x[0] * x[3] * (x[0] + x[1] + x[2]) + x[2]);
+    problem
+        .inequality(|x: &[Number]| x[0] * x[1] * x[2] * x[3] - 25.0)
+        .equality(|x: &[Number]| x[0] * x[0] + x[1] * x[1] + x[2] * x[2] + x[3] * x[3] - 40.0)
+        .bounds(vec![1.0; 4], vec![5.0; 4]);
+
+    let result = AugmentedLagrangian::new().minimize(&problem, &[1.0, 5.0, 5.0, 1.0]);
+    assert!(result.converged(), "{:?}", result.history);
+    assert!((result.value - 17.0140173).abs() < 1e-6, "{}", result.value);
+    for (x, expected) in result.x.iter().zip([1.0, 4.7429994, 3.8211503, 1.3794082]) {
+        assert!((x - expected).abs() < 1e-5, "{:?}", result.x);
+    }
+    // both constraints are active, with the multipliers of the reference
+    // solution for f - lambda c - mu g
+    assert!(result.constraint_violation <= 1e-8);
+    assert!(result.optimality <= 1e-8);
+    assert!((result.equality_multipliers[0] + 0.16146856).abs() < 1e-5);
+    assert!((result.inequality_multipliers[0] - 0.55229366).abs() < 1e-5);
+
+    // rows of the Jacobian: 2x for the equality, the products of the other
+    // three for the inequality
+    let x = &result.x;
+    let jacobian = &result.constraint_jacobian;
+    assert_eq!(jacobian.len(), 2);
+    for i in 0..4 {
+        assert!((jacobian[0][i] - 2.0 * x[i]).abs() < 1e-12);
+        let others: f64 = (0..4).filter(|j| *j != i).map(|j| x[j]).product();
+        assert!((jacobian[1][i] - others).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_multipliers_of_a_projection() {
+    // the closest point to (2, 1) on the line x + y = 1 is (1, 0), where the
+    // gradient (-2, -2) of the objective balances the constraint
+    let mut with_equality = ConstrainedProblem::new(distance_to_two_one);
+    with_equality.equality(|x: &[Number]| x[0] + x[1] - 1.0);
+    let equality = AugmentedLagrangian::new().minimize(&with_equality, &[0.0, 0.0]);
+
+    let mut with_inequality = ConstrainedProblem::new(distance_to_two_one);
+    with_inequality.inequality(|x: &[Number]| 1.0 - x[0] - x[1]);
+    let inequality = AugmentedLagrangian::new().minimize(&with_inequality, &[0.0, 0.0]);
+
+    for result in [&equality, &inequality] {
+        assert_eq!(result.termination, Termination::Converged);
+        assert!((result.x[0] - 1.0).abs() < 1e-8 && result.x[1].abs() < 1e-8);
+        assert!((result.value - 2.0).abs() < 1e-7);
+        assert_eq!(result.iterations, result.history.len());
+    }
+    assert!((equality.equality_multipliers[0] + 2.0).abs() < 1e-7);
+    assert!((inequality.inequality_multipliers[0] - 2.0).abs() < 1e-7);
+}
+
+#[test]
+fn test_inactive_inequality_and_bounds() {
+    // (2, 1) satisfies the inequality, the bounds cut it off
+    let mut problem = ConstrainedProblem::new(distance_to_two_one);
+    problem
+        .inequality(|x: &[Number]| 10.0 - x[0] - x[1])
+        .bounds(vec![f64::NEG_INFINITY, 1.2], vec![1.5, f64::INFINITY]);
+    let result = AugmentedLagrangian::new().minimize(&problem, &[5.0, -3.0]);
+
+    assert!(result.converged());
+    assert_eq!(result.x, vec![1.5, 1.2]);
+    assert_eq!(result.inequality_multipliers[0], 0.0);
+    // the bounds hold at every iterate, so the subproblems see no penalty
+    assert!(result.history.iter().all(|h| h.penalty == 10.0));
+}
+
+#[test]
+fn test_feller_condition() {
+    // the nearest admissible kappa, theta and xi to a target that violates
+    // 2 kappa theta >= xi^2, with positive parameters
+    let target = [0.5, 0.04, 0.5];
+    let mut problem = ConstrainedProblem::new(move |p: &[Number]| {
+        p.iter()
+            .zip(target)
+            .fold(Number::from(0.0), |sum, (p, t)| sum + (*p - t) * (*p - t))
+    });
+    problem
+        .inequality(|p: &[Number]| 2.0 * p[0] * p[1] - p[2] * p[2])
+        .bounds(vec![1e-4; 3], vec![f64::INFINITY; 3]);
+    let result = AugmentedLagrangian::new().minimize(&problem, &target);
+
+    assert!(result.converged(), "{:?}", result.history);
+    let [kappa, theta, xi] = [result.x[0], result.x[1], result.x[2]];
+    assert!(2.0 * kappa * theta - xi * xi > -1e-8);
+    assert!(result.inequality_multipliers[0] > 0.0);
+    // stationarity: the gradient of the objective is mu times that of the
+    // constraint
+    let mu = result.inequality_multipliers[0];
+    for (i, t) in target.iter().enumerate() {
+        let gradient = 2.0 * (result.x[i] - t);
+        assert!((gradient - mu * result.constraint_jacobian[0][i]).abs() < 1e-7);
+    }
+}
+
+#[test]
+fn test_max_iterations() {
+    let mut problem = ConstrainedProblem::new(distance_to_two_one);
+    problem.equality(|x: &[Number]| x[0] * x[0] + x[1] * x[1] - 1.0);
+    let solver = AugmentedLagrangian {
+        max_iterations: 1,
+        ..AugmentedLagrangian::new()
+    };
+    let result = solver.minimize(&problem, &[0.0, 0.0]);
+    assert_eq!(result.termination, Termination::MaxIterations);
+    assert_eq!(result.iterations, 1);
+    assert!(result.constraint_violation > 1e-8);
+}
+
+#[test]
+fn test_failed_line_search_is_reported() {
+    // at its minimum 0 the adjoint of |x| + x / 2 is 1 / 2, and no step
+    // against it lowers the objective
+    let problem = ConstrainedProblem::new(|x: &[Number]| x[0].abs() + 0.5 * x[0]);
+    let result = AugmentedLagrangian::new().minimize(&problem, &[0.0]);
+    assert!(!result.converged());
+    assert!(
+        result.history.iter().all(|h| h.line_search_failed),
+        "{:?}",
+        result.history
+    );
+    assert_eq!(result.x, [0.0]);
+}
+
+#[test]
+#[should_panic(expected = "must pair up")]
+fn test_bounds_must_pair_up() {
+    ConstrainedProblem::new(distance_to_two_one).bounds(vec![0.0, 1.0], vec![1.0, 0.0]);
+}
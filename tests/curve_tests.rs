@@ -0,0 +1,214 @@
+use aad::finance::curve::{self, DiscountCurve, Instrument};
+use aad::number::Number;
+
+/// Deposits to 6 months, FRAs to 18 months and annual swaps from 2 to 10 years.
+fn market() -> Vec<Instrument> {
+    let deposit = |maturity, rate| Instrument::Deposit {
+        maturity,
+        rate: Number::new(rate),
+    };
+    let fra = |start, end, rate| Instrument::Fra {
+        start,
+        end,
+        rate: Number::new(rate),
+    };
+    let swap = |maturity, rate| Instrument::Swap {
+        maturity,
+        fixed_period: 1.0,
+        rate: Number::new(rate),
+    };
+    vec![
+        deposit(0.25, 0.0300),
+        deposit(0.5, 0.0315),
+        fra(0.5, 1.0, 0.0340),
+        fra(1.0, 1.5, 0.0355),
+        swap(2.0, 0.0360),
+        swap(3.0, 0.0372),
+        swap(5.0, 0.0390),
+        swap(7.0, 0.0401),
+        swap(10.0, 0.0412),
+    ]
+}
+
+fn quotes(instruments: &[Instrument]) -> Vec<f64> {
+    instruments.iter().map(|i| i.quote().result).collect()
+}
+
+/// Untaped value of `pricer` on the curve bootstrapped from `instruments`
+/// requoted at `rates`.
+fn revalue<P>(instruments: &[Instrument], rates: &[f64], pricer: &P) -> f64
+where
+    P: Fn(&DiscountCurve) -> Number,
+{
+    aad::no_tape(|| {
+        let requoted: Vec<Instrument> = instruments
+            .iter()
+            .zip(rates)
+            .map(|(instrument, rate)| instrument.with_quote(Number::new(*rate)))
+            .collect();
+        pricer(&DiscountCurve::bootstrap(&requoted)).result
+    })
+}
+
+/// Compares the bucketed deltas of `pricer` with central one basis point
+/// bump-and-revalue differences.
+fn test_against_bump_and_revalue<P>(pricer: P)
+where
+    P: Fn(&DiscountCurve) -> Number,
+{
+    let instruments = market();
+    let risk = curve::bucketed_delta(&instruments, &pricer);
+
+    let rates = quotes(&instruments);
+    assert!((risk.value - revalue(&instruments, &rates, &pricer)).abs() < 1e-12);
+    assert_eq!(risk.deltas.len(), instruments.len());
+
+    let bump = 1e-4;
+    for (i, delta) in risk.deltas.iter().enumerate() {
+        let mut up = rates.clone();
+        up[i] += bump;
+        let mut down = rates.clone();
+        down[i] -= bump;
+        let difference = (revalue(&instruments, &up, &pricer)
+            - revalue(&instruments, &down, &pricer))
+            / (2.0 * bump);
+        assert!(
+            (delta - difference).abs() <= 1e-6 * difference.abs().max(1.0),
+            "quote {}: adjoint {} bump and revalue {}",
+            i,
+            delta,
+            difference
+        );
+    }
+}
+
+#[test]
+fn test_bootstrap_reprices_the_instruments() {
+    let instruments = market();
+    let curve = aad::no_tape(|| DiscountCurve::bootstrap(&instruments));
+
+    assert_eq!(
+        curve.times,
+        vec![0.25, 0.5, 1.0, 1.5, 2.0, 3.0, 5.0, 7.0, 10.0]
+    );
+    aad::no_tape(|| {
+        for instrument in &instruments {
+            let par_rate = curve.par_rate(instrument).result;
+            assert!(
+                (par_rate - instrument.quote().result).abs() < 1e-13,
+                "{:?}: par rate {}",
+                instrument,
+                par_rate
+            );
+            assert!(curve.present_value(instrument, 1e6).result.abs() < 1e-7);
+        }
+    });
+}
+
+#[test]
+fn test_deposit_discount_factor() {
+    let instruments = [Instrument::Deposit {
+        maturity: 0.5,
+        rate: Number::new(0.04),
+    }];
+    let risk = curve::bucketed_delta(&instruments, |curve| curve.discount(0.5));
+
+    // df = 1 / (1 + r T), d df / dr = -T / (1 + r T)^2
+    assert!((risk.value - 1.0 / 1.02).abs() < 1e-15);
+    assert!((risk.deltas[0] + 0.5 / (1.02 * 1.02)).abs() < 1e-12);
+}
+
+#[test]
+fn test_flat_curve() {
+    // quotes implied by a flat continuously compounded rate of 3%
+    let r: f64 = 0.03;
+    let df = |t: f64| (-r * t).exp();
+    let instruments = [
+        Instrument::Deposit {
+            maturity: 0.5,
+            rate: Number::new((1.0 / df(0.5) - 1.0) / 0.5),
+        },
+        Instrument::Fra {
+            start: 0.5,
+            end: 1.0,
+            rate: Number::new((df(0.5) / df(1.0) - 1.0) / 0.5),
+        },
+        Instrument::Swap {
+            maturity: 4.0,
+            fixed_period: 0.5,
+            rate: Number::new(
+                (1.0 - df(4.0)) / (1..=8).map(|i| 0.5 * df(0.5 * i as f64)).sum::<f64>(),
+            ),
+        },
+    ];
+
+    aad::no_tape(|| {
+        let curve = DiscountCurve::bootstrap(&instruments);
+        for t in [0.1, 0.5, 0.75, 1.0, 2.3, 4.0, 6.0] {
+            assert!((curve.zero_rate(t).result - r).abs() < 1e-13, "t = {}", t);
+        }
+        let forward = curve.forward_rate(1.0, 3.0).result;
+        assert!((forward - (df(1.0) / df(3.0) - 1.0) / 2.0).abs() < 1e-13);
+    });
+}
+
+#[test]
+fn test_swap_bucketed_delta_against_bump_and_revalue() {
+    // off-market 6 year swap receiving 4.5% on 10 million, whose coupon dates
+    // fall between pillars
+    test_against_bump_and_revalue(|curve| {
+        curve.present_value(
+            &Instrument::Swap {
+                maturity: 6.0,
+                fixed_period: 0.5,
+                rate: Number::from(0.045),
+            },
+            1e7,
+        )
+    });
+}
+
+#[test]
+fn test_rates_bucketed_delta_against_bump_and_revalue() {
+    test_against_bump_and_revalue(|curve| {
+        curve.zero_rate(4.2) * 1e4 + curve.forward_rate(0.75, 1.25) * 1e4 + curve.discount(12.0)
+    });
+}
+
+#[test]
+fn test_delta_is_local_to_the_pillars_used() {
+    // df(1.5) depends on the 6 month deposit and the two FRAs chained from it
+    let risk = curve::bucketed_delta(&market(), |curve| curve.discount(1.5));
+    assert_eq!(risk.deltas[0], 0.0);
+    assert!(risk.deltas[1..4].iter().all(|delta| *delta < 0.0));
+    assert!(risk.deltas[4..].iter().all(|delta| *delta == 0.0));
+}
+
+#[test]
+fn test_par_swap_delta_is_its_annuity() {
+    // receiving 3.9% for 5 years is at par on the market curve; a higher 5
+    // year quote costs the annuity per unit of rate, the shorter quotes are
+    // hedged by the swap itself
+    let instruments = market();
+    let risk = curve::bucketed_delta(&instruments, |curve| {
+        curve.present_value(&instruments[6].with_quote(Number::from(0.039)), 1.0)
+    });
+    let annuity = aad::no_tape(|| {
+        DiscountCurve::bootstrap(&instruments)
+            .annuity(5.0, 1.0)
+            .result
+    });
+
+    assert!(risk.value.abs() < 1e-14);
+    assert!(risk.deltas[..6].iter().all(|delta| delta.abs() < 1e-12));
+    assert!((risk.deltas[6] + annuity).abs() < 1e-12);
+    assert!(risk.deltas[7..].iter().all(|delta| *delta == 0.0));
+}
+
+#[test]
+#[should_panic(expected = "sorted by strictly increasing maturity")]
+fn test_unsorted_instruments() {
+    let mut instruments = market();
+    instruments.swap(2, 3);
+    aad::no_tape(|| DiscountCurve::bootstrap(&instruments));
+}
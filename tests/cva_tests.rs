@@ -0,0 +1,203 @@
+use aad::finance::curve::{DiscountCurve, Instrument};
+use aad::finance::cva::{Cva, CvaMarket, HazardCurve, HullWhite, Swap};
+use aad::number::Number;
+
+mod common;
+
+fn market() -> CvaMarket {
+    let deposit = |maturity, rate| Instrument::Deposit {
+        maturity,
+        rate: Number::new(rate),
+    };
+    let swap = |maturity, rate| Instrument::Swap {
+        maturity,
+        fixed_period: 1.0,
+        rate: Number::new(rate),
+    };
+    CvaMarket {
+        instruments: vec![
+            deposit(0.5, 0.0315),
+            swap(1.0, 0.0330),
+            swap(2.0, 0.0350),
+            swap(3.0, 0.0365),
+            swap(5.0, 0.0385),
+        ],
+        mean_reversion: 0.05,
+        volatility: 0.01,
+        hazard_times: vec![1.0, 3.0, 5.0],
+        hazard_rates: vec![0.01, 0.015, 0.02],
+        recovery: 0.4,
+    }
+}
+
+fn quarterly(years: usize) -> Vec<f64> {
+    (1..=4 * years).map(|i| 0.25 * i as f64).collect()
+}
+
+fn receiver(fixed_rate: f64, maturity: f64) -> Swap {
+    Swap {
+        notional: 1e6,
+        fixed_rate,
+        maturity,
+        fixed_period: 1.0,
+        payer: false,
+    }
+}
+
+#[test]
+fn test_bonds_at_time_zero_are_the_curve() {
+    aad::no_tape(|| {
+        let curve = DiscountCurve::bootstrap(&market().instruments);
+        let model = HullWhite::new(0.05, 0.01);
+        for maturity in [0.3, 1.0, 2.7, 5.0] {
+            let bond = model.bond(|t| curve.discount(t), 0.0, maturity, Number::from(0.0));
+            assert!((bond.result - curve.discount(maturity).result).abs() < 1e-15);
+        }
+    });
+}
+
+#[test]
+fn test_survival() {
+    aad::no_tape(|| {
+        let hazard_curve =
+            HazardCurve::new(vec![1.0, 3.0], vec![Number::new(0.01), Number::new(0.02)]);
+        let survival = |t: f64| hazard_curve.survival(t).result;
+        assert_eq!(survival(0.0), 1.0);
+        assert!((survival(0.5) - (-0.005f64).exp()).abs() < 1e-15);
+        assert!((survival(2.0) - (-0.03f64).exp()).abs() < 1e-15);
+        // flat beyond the last time
+        assert!((survival(4.0) - (-0.07f64).exp()).abs() < 1e-15);
+    });
+}
+
+#[test]
+fn test_swap_value_at_time_zero_is_its_present_value() {
+    aad::no_tape(|| {
+        let curve = DiscountCurve::bootstrap(&market().instruments);
+        let swap = receiver(0.04, 4.0);
+        let value = swap.value(0.0, |t| curve.discount(t)).result;
+        let present_value = curve
+            .present_value(
+                &Instrument::Swap {
+                    maturity: 4.0,
+                    fixed_period: 1.0,
+                    rate: Number::from(0.04),
+                },
+                1e6,
+            )
+            .result;
+        assert!((value - present_value).abs() < 1e-8);
+
+        let payer = Swap {
+            payer: true,
+            ..swap
+        };
+        assert_eq!(payer.value(0.0, |t| curve.discount(t)).result, -value);
+        // nothing left after the last coupon
+        assert_eq!(swap.value(4.0, |t| curve.discount(t)).result, 0.0);
+    });
+}
+
+#[test]
+fn test_exposure_of_a_swap_always_in_the_money() {
+    // receiving 20% the swap never has a negative value, and the discounted
+    // exposure is the forward value of its remaining cash flows
+    let market = market();
+    let swap = receiver(0.2, 3.0);
+    let engine = Cva::new(1000, quarterly(3), 7);
+    let result = engine.run(&market, &[swap]);
+
+    let curve = aad::no_tape(|| DiscountCurve::bootstrap(&market.instruments));
+    for (t, exposure) in engine
+        .exposure_times
+        .iter()
+        .zip(&result.expected_positive_exposure)
+    {
+        let expected = aad::no_tape(|| {
+            swap.value(*t, |maturity| curve.discount(maturity) / curve.discount(*t))
+                .result
+                * curve.discount(*t).result
+        });
+        assert!(
+            (exposure - expected).abs() < 3e-3 * 1e6,
+            "t = {}: exposure {} expected {}",
+            t,
+            exposure,
+            expected
+        );
+    }
+    assert_eq!(*result.expected_positive_exposure.last().unwrap(), 0.0);
+}
+
+#[test]
+fn test_sensitivities_against_bump_and_revalue() {
+    let market = market();
+    let netting_set = [
+        receiver(0.037, 5.0),
+        Swap {
+            payer: true,
+            ..receiver(0.035, 2.0)
+        },
+    ];
+    let engine = Cva::new(100, quarterly(5), 11);
+    let result = engine.run(&market, &netting_set);
+
+    let inputs = market.inputs();
+    assert!(result.cva > 0.0);
+    assert!((result.cva - engine.value(&market, &netting_set)).abs() < 1e-9);
+
+    let sensitivities: Vec<f64> = result
+        .sensitivities
+        .curve
+        .iter()
+        .copied()
+        .chain([
+            result.sensitivities.mean_reversion,
+            result.sensitivities.volatility,
+        ])
+        .chain(result.sensitivities.hazard_rates.iter().copied())
+        .chain([result.sensitivities.recovery])
+        .collect();
+    common::assert_against_bump_and_revalue(&sensitivities, &inputs, 1e-6, |inputs| {
+        engine.value(&market.with_inputs(inputs), &netting_set)
+    });
+}
+
+#[test]
+fn test_recovery_and_credit_sensitivities() {
+    let market = market();
+    let engine = Cva::new(100, quarterly(5), 3);
+    let result = engine.run(&market, &[receiver(0.04, 5.0)]);
+
+    // CVA is linear in the loss given default
+    let recovery = result.sensitivities.recovery;
+    assert!((recovery + result.cva / (1.0 - market.recovery)).abs() < 1e-9);
+    // more default risk costs more
+    assert!(result.sensitivities.hazard_rates.iter().all(|s| *s > 0.0));
+
+    let riskless = CvaMarket {
+        hazard_rates: vec![0.0; 3],
+        ..market
+    };
+    assert_eq!(engine.run(&riskless, &[receiver(0.04, 5.0)]).cva, 0.0);
+}
+
+#[test]
+fn test_netting() {
+    // a swap and its mirror net to no exposure
+    let market = market();
+    let engine = Cva::new(50, quarterly(5), 5);
+    let swap = receiver(0.038, 5.0);
+    let mirror = Swap {
+        payer: true,
+        ..swap
+    };
+
+    let alone = engine.run(&market, &[swap]).cva;
+    let mirror_alone = engine.run(&market, &[mirror]).cva;
+    let netted = engine.run(&market, &[swap, mirror]);
+
+    assert!(alone > 0.0 && mirror_alone > 0.0);
+    assert_eq!(netted.cva, 0.0);
+    assert!(netted.expected_positive_exposure.iter().all(|e| *e == 0.0));
+}
@@ -0,0 +1,157 @@
+use aad::automatic_differentiator::AutomaticDifferentiator;
+use aad::differential_ml::{DifferentialRegression, SampleGenerator};
+use aad::finance::black_scholes::{self, EuropeanOption, OptionType};
+use aad::number::Number;
+
+const STRIKE: f64 = 100.0;
+const MATURITY: f64 = 1.0;
+const RATE: f64 = 0.02;
+const VOLATILITY: f64 = 0.25;
+
+/// Discounted payoff of a call on one GBM path from the spot `state[0]`.
+fn call_path(state: &[Number], normals: &[f64]) -> Number {
+    let spot = state[0]
+        * ((RATE - 0.5 * VOLATILITY * VOLATILITY) * MATURITY
+            + VOLATILITY * MATURITY.sqrt() * normals[0])
+            .exp();
+    (-RATE * MATURITY).exp() * (spot - STRIKE).max(0.0)
+}
+
+fn call(spot: f64) -> EuropeanOption {
+    EuropeanOption {
+        option_type: OptionType::Call,
+        spot,
+        strike: STRIKE,
+        maturity: MATURITY,
+        rate: RATE,
+        dividend_yield: 0.0,
+        volatility: VOLATILITY,
+    }
+}
+
+fn polynomial(x: &[Number]) -> Number {
+    1.5 + 2.0 * x[0] - 0.5 * x[1] + 0.25 * x[0] * x[1] - 0.1 * x[1] * x[1]
+}
+
+#[test]
+fn test_samples_are_values_and_adjoints() {
+    let generator = SampleGenerator::new(vec![80.0, 0.1], vec![120.0, 0.4], 0, 3);
+    let samples = generator.generate(20, |state: &[Number], _normals: &[f64]| {
+        black_scholes::price(
+            OptionType::Put,
+            state[0],
+            Number::from(STRIKE),
+            Number::from(MATURITY),
+            Number::from(RATE),
+            Number::from(0.0),
+            state[1],
+        )
+    });
+    assert_eq!(samples.len(), 20);
+
+    for sample in &samples {
+        assert!(sample.x[0] >= 80.0 && sample.x[0] <= 120.0);
+        assert!(sample.x[1] >= 0.1 && sample.x[1] <= 0.4);
+        let greeks = EuropeanOption {
+            option_type: OptionType::Put,
+            volatility: sample.x[1],
+            ..call(sample.x[0])
+        }
+        .greeks();
+        assert!((sample.y - greeks.price).abs() < 1e-10);
+        assert!((sample.dydx[0] - greeks.delta).abs() < 1e-10);
+        assert!((sample.dydx[1] - greeks.vega).abs() < 1e-8);
+    }
+
+    // reproducible from the seed
+    let again = generator.generate(20, |state: &[Number], _normals: &[f64]| state[0] * state[1]);
+    let first = generator.generate(20, |state: &[Number], _normals: &[f64]| state[0] * state[1]);
+    assert_eq!(again, first);
+    assert!(first.iter().zip(&samples).all(|(a, b)| a.x == b.x));
+}
+
+#[test]
+fn test_fit_recovers_a_polynomial() {
+    let generator = SampleGenerator::new(vec![-1.0, 0.0], vec![2.0, 3.0], 0, 7);
+    let samples = generator.generate(30, |state: &[Number], _normals: &[f64]| polynomial(state));
+
+    for derivative_weight in [0.0, 1.0, 100.0] {
+        let proxy = DifferentialRegression::new(2, derivative_weight).fit(&samples);
+        assert_eq!(proxy.exponents.len(), 6);
+
+        let mut automatic_differentiator = AutomaticDifferentiator::new();
+        for point in [[0.5, 1.0], [-0.7, 2.5], [1.9, 0.1]] {
+            let arguments: Vec<Number> = point.iter().map(|x| Number::new(*x)).collect();
+            let proxied = automatic_differentiator.derivatives(|x| proxy.value(x), &arguments);
+            let exact = automatic_differentiator.derivatives(polynomial, &arguments);
+            assert!((proxied.result - exact.result).abs() < 1e-10);
+            for argument in &arguments {
+                assert!(
+                    (proxied.derivative(*argument) - exact.derivative(*argument)).abs() < 1e-10
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_derivatives_alone_fit_up_to_a_constant() {
+    // with the derivatives weighing everything the proxy still matches the
+    // slopes, the values only pin the constant
+    let generator = SampleGenerator::new(vec![-1.0, 0.0], vec![2.0, 3.0], 0, 11);
+    let mut samples =
+        generator.generate(30, |state: &[Number], _normals: &[f64]| polynomial(state));
+    for sample in samples.iter_mut() {
+        sample.y += 0.5;
+    }
+    let proxy = DifferentialRegression::new(2, 1e6).fit(&samples);
+    let value = |x: [f64; 2]| aad::no_tape(|| proxy.value(&x.map(Number::new)).result);
+    let exact = |x: [f64; 2]| aad::no_tape(|| polynomial(&x.map(Number::new)).result);
+    let shift = value([0.0, 0.0]) - exact([0.0, 0.0]);
+    assert!((shift - 0.5).abs() < 1e-6);
+    assert!((value([1.5, 2.0]) - exact([1.5, 2.0]) - shift).abs() < 1e-6);
+}
+
+#[test]
+fn test_differential_fit_of_monte_carlo_samples() {
+    // one path per sample: the payoffs are noisy labels of the price, their
+    // pathwise deltas much less noisy labels of its slope
+    let generator = SampleGenerator::new(vec![60.0], vec![140.0], 1, 19);
+    let samples = generator.generate(2000, call_path);
+
+    let classic = DifferentialRegression::new(5, 0.0).fit(&samples);
+    let differential = DifferentialRegression::new(5, 1.0).fit(&samples);
+
+    let errors = |proxy: &aad::differential_ml::PolynomialProxy| {
+        let mut automatic_differentiator = AutomaticDifferentiator::new();
+        let (mut price_error, mut delta_error) = (0.0f64, 0.0f64);
+        for i in 0..=20 {
+            let spot = 70.0 + 3.0 * i as f64;
+            let greeks = call(spot).greeks();
+            let argument = Number::new(spot);
+            let evaluation = automatic_differentiator.derivatives(|x| proxy.value(x), &[argument]);
+            price_error = price_error.max((evaluation.result - greeks.price).abs());
+            delta_error = delta_error.max((evaluation.derivative(argument) - greeks.delta).abs());
+        }
+        (price_error, delta_error)
+    };
+    let (classic_price, classic_delta) = errors(&classic);
+    let (price, delta) = errors(&differential);
+
+    assert!(price < 1.0, "price error {}", price);
+    assert!(delta < 0.1, "delta error {}", delta);
+    assert!(
+        price < 0.5 * classic_price && delta < 0.5 * classic_delta,
+        "differential {} {}, values only {} {}",
+        price,
+        delta,
+        classic_price,
+        classic_delta
+    );
+}
+
+#[test]
+#[should_panic(expected = "must pair up")]
+fn test_bounds_must_pair_up() {
+    SampleGenerator::new(vec![0.0, 1.0], vec![1.0], 0, 1);
+}
@@ -15,7 +15,7 @@ fn test_binary_operator<F>(
 {
     let mut automatic_differentiator = AutomaticDifferentiator::new();
 
-    let evaluation = automatic_differentiator.derivatives(func, &arguments);
+    let evaluation = automatic_differentiator.derivatives(func, arguments);
 
     let x = arguments[0];
     let y = arguments[1];
@@ -59,6 +59,20 @@ fn test_add_3_5() {
     test_binary_operator(f, &arguments, 8.000000, 1.00000, 1.00000);
 }
 
+#[test]
+fn test_add_with_itself() {
+    let x = Number::new(3.0);
+    let y = Number::new(5.0);
+    let arguments = vec![x, y];
+
+    fn f(args: &[Number]) -> Number {
+        let x = args[0];
+        let y = args[1];
+        (x + x) * y
+    }
+    test_binary_operator(f, &arguments, 30.000000, 10.00000, 6.00000);
+}
+
 #[test]
 fn test_sub_3_5() {
     let x = Number::new(3.0);
@@ -114,3 +128,171 @@ fn test_div_5_3() {
     }
     test_binary_operator(f, &arguments, 1.666667, 0.333333, -0.55556);
 }
+
+#[test]
+fn test_atan2_3_5() {
+    let x = Number::new(3.0);
+    let y = Number::new(5.0);
+    let arguments = vec![x, y];
+
+    fn f(args: &[Number]) -> Number {
+        let x = args[0];
+        let y = args[1];
+        x.atan2(y)
+    }
+    test_binary_operator(f, &arguments, 0.540420, 0.147059, -0.088235);
+}
+
+#[test]
+fn test_atan2_3_minus_5() {
+    let x = Number::new(3.0);
+    let y = Number::new(-5.0);
+    let arguments = vec![x, y];
+
+    fn f(args: &[Number]) -> Number {
+        let x = args[0];
+        let y = args[1];
+        x.atan2(y)
+    }
+    test_binary_operator(f, &arguments, 2.601173, -0.147059, -0.088235);
+}
+
+#[test]
+fn test_hypot_3_4() {
+    let x = Number::new(3.0);
+    let y = Number::new(4.0);
+    let arguments = vec![x, y];
+
+    fn f(args: &[Number]) -> Number {
+        let x = args[0];
+        let y = args[1];
+        x.hypot(y)
+    }
+    test_binary_operator(f, &arguments, 5.000000, 0.600000, 0.800000);
+}
+
+#[test]
+fn test_hypot_at_origin() {
+    let x = Number::new(0.0);
+    let y = Number::new(0.0);
+    let arguments = vec![x, y];
+
+    fn f(args: &[Number]) -> Number {
+        let x = args[0];
+        let y = args[1];
+        x.hypot(y)
+    }
+    test_binary_operator(f, &arguments, 0.000000, 0.000000, 0.000000);
+}
+
+#[test]
+fn test_powf_2_3() {
+    let x = Number::new(2.0);
+    let y = Number::new(3.0);
+    let arguments = vec![x, y];
+
+    fn f(args: &[Number]) -> Number {
+        let x = args[0];
+        let y = args[1];
+        x.powf(y)
+    }
+    test_binary_operator(f, &arguments, 8.000000, 12.000000, 5.545177);
+}
+
+#[test]
+fn test_powf_3_minus_0_comma_5() {
+    let x = Number::new(3.0);
+    let y = Number::new(-0.5);
+    let arguments = vec![x, y];
+
+    fn f(args: &[Number]) -> Number {
+        let x = args[0];
+        let y = args[1];
+        x.powf(y)
+    }
+    test_binary_operator(f, &arguments, 0.577350, -0.096225, 0.634284);
+}
+
+#[test]
+fn test_powf_0_2() {
+    let x = Number::new(0.0);
+    let y = Number::new(2.0);
+    let arguments = vec![x, y];
+
+    fn f(args: &[Number]) -> Number {
+        let x = args[0];
+        let y = args[1];
+        x.powf(y)
+    }
+    test_binary_operator(f, &arguments, 0.000000, 0.000000, 0.000000);
+}
+
+#[test]
+fn test_powf_0_1() {
+    let x = Number::new(0.0);
+    let y = Number::new(1.0);
+    let arguments = vec![x, y];
+
+    fn f(args: &[Number]) -> Number {
+        let x = args[0];
+        let y = args[1];
+        x.powf(y)
+    }
+    test_binary_operator(f, &arguments, 0.000000, 1.000000, 0.000000);
+}
+
+#[test]
+fn test_powf_0_0() {
+    let x = Number::new(0.0);
+    let y = Number::new(0.0);
+    let arguments = vec![x, y];
+
+    fn f(args: &[Number]) -> Number {
+        let x = args[0];
+        let y = args[1];
+        x.powf(y)
+    }
+    test_binary_operator(f, &arguments, 1.000000, 0.000000, 0.000000);
+}
+
+#[test]
+fn test_powf_5_0() {
+    let x = Number::new(5.0);
+    let y = Number::new(0.0);
+    let arguments = vec![x, y];
+
+    fn f(args: &[Number]) -> Number {
+        let x = args[0];
+        let y = args[1];
+        x.powf(y)
+    }
+    test_binary_operator(f, &arguments, 1.000000, 0.000000, 1.609438);
+}
+
+#[test]
+fn test_log_base_8_2() {
+    let x = Number::new(8.0);
+    let y = Number::new(2.0);
+    let arguments = vec![x, y];
+
+    fn f(args: &[Number]) -> Number {
+        let x = args[0];
+        let y = args[1];
+        x.log_base(y)
+    }
+    test_binary_operator(f, &arguments, 3.000000, 0.180337, -2.164043);
+}
+
+#[test]
+fn test_log_base_3_10() {
+    let x = Number::new(3.0);
+    let y = Number::new(10.0);
+    let arguments = vec![x, y];
+
+    fn f(args: &[Number]) -> Number {
+        let x = args[0];
+        let y = args[1];
+        x.log_base(y)
+    }
+    test_binary_operator(f, &arguments, 0.477121, 0.144765, -0.020721);
+}
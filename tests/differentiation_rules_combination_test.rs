@@ -1,3 +1,5 @@
+#![allow(clippy::excessive_precision)]
+
 use std::f64::consts::PI;
 
 use aad::automatic_differentiator::AutomaticDifferentiator;
@@ -21,8 +23,7 @@ fn test_operators_add_mul_ln() {
     fn f(args: &[Number]) -> Number {
         let y1 = args[2] * (args[4] * args[0] + args[1]);
         let y2 = y1.ln();
-        let y = (y1 + args[3] * y2) * (y1 + y2);
-        y
+        (y1 + args[3] * y2) * (y1 + y2)
     }
 
     let evaluation = automatic_differentiator.derivatives(f, &arguments);
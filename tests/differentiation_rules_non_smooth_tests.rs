@@ -0,0 +1,193 @@
+use aad::automatic_differentiator::AutomaticDifferentiator;
+use aad::number::Number;
+
+fn test_unary_operator<F>(func: F, x: f64, expected_result: f64, expected_dfdx: f64)
+where
+    F: Fn(&[Number]) -> Number,
+{
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let x = Number::new(x);
+    let evaluation = automatic_differentiator.derivatives(func, &[x]);
+
+    let epsilon = 1e-12;
+    assert_eq!(evaluation.derivatives.len(), 1);
+    assert!((evaluation.result - expected_result).abs() < epsilon);
+    assert!((evaluation.derivative(x) - expected_dfdx).abs() < epsilon);
+}
+
+fn test_binary_operator<F>(
+    func: F,
+    x: f64,
+    y: f64,
+    expected_result: f64,
+    expected_dfdx: f64,
+    expected_dfdy: f64,
+) where
+    F: Fn(&[Number]) -> Number,
+{
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let x = Number::new(x);
+    let y = Number::new(y);
+    let evaluation = automatic_differentiator.derivatives(func, &[x, y]);
+
+    let epsilon = 1e-12;
+    assert_eq!(evaluation.derivatives.len(), 2);
+    assert!((evaluation.result - expected_result).abs() < epsilon);
+    assert!((evaluation.derivative(x) - expected_dfdx).abs() < epsilon);
+    assert!((evaluation.derivative(y) - expected_dfdy).abs() < epsilon);
+}
+
+#[test]
+fn test_abs() {
+    fn f(args: &[Number]) -> Number {
+        args[0].abs()
+    }
+    test_unary_operator(f, -3.0, 3.0, -1.0);
+    test_unary_operator(f, 2.5, 2.5, 1.0);
+    test_unary_operator(f, 0.0, 0.0, 0.0);
+}
+
+#[test]
+fn test_abs_of_product() {
+    // |x * x * x| = -x^3 for x < 0, derivative -3x^2
+    fn f(args: &[Number]) -> Number {
+        (args[0] * args[0] * args[0]).abs()
+    }
+    test_unary_operator(f, -2.0, 8.0, -12.0);
+}
+
+#[test]
+fn test_max() {
+    fn f(args: &[Number]) -> Number {
+        args[0].max(args[1])
+    }
+    test_binary_operator(f, 3.0, 2.0, 3.0, 1.0, 0.0);
+    test_binary_operator(f, -3.0, 2.0, 2.0, 0.0, 1.0);
+    test_binary_operator(f, 2.0, 2.0, 2.0, 0.5, 0.5);
+}
+
+#[test]
+fn test_min() {
+    fn f(args: &[Number]) -> Number {
+        args[0].min(args[1])
+    }
+    test_binary_operator(f, 3.0, 2.0, 2.0, 0.0, 1.0);
+    test_binary_operator(f, -3.0, 2.0, -3.0, 1.0, 0.0);
+    test_binary_operator(f, 2.0, 2.0, 2.0, 0.5, 0.5);
+}
+
+#[test]
+fn test_max_with_itself() {
+    fn f(args: &[Number]) -> Number {
+        (args[0] * 2.0).max(args[0] * 2.0)
+    }
+    test_unary_operator(f, 1.5, 3.0, 2.0);
+}
+
+#[test]
+fn test_call_payoff() {
+    // max(S - K, 0)
+    fn f(args: &[Number]) -> Number {
+        (args[0] - args[1]).max(0.0)
+    }
+    test_binary_operator(f, 110.0, 100.0, 10.0, 1.0, -1.0);
+    test_binary_operator(f, 90.0, 100.0, 0.0, 0.0, 0.0);
+    test_binary_operator(f, 100.0, 100.0, 0.0, 0.5, -0.5);
+}
+
+#[test]
+fn test_put_payoff_with_min() {
+    // -min(S - K, 0) = max(K - S, 0)
+    fn f(args: &[Number]) -> Number {
+        -1.0 * (args[0] - args[1]).min(0.0)
+    }
+    test_binary_operator(f, 90.0, 100.0, 10.0, -1.0, 1.0);
+    test_binary_operator(f, 110.0, 100.0, 0.0, 0.0, 0.0);
+}
+
+#[test]
+fn test_clamp() {
+    fn f(args: &[Number]) -> Number {
+        (args[0] * 3.0).clamp(-1.0, 1.0)
+    }
+    test_unary_operator(f, 0.25, 0.75, 3.0);
+    test_unary_operator(f, 1.0, 1.0, 0.0);
+    test_unary_operator(f, -1.0, -1.0, 0.0);
+    test_unary_operator(f, 1.0 / 3.0, 1.0, 1.5);
+}
+
+#[test]
+fn test_clamp_to_a_point() {
+    fn f(args: &[Number]) -> Number {
+        args[0].clamp(2.0, 2.0)
+    }
+    test_unary_operator(f, 2.0, 2.0, 0.0);
+}
+
+#[test]
+fn test_floor_ceil_signum() {
+    fn floor(args: &[Number]) -> Number {
+        (args[0] * 2.0).floor()
+    }
+    fn ceil(args: &[Number]) -> Number {
+        (args[0] * 2.0).ceil()
+    }
+    fn signum(args: &[Number]) -> Number {
+        (args[0] * 2.0).signum()
+    }
+    test_unary_operator(floor, 1.3, 2.0, 0.0);
+    test_unary_operator(floor, -1.3, -3.0, 0.0);
+    test_unary_operator(ceil, 1.3, 3.0, 0.0);
+    test_unary_operator(ceil, -1.3, -2.0, 0.0);
+    test_unary_operator(signum, -1.3, -1.0, 0.0);
+    test_unary_operator(signum, 0.0, 1.0, 0.0);
+}
+
+#[test]
+fn test_floor_in_sum() {
+    // x + floor(x) has derivative 1 from the smooth term only
+    fn f(args: &[Number]) -> Number {
+        args[0] + args[0].floor()
+    }
+    test_unary_operator(f, 2.7, 4.7, 1.0);
+}
+
+#[test]
+fn test_comparisons_use_primal_value() {
+    let x = Number::new(1.0);
+    let y = Number::new(2.0);
+    let also_one = Number::new(1.0);
+
+    assert!(x < y);
+    assert!(y > x);
+    assert!(x <= also_one);
+    assert!(x == also_one);
+    assert!(x != y);
+    assert!(x > 0.5);
+    assert!(x == 1.0);
+    assert!(3.0 > y);
+    assert!(2.0 == y);
+    assert!(x.partial_cmp(&Number::new(f64::NAN)).is_none());
+}
+
+#[test]
+fn test_non_smooth_functions_without_tape() {
+    let y = aad::no_tape(|| {
+        let x = Number::new(-2.5);
+        (x.abs().max(3.0) + x.clamp(-1.0, 1.0) + x.floor() + x.ceil()).min(0.0)
+    });
+    assert_eq!(y.id, 0);
+    assert_eq!(y.result, 3.0 - 1.0 - 3.0 - 2.0);
+}
+
+#[test]
+fn test_nested_no_tape_keeps_the_tape_disabled() {
+    let y = aad::no_tape(|| {
+        let x = aad::no_tape(|| Number::new(2.0) * 3.0);
+        x.abs() + 1.0
+    });
+    assert_eq!(y.id, 0);
+    assert_eq!(y.result, 7.0);
+}
@@ -0,0 +1,132 @@
+use aad::automatic_differentiator::AutomaticDifferentiator;
+use aad::number::Number;
+
+// Reference values computed with mpmath at 30 significant digits.
+
+fn test_unary_operator<F>(func: F, x: f64, expected_result: f64, expected_dfdx: f64)
+where
+    F: Fn(&[Number]) -> Number,
+{
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let x = Number::new(x);
+    let evaluation = automatic_differentiator.derivatives(func, &[x]);
+
+    let epsilon = 1e-10;
+    assert_eq!(evaluation.derivatives.len(), 1);
+    assert!((evaluation.result - expected_result).abs() < epsilon);
+    assert!((evaluation.derivative(x) - expected_dfdx).abs() < epsilon);
+}
+
+#[test]
+fn test_norm_pdf_of_0_comma_7() {
+    fn f(args: &[Number]) -> Number {
+        args[0].norm_pdf()
+    }
+    test_unary_operator(f, 0.7, 0.312253933366761, -0.218577753356733);
+}
+
+#[test]
+fn test_cdf_of_0_comma_7() {
+    fn f(args: &[Number]) -> Number {
+        args[0].cdf()
+    }
+    test_unary_operator(f, 0.7, 0.758036347776927, 0.312253933366761);
+}
+
+#[test]
+fn test_norm_inv_cdf_of_0_comma_975() {
+    fn f(args: &[Number]) -> Number {
+        args[0].norm_inv_cdf()
+    }
+    test_unary_operator(f, 0.975, 1.959963984540054, 17.11008308033272);
+}
+
+#[test]
+fn test_norm_inv_cdf_of_0_comma_01() {
+    fn f(args: &[Number]) -> Number {
+        args[0].norm_inv_cdf()
+    }
+    test_unary_operator(f, 0.01, -2.326347874040841, 37.52043615729517);
+}
+
+#[test]
+fn test_cdf_of_norm_inv_cdf_is_identity() {
+    fn f(args: &[Number]) -> Number {
+        args[0].norm_inv_cdf().cdf()
+    }
+    for p in [1e-6, 0.02, 0.3, 0.5, 0.8, 0.999] {
+        test_unary_operator(f, p, p, 1.0);
+    }
+}
+
+#[test]
+fn test_erf_of_0_comma_7() {
+    fn f(args: &[Number]) -> Number {
+        args[0].erf()
+    }
+    test_unary_operator(f, 0.7, 0.677801193837418, 0.691274860410539);
+}
+
+#[test]
+fn test_erfc_of_0_comma_7() {
+    fn f(args: &[Number]) -> Number {
+        args[0].erfc()
+    }
+    test_unary_operator(f, 0.7, 0.322198806162582, -0.691274860410539);
+}
+
+#[test]
+fn test_normal_cdf_with_active_mean_and_deviation() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let x = Number::new(1.3);
+    let mu = Number::new(0.5);
+    let sigma = Number::new(2.0);
+
+    fn f(args: &[Number]) -> Number {
+        args[0].normal_cdf(args[1], args[2])
+    }
+
+    let evaluation = automatic_differentiator.derivatives(f, &[x, mu, sigma]);
+
+    let epsilon = 1e-10;
+    assert_eq!(evaluation.derivatives.len(), 3);
+    assert!((evaluation.result - 0.655421741610324).abs() < epsilon);
+    assert!((evaluation.derivative(x) - 0.184135070151662).abs() < epsilon);
+    assert!((evaluation.derivative(mu) + 0.184135070151662).abs() < epsilon);
+    assert!((evaluation.derivative(sigma) + 0.073654028060665).abs() < epsilon);
+}
+
+#[test]
+fn test_normal_cdf_matches_standardised_cdf() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let x = Number::new(-0.4);
+    let mu = Number::new(0.1);
+    let sigma = Number::new(0.3);
+
+    fn single_node(args: &[Number]) -> Number {
+        args[0].normal_cdf(args[1], args[2])
+    }
+    fn composed(args: &[Number]) -> Number {
+        ((args[0] - args[1]) / args[2]).cdf()
+    }
+
+    let single = automatic_differentiator.derivatives(single_node, &[x, mu, sigma]);
+    let reference = automatic_differentiator.derivatives(composed, &[x, mu, sigma]);
+
+    let epsilon = 1e-12;
+    assert!((single.result - reference.result).abs() < epsilon);
+    for input in [x, mu, sigma] {
+        assert!((single.derivative(input) - reference.derivative(input)).abs() < epsilon);
+    }
+}
+
+#[test]
+fn test_normal_cdf_with_constant_parameters() {
+    fn f(args: &[Number]) -> Number {
+        args[0].normal_cdf(0.5, 2.0)
+    }
+    test_unary_operator(f, 1.3, 0.655421741610324, 0.184135070151662);
+}
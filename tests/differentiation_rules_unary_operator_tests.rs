@@ -1,3 +1,7 @@
+#![allow(clippy::excessive_precision)]
+
+use std::f64::consts::{FRAC_PI_3, FRAC_PI_6};
+
 use aad::automatic_differentiator::AutomaticDifferentiator;
 use aad::number::Number;
 
@@ -10,7 +14,7 @@ where
 {
     let mut automatic_differentiator = AutomaticDifferentiator::new();
 
-    let evaluation = automatic_differentiator.derivatives(func, &arguments);
+    let evaluation = automatic_differentiator.derivatives(func, arguments);
 
     let x = arguments[0];
 
@@ -25,7 +29,7 @@ where
     let epsilon = 1e-5;
     assert_eq!(evaluation.derivatives.len(), 1);
     assert!((evaluation.result - expected_result).abs() < epsilon);
-    assert!(dfdx - expected_dfdx < epsilon);
+    assert!((dfdx - expected_dfdx).abs() < epsilon);
 }
 
 #[test]
@@ -124,3 +128,267 @@ fn test_log_base_eight_of_3() {
     }
     test_unary_operator(f, &arguments, 0.528321, 0.160299);
 }
+
+#[test]
+fn test_tan_of_0_comma_5() {
+    let arg = Number::new(0.5);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.tan()
+    }
+    test_unary_operator(f, &arguments, 0.546302, 1.298446);
+}
+
+#[test]
+fn test_asin_of_0_comma_5() {
+    let arg = Number::new(0.5);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.asin()
+    }
+    test_unary_operator(f, &arguments, FRAC_PI_6, 1.154701);
+}
+
+#[test]
+fn test_acos_of_0_comma_5() {
+    let arg = Number::new(0.5);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.acos()
+    }
+    test_unary_operator(f, &arguments, FRAC_PI_3, -1.154701);
+}
+
+#[test]
+fn test_atan_of_3() {
+    let arg = Number::new(3.0);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.atan()
+    }
+    test_unary_operator(f, &arguments, 1.249046, 0.100000);
+}
+
+#[test]
+fn test_sinh_of_3() {
+    let arg = Number::new(3.0);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.sinh()
+    }
+    test_unary_operator(f, &arguments, 10.017875, 10.067662);
+}
+
+#[test]
+fn test_cosh_of_3() {
+    let arg = Number::new(3.0);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.cosh()
+    }
+    test_unary_operator(f, &arguments, 10.067662, 10.017875);
+}
+
+#[test]
+fn test_tanh_of_0_comma_5() {
+    let arg = Number::new(0.5);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.tanh()
+    }
+    test_unary_operator(f, &arguments, 0.462117, 0.786448);
+}
+
+#[test]
+fn test_asinh_of_3() {
+    let arg = Number::new(3.0);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.asinh()
+    }
+    test_unary_operator(f, &arguments, 1.818446, 0.316228);
+}
+
+#[test]
+fn test_acosh_of_3() {
+    let arg = Number::new(3.0);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.acosh()
+    }
+    test_unary_operator(f, &arguments, 1.762747, 0.353553);
+}
+
+#[test]
+fn test_atanh_of_0_comma_5() {
+    let arg = Number::new(0.5);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.atanh()
+    }
+    test_unary_operator(f, &arguments, 0.549306, 1.333333);
+}
+
+#[test]
+fn test_exp2_of_3() {
+    let arg = Number::new(3.0);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.exp2()
+    }
+    test_unary_operator(f, &arguments, 8.000000, 5.545177);
+}
+
+#[test]
+fn test_exp_m1_of_3() {
+    let arg = Number::new(3.0);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.exp_m1()
+    }
+    test_unary_operator(f, &arguments, 19.085537, 20.085537);
+}
+
+#[test]
+fn test_ln_1p_of_3() {
+    let arg = Number::new(3.0);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.ln_1p()
+    }
+    test_unary_operator(f, &arguments, 1.386294, 0.250000);
+}
+
+#[test]
+fn test_cbrt_of_3() {
+    let arg = Number::new(3.0);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.cbrt()
+    }
+    test_unary_operator(f, &arguments, 1.442250, 0.160250);
+}
+
+#[test]
+fn test_recip_of_3() {
+    let arg = Number::new(3.0);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.recip()
+    }
+    test_unary_operator(f, &arguments, 0.333333, -0.111111);
+}
+
+#[test]
+fn test_powi_fifth_of_3() {
+    let arg = Number::new(3.0);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.powi(5)
+    }
+    test_unary_operator(f, &arguments, 243.0000, 405.0000);
+}
+
+#[test]
+fn test_powi_minus_second_of_3() {
+    let arg = Number::new(3.0);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.powi(-2)
+    }
+    test_unary_operator(f, &arguments, 0.111111, -0.074074);
+}
+
+#[test]
+fn test_powi_zeroth_of_0() {
+    let arg = Number::new(0.0);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.powi(0)
+    }
+    test_unary_operator(f, &arguments, 1.000000, 0.000000);
+}
+
+#[test]
+fn test_tanh_of_sinh_of_3() {
+    let arg = Number::new(3.0);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.sinh().tanh()
+    }
+    test_unary_operator(f, &arguments, 1.000000, 0.000000);
+}
+
+#[test]
+fn test_powf_x_to_the_x_of_2() {
+    let arg = Number::new(2.0);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.powf(arg)
+    }
+    test_unary_operator(f, &arguments, 4.000000, 6.772589);
+}
+
+#[test]
+fn test_powf_with_constant_exponent_matches_pow() {
+    let arg = Number::new(3.0);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.powf(5.0)
+    }
+    test_unary_operator(f, &arguments, 243.0000, 405.0000);
+}
+
+#[test]
+fn test_log_base_with_constant_base_matches_log() {
+    let arg = Number::new(3.0);
+    let arguments = vec![arg];
+
+    fn f(args: &[Number]) -> Number {
+        let arg = args[0];
+        arg.log_base(8.0)
+    }
+    test_unary_operator(f, &arguments, 0.528321, 0.160299);
+}
@@ -0,0 +1,285 @@
+use aad::automatic_differentiator::AutomaticDifferentiator;
+use aad::distributions::{
+    Beta, Binomial, Exponential, Gamma, LogDensity, LogNormal, Normal, Poisson, StudentsT,
+};
+use aad::number::Number;
+use statrs::distribution::{self, Continuous, Discrete};
+
+mod common;
+
+/// Compares value and adjoints of `func` with `reference` and central finite
+/// differences at every point.
+fn test_against_finite_differences<F, R>(func: F, reference: R, points: &[Vec<f64>])
+where
+    F: Fn(&[Number]) -> Number,
+    R: Fn(&[f64]) -> f64,
+{
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    for point in points {
+        let arguments: Vec<Number> = point.iter().map(|x| Number::new(*x)).collect();
+        let evaluation = automatic_differentiator.derivatives(&func, &arguments);
+
+        let expected = reference(point);
+        assert!(
+            (evaluation.result - expected).abs() <= 1e-10 * expected.abs().max(1.0),
+            "at {:?}: value {} expected {}",
+            point,
+            evaluation.result,
+            expected
+        );
+
+        for (i, argument) in arguments.iter().enumerate() {
+            let adjoint = evaluation.derivative(*argument);
+            let difference = common::finite_difference(&func, point, i);
+            assert!(
+                (adjoint - difference).abs() <= 1e-6 * difference.abs().max(1.0),
+                "at {:?}, argument {}: adjoint {} finite difference {}",
+                point,
+                i,
+                adjoint,
+                difference
+            );
+        }
+    }
+}
+
+fn grid(axes: &[&[f64]]) -> Vec<Vec<f64>> {
+    axes.iter().fold(vec![vec![]], |points, axis| {
+        points
+            .iter()
+            .flat_map(|point| {
+                axis.iter().map(move |x| {
+                    let mut point = point.clone();
+                    point.push(*x);
+                    point
+                })
+            })
+            .collect()
+    })
+}
+
+#[test]
+fn test_normal_log_pdf() {
+    test_against_finite_differences(
+        |args: &[Number]| Normal::new(args[1], args[2]).log_pdf(args[0]),
+        |p: &[f64]| distribution::Normal::new(p[1], p[2]).unwrap().ln_pdf(p[0]),
+        &grid(&[&[-3.0, 0.2, 4.5], &[-1.0, 0.5], &[0.3, 2.0]]),
+    );
+}
+
+#[test]
+fn test_log_normal_log_pdf() {
+    test_against_finite_differences(
+        |args: &[Number]| LogNormal::new(args[1], args[2]).log_pdf(args[0]),
+        |p: &[f64]| {
+            distribution::LogNormal::new(p[1], p[2])
+                .unwrap()
+                .ln_pdf(p[0])
+        },
+        &grid(&[&[0.05, 1.0, 7.0], &[-0.5, 1.2], &[0.25, 1.5]]),
+    );
+}
+
+#[test]
+fn test_exponential_log_pdf() {
+    test_against_finite_differences(
+        |args: &[Number]| Exponential::new(args[1]).log_pdf(args[0]),
+        |p: &[f64]| distribution::Exp::new(p[1]).unwrap().ln_pdf(p[0]),
+        &grid(&[&[0.1, 2.0, 9.0], &[0.2, 1.0, 3.5]]),
+    );
+}
+
+#[test]
+fn test_gamma_log_pdf() {
+    test_against_finite_differences(
+        |args: &[Number]| Gamma::new(args[1], args[2]).log_pdf(args[0]),
+        |p: &[f64]| distribution::Gamma::new(p[1], p[2]).unwrap().ln_pdf(p[0]),
+        &grid(&[&[0.2, 1.5, 6.0], &[0.7, 2.0, 9.0], &[0.5, 3.0]]),
+    );
+}
+
+#[test]
+fn test_beta_log_pdf() {
+    test_against_finite_differences(
+        |args: &[Number]| Beta::new(args[1], args[2]).log_pdf(args[0]),
+        |p: &[f64]| distribution::Beta::new(p[1], p[2]).unwrap().ln_pdf(p[0]),
+        &grid(&[&[0.05, 0.4, 0.9], &[0.6, 2.0, 5.0], &[0.8, 3.0]]),
+    );
+}
+
+#[test]
+fn test_students_t_log_pdf() {
+    test_against_finite_differences(
+        |args: &[Number]| StudentsT::new(args[1], args[2], args[3]).log_pdf(args[0]),
+        |p: &[f64]| {
+            distribution::StudentsT::new(p[1], p[2], p[3])
+                .unwrap()
+                .ln_pdf(p[0])
+        },
+        &grid(&[
+            &[-4.0, 0.3, 2.5],
+            &[-1.0, 0.5],
+            &[0.4, 2.0],
+            &[1.0, 3.5, 40.0],
+        ]),
+    );
+}
+
+#[test]
+fn test_poisson_log_pmf() {
+    for k in [0_u64, 1, 4, 25] {
+        test_against_finite_differences(
+            |args: &[Number]| Poisson::new(args[0]).log_pdf(Number::from(k as f64)),
+            |p: &[f64]| distribution::Poisson::new(p[0]).unwrap().ln_pmf(k),
+            &grid(&[&[0.3, 2.0, 20.0]]),
+        );
+    }
+}
+
+#[test]
+fn test_binomial_log_pmf() {
+    for k in [0_u64, 3, 10] {
+        test_against_finite_differences(
+            |args: &[Number]| Binomial::new(10, args[0]).log_pdf(Number::from(k as f64)),
+            |p: &[f64]| distribution::Binomial::new(p[0], 10).unwrap().ln_pmf(k),
+            &grid(&[&[0.05, 0.5, 0.9]]),
+        );
+    }
+}
+
+#[test]
+fn test_counts_have_no_derivative() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let k = Number::new(3.0);
+    let rate = Number::new(2.0);
+    let evaluation = automatic_differentiator.derivatives(
+        |args: &[Number]| Poisson::new(args[1]).log_pdf(args[0]),
+        &[k, rate],
+    );
+    assert_eq!(evaluation.derivative(k), 0.0);
+    assert!((evaluation.derivative(rate) - 0.5).abs() < 1e-12);
+}
+
+#[test]
+fn test_outside_support() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let x = Number::new(-1.0);
+    let shape = Number::new(2.0);
+    let rate = Number::new(1.0);
+    let evaluation = automatic_differentiator.derivatives(
+        |args: &[Number]| Gamma::new(args[1], args[2]).log_pdf(args[0]),
+        &[x, shape, rate],
+    );
+    assert_eq!(evaluation.result, f64::NEG_INFINITY);
+    for input in [x, shape, rate] {
+        assert_eq!(evaluation.derivative(input), 0.0);
+    }
+
+    let p = Number::new(0.4);
+    let evaluation = automatic_differentiator.derivatives(
+        |args: &[Number]| Binomial::new(5, args[0]).log_pdf(Number::from(2.5)),
+        &[p],
+    );
+    assert_eq!(evaluation.result, f64::NEG_INFINITY);
+    assert_eq!(evaluation.derivative(p), 0.0);
+}
+
+#[test]
+fn test_shared_argument() {
+    // x ~ Normal(mu = x, sigma) has log density -ln(sigma) - ln(sqrt(2 pi)), independent of x
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let x = Number::new(1.7);
+    let sigma = Number::new(0.5);
+    let evaluation = automatic_differentiator.derivatives(
+        |args: &[Number]| Normal::new(args[0], args[1]).log_pdf(args[0]),
+        &[x, sigma],
+    );
+    assert!(evaluation.derivative(x).abs() < 1e-12);
+    assert!((evaluation.derivative(sigma) + 2.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_log_likelihood_is_sum_of_log_pdfs() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let data = [0.3, 1.2, 2.2, 0.9, 4.1, 1.6];
+    let shape = Number::new(1.8);
+    let rate = Number::new(0.9);
+
+    let likelihood = automatic_differentiator.derivatives(
+        |args: &[Number]| Gamma::new(args[0], args[1]).log_likelihood(&data),
+        &[shape, rate],
+    );
+    let sum = automatic_differentiator.derivatives(
+        |args: &[Number]| {
+            let model = Gamma::new(args[0], args[1]);
+            data.iter()
+                .map(|x| model.log_pdf(Number::from(*x)))
+                .fold(Number::from(0.0), |total, log_pdf| total + log_pdf)
+        },
+        &[shape, rate],
+    );
+
+    let epsilon = 1e-12;
+    assert!((likelihood.result - sum.result).abs() < epsilon);
+    for input in [shape, rate] {
+        assert!((likelihood.derivative(input) - sum.derivative(input)).abs() < epsilon);
+    }
+}
+
+#[test]
+fn test_log_likelihood_with_constant_parameter() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let data = [2.0, 5.0, 1.0, 0.0, 3.0];
+    let rate = Number::new(2.5);
+    let evaluation = automatic_differentiator.derivatives(
+        |args: &[Number]| Poisson::new(args[0]).log_likelihood(&data),
+        &[rate],
+    );
+    assert_eq!(evaluation.derivatives.len(), 1);
+    assert!((evaluation.derivative(rate) - (11.0 / 2.5 - 5.0)).abs() < 1e-12);
+
+    let alpha = Number::new(2.0);
+    let evaluation = automatic_differentiator.derivatives(
+        |args: &[Number]| Beta::new(args[0], 3.0).log_likelihood(&[0.2, 0.5, 0.7]),
+        &[alpha],
+    );
+    assert_eq!(evaluation.derivatives.len(), 1);
+}
+
+#[test]
+fn test_normal_log_likelihood_of_100k_observations() {
+    let (mu0, sigma0) = (1.5, 0.8);
+    let n = 100_000;
+    // deterministic sample on the quantile grid of N(mu0, sigma0^2)
+    let data: Vec<f64> = aad::no_tape(|| {
+        (0..n)
+            .map(|i| {
+                let p = (i as f64 + 0.5) / n as f64;
+                mu0 + sigma0 * Number::new(p).norm_inv_cdf().result
+            })
+            .collect()
+    });
+
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+    let mu = Number::new(1.4);
+    let sigma = Number::new(0.9);
+    let evaluation = automatic_differentiator.derivatives(
+        |args: &[Number]| Normal::new(args[0], args[1]).log_likelihood(&data),
+        &[mu, sigma],
+    );
+
+    let (m, s) = (mu.result, sigma.result);
+    let sum_of_deviations: f64 = data.iter().map(|x| x - m).sum();
+    let sum_of_squares: f64 = data.iter().map(|x| (x - m) * (x - m)).sum();
+    let expected_dmu = sum_of_deviations / (s * s);
+    let expected_dsigma = sum_of_squares / (s * s * s) - n as f64 / s;
+
+    assert!((evaluation.derivative(mu) - expected_dmu).abs() < 1e-8 * n as f64);
+    assert!((evaluation.derivative(sigma) - expected_dsigma).abs() < 1e-8 * n as f64);
+}
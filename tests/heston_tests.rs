@@ -0,0 +1,194 @@
+use aad::automatic_differentiator::AutomaticDifferentiator;
+use aad::finance::black_scholes::{self, OptionType};
+use aad::finance::heston::{self, Heston, HestonParameters, Quote};
+use aad::number::Number;
+
+const SPOT: f64 = 100.0;
+const RATE: f64 = 0.03;
+const DIVIDEND_YIELD: f64 = 0.01;
+
+const PARAMETERS: HestonParameters = HestonParameters {
+    v0: 0.04,
+    kappa: 1.5,
+    theta: 0.06,
+    xi: 0.5,
+    rho: -0.7,
+};
+
+fn model(parameters: &HestonParameters) -> Heston {
+    Heston::new(
+        parameters.v0,
+        parameters.kappa,
+        parameters.theta,
+        parameters.xi,
+        parameters.rho,
+    )
+}
+
+fn untaped_price(
+    parameters: &HestonParameters,
+    option_type: OptionType,
+    strike: f64,
+    maturity: f64,
+) -> f64 {
+    aad::no_tape(|| {
+        model(parameters)
+            .price(
+                option_type,
+                Number::new(SPOT),
+                strike,
+                maturity,
+                Number::new(RATE),
+                Number::new(DIVIDEND_YIELD),
+            )
+            .result
+    })
+}
+
+fn assert_close(name: &str, value: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (value - expected).abs() < tolerance,
+        "{}: {} != {}",
+        name,
+        value,
+        expected
+    );
+}
+
+#[test]
+fn test_call_prices_against_reference() {
+    // reference values from adaptive high-precision quadrature of the
+    // Heston (1993) probabilities P1 and P2
+    for (strike, maturity, expected) in [
+        (100.0, 1.0, 9.045525715741),
+        (80.0, 0.25, 20.577344034995),
+        (120.0, 2.0, 5.186282978701),
+        (100.0, 0.1, 2.610881646102),
+    ] {
+        let price = untaped_price(&PARAMETERS, OptionType::Call, strike, maturity);
+        assert_close("call", price, expected, 1e-5);
+    }
+}
+
+#[test]
+fn test_put_call_parity() {
+    for (strike, maturity) in [(90.0, 0.5), (110.0, 1.5)] {
+        let call = untaped_price(&PARAMETERS, OptionType::Call, strike, maturity);
+        let put = untaped_price(&PARAMETERS, OptionType::Put, strike, maturity);
+        let forward = SPOT * (-DIVIDEND_YIELD * maturity).exp() - strike * (-RATE * maturity).exp();
+        assert_close("parity", call - put, forward, 1e-12);
+    }
+}
+
+#[test]
+fn test_constant_variance_is_black_scholes() {
+    // with v0 = theta and a vanishing vol of variance the variance stays at v0
+    let parameters = HestonParameters {
+        v0: 0.09,
+        kappa: 2.0,
+        theta: 0.09,
+        xi: 1e-4,
+        rho: 0.0,
+    };
+    for (strike, maturity) in [(85.0, 0.5), (100.0, 1.0), (125.0, 3.0)] {
+        let heston = untaped_price(&parameters, OptionType::Put, strike, maturity);
+        let black_scholes = aad::no_tape(|| {
+            black_scholes::put_price(
+                Number::new(SPOT),
+                Number::new(strike),
+                Number::new(maturity),
+                Number::new(RATE),
+                Number::new(DIVIDEND_YIELD),
+                Number::new(0.3),
+            )
+            .result
+        });
+        assert_close("put", heston, black_scholes, 1e-6);
+    }
+}
+
+#[test]
+fn test_sensitivities_against_finite_differences() {
+    // arguments: v0, kappa, theta, xi, rho, spot, rate, dividend yield
+    let price = |args: &[Number]| {
+        Heston::new(args[0], args[1], args[2], args[3], args[4]).price(
+            OptionType::Put,
+            args[5],
+            95.0,
+            0.75,
+            args[6],
+            args[7],
+        )
+    };
+    let point = [0.04, 1.5, 0.06, 0.5, -0.7, SPOT, RATE, DIVIDEND_YIELD];
+
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+    let arguments: Vec<Number> = point.iter().map(|x| Number::new(*x)).collect();
+    let evaluation = automatic_differentiator.derivatives(price, &arguments);
+
+    for (i, argument) in arguments.iter().enumerate() {
+        let h = 1e-5 * point[i].abs().max(1e-2);
+        let evaluate = |shift: f64| {
+            aad::no_tape(|| {
+                let args: Vec<Number> = point
+                    .iter()
+                    .enumerate()
+                    .map(|(j, x)| Number::new(if i == j { x + shift } else { *x }))
+                    .collect();
+                price(&args).result
+            })
+        };
+        let difference = (evaluate(h) - evaluate(-h)) / (2.0 * h);
+        let adjoint = evaluation.derivative(*argument);
+        assert!(
+            (adjoint - difference).abs() <= 1e-5 * difference.abs().max(1.0),
+            "argument {}: adjoint {} finite difference {}",
+            i,
+            adjoint,
+            difference
+        );
+    }
+}
+
+#[test]
+fn test_calibration_recovers_parameters() {
+    let quotes: Vec<Quote> = [0.25, 1.0, 2.0]
+        .iter()
+        .flat_map(|maturity| {
+            [80.0, 90.0, 100.0, 110.0, 120.0].map(|strike| {
+                let option_type = if strike < SPOT {
+                    OptionType::Put
+                } else {
+                    OptionType::Call
+                };
+                Quote {
+                    option_type,
+                    strike,
+                    maturity: *maturity,
+                    price: untaped_price(&PARAMETERS, option_type, strike, *maturity),
+                }
+            })
+        })
+        .collect();
+
+    let initial_guess = HestonParameters {
+        v0: 0.09,
+        kappa: 1.0,
+        theta: 0.09,
+        xi: 0.3,
+        rho: -0.3,
+    };
+    let calibration = heston::calibrate(SPOT, RATE, DIVIDEND_YIELD, &quotes, initial_guess);
+
+    assert!(calibration.root_mean_square_error < 1e-8);
+    let recovered = calibration.parameters;
+    for (name, value, expected) in [
+        ("v0", recovered.v0, PARAMETERS.v0),
+        ("kappa", recovered.kappa, PARAMETERS.kappa),
+        ("theta", recovered.theta, PARAMETERS.theta),
+        ("xi", recovered.xi, PARAMETERS.xi),
+        ("rho", recovered.rho, PARAMETERS.rho),
+    ] {
+        assert_close(name, value, expected, 1e-6 * expected.abs());
+    }
+}
@@ -0,0 +1,232 @@
+use aad::automatic_differentiator::AutomaticDifferentiator;
+use aad::finance::black_scholes::{self, OptionType};
+use aad::number::Number;
+use statrs::distribution::{Continuous, Normal};
+
+fn option_type(is_call: bool) -> OptionType {
+    if is_call {
+        OptionType::Call
+    } else {
+        OptionType::Put
+    }
+}
+
+/// Untaped Black-Scholes price without dividends.
+fn price(is_call: bool, spot: f64, strike: f64, maturity: f64, rate: f64, volatility: f64) -> f64 {
+    aad::no_tape(|| {
+        black_scholes::price(
+            option_type(is_call),
+            Number::new(spot),
+            Number::new(strike),
+            Number::new(maturity),
+            Number::new(rate),
+            Number::new(0.0),
+            Number::new(volatility),
+        )
+        .result
+    })
+}
+
+/// arguments: price, spot, strike, maturity, rate
+fn implied_vol(args: &[Number], is_call: bool) -> Number {
+    black_scholes::implied_vol(args[0], args[1], args[2], args[3], args[4], is_call)
+}
+
+fn untaped_implied_vol(point: &[f64], is_call: bool) -> f64 {
+    aad::no_tape(|| {
+        let args: Vec<Number> = point.iter().map(|x| Number::new(*x)).collect();
+        implied_vol(&args, is_call).result
+    })
+}
+
+#[test]
+fn test_round_trip() {
+    for is_call in [true, false] {
+        for (spot, strike, maturity, rate, volatility) in [
+            (100.0, 100.0, 1.0, 0.05, 0.2),
+            (100.0, 60.0, 0.5, 0.02, 0.35),
+            (100.0, 150.0, 2.0, 0.03, 0.25),
+            (50.0, 52.0, 0.02, 0.0, 0.8),
+            (100.0, 95.0, 10.0, 0.04, 0.05),
+            (100.0, 100.0, 1.0, -0.01, 1.5),
+        ] {
+            let quote = price(is_call, spot, strike, maturity, rate, volatility);
+            let implied = untaped_implied_vol(&[quote, spot, strike, maturity, rate], is_call);
+            assert!(
+                (implied - volatility).abs() < 1e-10,
+                "call {} {:?}: {} != {}",
+                is_call,
+                (spot, strike, maturity, rate),
+                implied,
+                volatility
+            );
+        }
+    }
+}
+
+#[test]
+fn test_prices_outside_the_bounds() {
+    // a call is worth between S - K e^(-rT) and S
+    let discounted_strike = 90.0 * (-0.05_f64).exp();
+    for quote in [100.0 - discounted_strike - 0.01, 100.0, 120.0, -1.0] {
+        assert!(untaped_implied_vol(&[quote, 100.0, 90.0, 1.0, 0.05], true).is_nan());
+    }
+    assert!(untaped_implied_vol(&[0.0, 100.0, 110.0, 1.0, 0.05], false).is_nan());
+}
+
+#[test]
+fn test_single_node_with_implicit_partials() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let (spot, strike, maturity, rate, volatility) = (100.0, 110.0, 0.75, 0.03, 0.3);
+    for is_call in [true, false] {
+        let quote = price(is_call, spot, strike, maturity, rate, volatility);
+        let point = [quote, spot, strike, maturity, rate];
+        let arguments: Vec<Number> = point.iter().map(|x| Number::new(*x)).collect();
+        let evaluation = automatic_differentiator
+            .derivatives(|args: &[Number]| implied_vol(args, is_call), &arguments);
+
+        // dsigma/dprice = 1 / vega
+        let d1 = ((spot / strike).ln() + (rate + 0.5 * volatility * volatility) * maturity)
+            / (volatility * f64::sqrt(maturity));
+        let vega = spot * Normal::standard().pdf(d1) * f64::sqrt(maturity);
+        assert!((evaluation.derivative(arguments[0]) - 1.0 / vega).abs() < 1e-12);
+
+        for (i, argument) in arguments.iter().enumerate() {
+            let h = 1e-6 * point[i].abs();
+            let (mut up, mut down) = (point, point);
+            up[i] += h;
+            down[i] -= h;
+            let difference = (untaped_implied_vol(&up, is_call)
+                - untaped_implied_vol(&down, is_call))
+                / (2.0 * h);
+            let adjoint = evaluation.derivative(*argument);
+            assert!(
+                (adjoint - difference).abs() <= 1e-6 * difference.abs().max(1e-3),
+                "call {}, argument {}: adjoint {} finite difference {}",
+                is_call,
+                i,
+                adjoint,
+                difference
+            );
+        }
+    }
+}
+
+#[test]
+fn test_implied_vol_inverts_the_price() {
+    // sigma -> price -> implied vol is the identity: d/dsigma = 1 and the
+    // other inputs cancel out
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let arguments: Vec<Number> = [100.0, 120.0, 1.5, 0.04, 0.22]
+        .iter()
+        .map(|x| Number::new(*x))
+        .collect();
+    let evaluation = automatic_differentiator.derivatives(
+        |args: &[Number]| {
+            let (spot, strike, maturity, rate, volatility) =
+                (args[0], args[1], args[2], args[3], args[4]);
+            let premium = black_scholes::put_price(
+                spot,
+                strike,
+                maturity,
+                rate,
+                Number::from(0.0),
+                volatility,
+            );
+            black_scholes::implied_vol(premium, spot, strike, maturity, rate, false)
+        },
+        &arguments,
+    );
+
+    assert!((evaluation.result - 0.22).abs() < 1e-12);
+    assert!((evaluation.derivative(arguments[4]) - 1.0).abs() < 1e-9);
+    for argument in &arguments[..4] {
+        assert!(evaluation.derivative(*argument).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn test_downstream_model_of_the_implied_vol() {
+    // pricing a second strike at the implied vol of a first: the sensitivity
+    // to the quoted premium is the ratio of the vegas
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let (spot, maturity, rate, volatility) = (100.0, 1.0, 0.02, 0.25);
+    let quote = price(true, spot, 100.0, maturity, rate, volatility);
+    let premium = Number::new(quote);
+    let evaluation = automatic_differentiator.derivatives(
+        |args: &[Number]| {
+            let (spot, maturity, rate) = (
+                Number::from(spot),
+                Number::from(maturity),
+                Number::from(rate),
+            );
+            let sigma = black_scholes::implied_vol(
+                args[0],
+                spot,
+                Number::from(100.0),
+                maturity,
+                rate,
+                true,
+            );
+            black_scholes::call_price(
+                spot,
+                Number::from(115.0),
+                maturity,
+                rate,
+                Number::from(0.0),
+                sigma,
+            )
+        },
+        &[premium],
+    );
+
+    let vega = |strike: f64| {
+        let d1 = ((spot / strike).ln() + (rate + 0.5 * volatility * volatility) * maturity)
+            / (volatility * f64::sqrt(maturity));
+        spot * Normal::standard().pdf(d1) * f64::sqrt(maturity)
+    };
+    assert!(
+        (evaluation.result - price(true, spot, 115.0, maturity, rate, volatility)).abs() < 1e-10
+    );
+    assert!((evaluation.derivative(premium) - vega(115.0) / vega(100.0)).abs() < 1e-10);
+}
+
+#[test]
+fn test_replay_solves_again() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+    let mut reference = AutomaticDifferentiator::new();
+
+    let point = [
+        price(false, 100.0, 90.0, 0.5, 0.01, 0.3),
+        100.0,
+        90.0,
+        0.5,
+        0.01,
+    ];
+    let arguments: Vec<Number> = point.iter().map(|x| Number::new(*x)).collect();
+    automatic_differentiator.derivatives(|args: &[Number]| implied_vol(args, false), &arguments);
+
+    let new_point = [
+        price(false, 97.0, 92.0, 0.6, 0.02, 0.45),
+        97.0,
+        92.0,
+        0.6,
+        0.02,
+    ];
+    let replay = automatic_differentiator.replay(&new_point);
+    let arguments: Vec<Number> = new_point.iter().map(|x| Number::new(*x)).collect();
+    let evaluation = reference.derivatives(|args: &[Number]| implied_vol(args, false), &arguments);
+
+    assert!((replay.evaluation.result - 0.45).abs() < 1e-12);
+    for (replayed, recorded) in replay
+        .evaluation
+        .derivatives
+        .iter()
+        .zip(&evaluation.derivatives)
+    {
+        assert!((replayed.derivative - recorded.derivative).abs() < 1e-12);
+    }
+}
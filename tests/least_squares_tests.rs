@@ -0,0 +1,161 @@
+use aad::automatic_differentiator::AutomaticDifferentiator;
+use aad::least_squares::{LevenbergMarquardt, Termination};
+use aad::number::Number;
+
+/// Observation times and a deterministic stand-in for measurement noise.
+fn observations() -> Vec<(f64, f64)> {
+    (0..20)
+        .map(|i| {
+            let t = 0.25 * i as f64;
+            (t, 0.01 * (2.7 * i as f64).sin())
+        })
+        .collect()
+}
+
+/// Residuals of a * exp(-b t) + c against data generated with
+/// a = 2, b = 0.7, c = 0.5 plus noise.
+fn decay_residuals(p: &[Number]) -> Vec<Number> {
+    observations()
+        .iter()
+        .map(|(t, noise)| {
+            let observed = 2.0 * (-0.7 * t).exp() + 0.5 + noise;
+            p[0] * (-1.0 * p[1] * *t).exp() + p[2] - observed
+        })
+        .collect()
+}
+
+fn rosenbrock_residuals(p: &[Number]) -> Vec<Number> {
+    vec![10.0 * (p[1] - p[0] * p[0]), 1.0 - p[0]]
+}
+
+#[test]
+fn test_jacobian_rows_match_single_sweeps() {
+    let func = |x: &[Number]| {
+        let shared = x[0] * x[1];
+        vec![
+            shared.sin() + x[2],
+            x[1],
+            shared * x[2].exp(),
+            Number::from(3.0),
+            shared.sin() + x[2],
+        ]
+    };
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+    let arguments = [0.3, -1.2, 0.8].map(Number::new);
+    let rows = automatic_differentiator.jacobian(func, &arguments);
+    assert_eq!(rows.len(), 5);
+
+    for (i, row) in rows.iter().enumerate() {
+        let single =
+            AutomaticDifferentiator::new().derivatives(|x: &[Number]| func(x)[i], &arguments);
+        assert_eq!(row.result, single.result);
+        for argument in &arguments {
+            assert!(
+                (row.derivative(*argument) - single.derivative(*argument)).abs() < 1e-15,
+                "output {}",
+                i
+            );
+        }
+    }
+    assert_eq!(rows[1].derivative(arguments[1]), 1.0);
+    assert_eq!(rows[1].derivative(arguments[0]), 0.0);
+    assert!(arguments.iter().all(|a| rows[3].derivative(*a) == 0.0));
+}
+
+#[test]
+fn test_fit_of_an_exponential_decay() {
+    let fit = LevenbergMarquardt::new().minimize(decay_residuals, &[1.0, 0.3, 0.0]);
+    assert_eq!(fit.iterations, fit.history.len());
+    assert!(
+        matches!(
+            fit.termination,
+            Termination::GradientTolerance | Termination::NoImprovement
+        ),
+        "{:?}",
+        fit.termination
+    );
+    for (fitted, truth) in fit.x.iter().zip([2.0, 0.7, 0.5]) {
+        assert!((fitted - truth).abs() < 0.02, "{:?}", fit.x);
+    }
+    // the fitted curve is within the noise of the data
+    assert!(fit.residuals.iter().all(|r| r.abs() < 0.015));
+    let sum_of_squares: f64 = fit.residuals.iter().map(|r| r * r).sum();
+    assert!((fit.sum_of_squares - sum_of_squares).abs() < 1e-15);
+    assert!((fit.residual_variance - fit.sum_of_squares / 17.0).abs() < 1e-18);
+
+    // the sum of squares falls with every iteration
+    let costs: Vec<f64> = fit.history.iter().map(|i| i.sum_of_squares).collect();
+    assert!(costs.windows(2).all(|pair| pair[1] < pair[0]));
+    assert_eq!(*costs.last().unwrap(), fit.sum_of_squares);
+
+    // the covariance times J^T J is s^2 times the identity
+    for i in 0..3 {
+        for j in 0..3 {
+            let product: f64 = (0..3)
+                .map(|k| {
+                    let normal: f64 = fit.jacobian.iter().map(|row| row[k] * row[j]).sum();
+                    fit.covariance[i][k] * normal
+                })
+                .sum();
+            let expected = if i == j { fit.residual_variance } else { 0.0 };
+            assert!((product - expected).abs() < 1e-12, "{} {}", i, j);
+        }
+        assert!((fit.covariance[i][i].sqrt() - fit.standard_errors()[i]).abs() < 1e-18);
+    }
+    // the amplitude and the rate are fitted together
+    assert!(fit.covariance[0][1] * fit.covariance[1][2] != 0.0);
+}
+
+#[test]
+fn test_covariance_of_a_straight_line() {
+    // ordinary least squares in closed form
+    let data = observations();
+    let residuals = |p: &[Number]| -> Vec<Number> {
+        data.iter()
+            .map(|(t, y)| p[0] + p[1] * *t - (1.0 + 2.0 * t + 10.0 * y))
+            .collect()
+    };
+    let fit = LevenbergMarquardt::gauss_newton().minimize(residuals, &[0.0, 0.0]);
+
+    let n = data.len() as f64;
+    let mean = data.iter().map(|(t, _)| t).sum::<f64>() / n;
+    let sxx: f64 = data.iter().map(|(t, _)| (t - mean).powi(2)).sum();
+    let variance = fit.sum_of_squares / (n - 2.0);
+    assert!((fit.covariance[1][1] - variance / sxx).abs() < 1e-12);
+    assert!((fit.covariance[0][0] - variance * (1.0 / n + mean * mean / sxx)).abs() < 1e-12);
+    assert!((fit.covariance[0][1] + variance * mean / sxx).abs() < 1e-12);
+    // one undamped step solves a linear problem
+    assert_eq!(fit.history[0].damping, 0.0);
+    assert!(fit.iterations <= 2, "{} iterations", fit.iterations);
+}
+
+#[test]
+fn test_rosenbrock_with_and_without_damping() {
+    for solver in [
+        LevenbergMarquardt::new(),
+        LevenbergMarquardt::gauss_newton(),
+    ] {
+        let fit = solver.minimize(rosenbrock_residuals, &[-1.2, 1.0]);
+        assert_eq!(fit.termination, Termination::ResidualTolerance);
+        assert!((fit.x[0] - 1.0).abs() < 1e-10 && (fit.x[1] - 1.0).abs() < 1e-10);
+        assert!(fit.iterations < 30, "{} iterations", fit.iterations);
+        // as many residuals as parameters leave no degrees of freedom
+        assert!(fit.residual_variance.is_nan());
+    }
+}
+
+#[test]
+fn test_max_iterations() {
+    let solver = LevenbergMarquardt {
+        max_iterations: 2,
+        ..LevenbergMarquardt::new()
+    };
+    let fit = solver.minimize(decay_residuals, &[1.0, 0.3, 0.0]);
+    assert_eq!(fit.termination, Termination::MaxIterations);
+    assert_eq!(fit.iterations, 2);
+    assert!(
+        fit.history
+            .iter()
+            .all(|i| i.step_norm > 0.0 && i.gradient_norm > 0.0)
+    );
+}
@@ -0,0 +1,122 @@
+use aad::finance::black_scholes::{EuropeanOption, OptionType};
+use aad::finance::longstaff_schwartz::LongstaffSchwartz;
+
+/// spot, rate, dividend yield, volatility
+const INPUTS: [f64; 4] = [36.0, 0.06, 0.0, 0.2];
+
+fn dates(count: usize, maturity: f64) -> Vec<f64> {
+    (1..=count)
+        .map(|i| maturity * i as f64 / count as f64)
+        .collect()
+}
+
+fn european(option_type: OptionType, strike: f64, maturity: f64) -> EuropeanOption {
+    EuropeanOption {
+        option_type,
+        spot: INPUTS[0],
+        strike,
+        maturity,
+        rate: INPUTS[1],
+        dividend_yield: INPUTS[2],
+        volatility: INPUTS[3],
+    }
+}
+
+#[test]
+fn test_bermudan_put_between_european_and_american() {
+    // Longstaff and Schwartz (2001), table 1: the American put is worth 4.478
+    let engine = LongstaffSchwartz::new(OptionType::Put, 40.0, dates(10, 1.0), 4000, 17);
+    let result = engine.run(&INPUTS);
+    let european = european(OptionType::Put, 40.0, 1.0).price();
+
+    assert!(
+        result.price > european + 3.0 * result.standard_error,
+        "bermudan {} european {}",
+        result.price,
+        european
+    );
+    assert!(
+        result.price < 4.478 + 3.0 * result.standard_error,
+        "bermudan {} american 4.478",
+        result.price
+    );
+    assert!(result.price > 4.3, "bermudan {}", result.price);
+}
+
+#[test]
+fn test_call_without_dividends_is_european() {
+    // never worth exercising early
+    let engine = LongstaffSchwartz::new(OptionType::Call, 40.0, dates(5, 1.0), 2000, 3);
+    let result = engine.run(&INPUTS);
+    let european = european(OptionType::Call, 40.0, 1.0).greeks();
+
+    assert!(
+        (result.price - european.price).abs() < 3.0 * result.standard_error,
+        "bermudan {} european {} standard error {}",
+        result.price,
+        european.price,
+        result.standard_error
+    );
+    assert!((result.sensitivities[0] - european.delta).abs() < 0.03);
+    assert!((result.sensitivities[3] - european.vega).abs() < 0.1 * european.vega);
+}
+
+#[test]
+fn test_greeks_against_bump_and_revalue() {
+    // with the regression frozen the price is a smooth function of the inputs
+    // on each path between exercise decisions, which small bumps do not flip
+    let engine = LongstaffSchwartz::new(OptionType::Put, 40.0, dates(10, 1.0), 1000, 5);
+    let result = engine.run(&INPUTS);
+    let regression = engine.regress(&INPUTS);
+    assert!((result.price - engine.price(&INPUTS, &regression)).abs() < 1e-12);
+
+    for (i, sensitivity) in result.sensitivities.iter().enumerate() {
+        let bump = 1e-7;
+        let mut up = INPUTS;
+        up[i] += bump;
+        let mut down = INPUTS;
+        down[i] -= bump;
+        let difference =
+            (engine.price(&up, &regression) - engine.price(&down, &regression)) / (2.0 * bump);
+        assert!(
+            (sensitivity - difference).abs() <= 1e-5 * difference.abs().max(1.0),
+            "input {}: adjoint {} bump and revalue {}",
+            i,
+            sensitivity,
+            difference
+        );
+    }
+
+    // a put loses value with the spot and gains with the volatility
+    assert!(result.sensitivities[0] < 0.0 && result.sensitivities[0] > -1.0);
+    assert!(result.sensitivities[3] > 0.0);
+}
+
+#[test]
+fn test_regression_is_reproducible() {
+    let engine = LongstaffSchwartz::new(OptionType::Put, 40.0, dates(4, 1.0), 500, 9);
+    let regression = engine.regress(&INPUTS);
+    assert_eq!(regression, engine.regress(&INPUTS));
+    assert_eq!(regression.coefficients.len(), 3);
+    assert!(regression.coefficients.iter().all(|beta| beta.len() == 3));
+    assert_eq!(engine.run(&INPUTS), engine.run(&INPUTS));
+
+    let other = LongstaffSchwartz { seed: 10, ..engine };
+    assert_ne!(regression, other.regress(&INPUTS));
+}
+
+#[test]
+fn test_single_exercise_date_is_european() {
+    let engine = LongstaffSchwartz::new(OptionType::Put, 40.0, vec![1.0], 2000, 23);
+    let result = engine.run(&INPUTS);
+    assert!(engine.regress(&INPUTS).coefficients.is_empty());
+
+    let european = european(OptionType::Put, 40.0, 1.0).price();
+    assert!((result.price - european).abs() < 3.0 * result.standard_error);
+}
+
+#[test]
+#[should_panic(expected = "strictly increasing")]
+fn test_exercise_dates_must_increase() {
+    LongstaffSchwartz::new(OptionType::Put, 40.0, vec![0.5, 0.5, 1.0], 10, 1);
+}
@@ -0,0 +1,356 @@
+use aad::finance::black_scholes::{self, EuropeanOption, OptionType};
+use aad::finance::monte_carlo::{Gbm, GreeksEstimator, MonteCarlo, Payoff, Sde};
+use aad::number::{Condition, Number};
+use aad::random::NormalMethod;
+
+mod common;
+
+fn discounted_call(strike: f64, maturity: f64) -> impl Fn(&[Number], &[Number]) -> Number {
+    move |path: &[Number], inputs: &[Number]| {
+        let discount = (-1.0 * inputs[Gbm::RATE] * maturity).exp();
+        discount * (path[path.len() - 1] - strike).max(0.0)
+    }
+}
+
+#[test]
+fn test_gbm_call_against_black_scholes() {
+    let option = EuropeanOption {
+        option_type: OptionType::Call,
+        spot: 100.0,
+        strike: 105.0,
+        maturity: 1.0,
+        rate: 0.03,
+        dividend_yield: 0.01,
+        volatility: 0.25,
+    };
+    let greeks = option.greeks();
+
+    let engine = MonteCarlo::new(20_000, vec![option.maturity], 11);
+    let inputs = [
+        option.spot,
+        option.rate,
+        option.dividend_yield,
+        option.volatility,
+    ];
+    let result = engine.run(
+        &Gbm,
+        &inputs,
+        discounted_call(option.strike, option.maturity),
+    );
+
+    assert!(
+        (result.price - greeks.price).abs() < 3.0 * result.standard_error,
+        "price {} +- {} vs {}",
+        result.price,
+        result.standard_error,
+        greeks.price
+    );
+    let delta = result.sensitivities[Gbm::SPOT];
+    let vega = result.sensitivities[Gbm::VOLATILITY];
+    let rho = result.sensitivities[Gbm::RATE];
+    assert!(
+        (delta - greeks.delta).abs() < 0.01,
+        "{} vs {}",
+        delta,
+        greeks.delta
+    );
+    assert!(
+        (vega - greeks.vega).abs() < 1.0,
+        "{} vs {}",
+        vega,
+        greeks.vega
+    );
+    assert!((rho - greeks.rho).abs() < 1.0, "{} vs {}", rho, greeks.rho);
+}
+
+#[test]
+fn test_results_are_reproducible() {
+    let inputs = [100.0, 0.02, 0.0, 0.3];
+    let payoff = discounted_call(100.0, 0.5);
+
+    let engine = MonteCarlo::new(500, vec![0.25, 0.5], 3);
+    let first = engine.run(&Gbm, &inputs, &payoff);
+    let second = engine.run(&Gbm, &inputs, &payoff);
+    assert_eq!(first, second);
+
+    let other_seed = MonteCarlo::new(500, vec![0.25, 0.5], 4).run(&Gbm, &inputs, &payoff);
+    assert_ne!(first.price, other_seed.price);
+}
+
+#[test]
+fn test_inverse_cdf_normals() {
+    let inputs = [100.0, 0.02, 0.0, 0.3];
+    let mut engine = MonteCarlo::new(5_000, vec![0.5], 9);
+    engine.normal_method = NormalMethod::InverseCdf;
+    let result = engine.run(&Gbm, &inputs, discounted_call(100.0, 0.5));
+
+    let option = EuropeanOption {
+        option_type: OptionType::Call,
+        spot: 100.0,
+        strike: 100.0,
+        maturity: 0.5,
+        rate: 0.02,
+        dividend_yield: 0.0,
+        volatility: 0.3,
+    };
+    assert!((result.price - option.price()).abs() < 3.0 * result.standard_error);
+}
+
+/// Ornstein-Uhlenbeck process `dX = kappa (theta - X) dt + sigma dW`, inputs
+/// `[x0, kappa, theta, sigma]`, with the default Euler scheme.
+struct OrnsteinUhlenbeck;
+
+impl Sde for OrnsteinUhlenbeck {
+    fn initial_value(&self, inputs: &[Number]) -> Number {
+        inputs[0]
+    }
+
+    fn drift(&self, _t: f64, x: Number, inputs: &[Number]) -> Number {
+        inputs[1] * (inputs[2] - x)
+    }
+
+    fn diffusion(&self, _t: f64, _x: Number, inputs: &[Number]) -> Number {
+        inputs[3]
+    }
+}
+
+#[test]
+fn test_user_sde_with_euler_scheme() {
+    // X_n = theta + (x0 - theta) (1 - kappa dt)^n + noise, so the pathwise
+    // derivatives of X_n with respect to x0 and theta do not depend on the noise
+    let steps = 20;
+    let dt = 0.05;
+    let time_grid: Vec<f64> = (1..=steps).map(|i| i as f64 * dt).collect();
+    let engine = MonteCarlo::new(200, time_grid, 1);
+
+    let (x0, kappa, theta, sigma) = (0.5, 1.5, 2.0, 0.3);
+    let result = engine.run(
+        &OrnsteinUhlenbeck,
+        &[x0, kappa, theta, sigma],
+        |path: &[Number], _inputs: &[Number]| path[path.len() - 1],
+    );
+
+    let decay = (1.0 - kappa * dt).powi(steps);
+    assert!((result.sensitivities[0] - decay).abs() < 1e-12);
+    assert!((result.sensitivities[2] - (1.0 - decay)).abs() < 1e-12);
+    assert!((result.price - (theta + (x0 - theta) * decay)).abs() < 3.0 * result.standard_error);
+}
+
+#[test]
+fn test_simulate_starts_at_the_initial_value() {
+    let engine = MonteCarlo::new(1, vec![0.5, 1.0], 0);
+    let path = aad::no_tape(|| {
+        let inputs: Vec<Number> = [100.0, 0.05, 0.0, 0.2]
+            .iter()
+            .map(|x| Number::new(*x))
+            .collect();
+        engine.simulate(&Gbm, &inputs, &[0.0, 0.0])
+    });
+    assert_eq!(path.len(), 3);
+    assert_eq!(path[0].result, 100.0);
+    // zero noise: S_t = S_0 exp((r - sigma^2 / 2) t)
+    assert!((path[2].result - 100.0 * (0.05_f64 - 0.02).exp()).abs() < 1e-12);
+}
+
+#[test]
+fn test_standard_error_needs_two_paths() {
+    let inputs = [100.0, 0.05, 0.0, 0.2];
+    let payoff = discounted_call(100.0, 1.0);
+
+    let single = MonteCarlo::new(1, vec![1.0], 2).run(&Gbm, &inputs, &payoff);
+    assert!(single.price.is_finite());
+    assert!(single.standard_error.is_nan());
+
+    let empty = MonteCarlo::new(0, vec![1.0], 2).run(&Gbm, &inputs, &payoff);
+    assert!(empty.price.is_nan());
+    assert!(empty.standard_error.is_nan());
+
+    let pair = MonteCarlo::new(2, vec![1.0], 2).run(&Gbm, &inputs, &payoff);
+    assert!(pair.standard_error.is_finite());
+}
+
+fn discounted_digital(strike: f64, maturity: f64) -> impl Fn(&[Number], &[Number]) -> Number {
+    move |path: &[Number], inputs: &[Number]| {
+        let discount = (-1.0 * inputs[Gbm::RATE] * maturity).exp();
+        discount * Number::select(Condition::gt(path[path.len() - 1], strike), 1.0, 0.0)
+    }
+}
+
+/// Black-Scholes price of a digital call, exp(-r T) N(d2), on the inputs of
+/// `Gbm`.
+fn digital_price(inputs: &[Number], strike: f64, maturity: f64) -> Number {
+    let volatility = inputs[Gbm::VOLATILITY];
+    let d2 = ((inputs[Gbm::SPOT] / strike).ln()
+        + (inputs[Gbm::RATE] - inputs[Gbm::DIVIDEND_YIELD] - 0.5 * volatility * volatility)
+            * maturity)
+        / (volatility * maturity.sqrt());
+    (-1.0 * inputs[Gbm::RATE] * maturity).exp() * d2.cdf()
+}
+
+#[test]
+fn test_pathwise_digital_has_no_delta() {
+    let inputs = [100.0, 0.03, 0.01, 0.2];
+    let engine = MonteCarlo::new(1000, vec![1.0], 5);
+    let payoff = Payoff::pathwise(discounted_digital(100.0, 1.0));
+    assert_eq!(payoff.estimator(), GreeksEstimator::Pathwise);
+
+    let result = engine.run_payoff(&Gbm, &inputs, &payoff);
+    assert_eq!(result.sensitivities[Gbm::SPOT], 0.0);
+    assert_eq!(result.sensitivities[Gbm::VOLATILITY], 0.0);
+    assert_eq!(
+        result,
+        engine.run(&Gbm, &inputs, discounted_digital(100.0, 1.0))
+    );
+
+    // the same paths and payoffs by likelihood ratio, only the sensitivities
+    // differ
+    let likelihood_ratio = engine.run_payoff(
+        &Gbm,
+        &inputs,
+        &Payoff::likelihood_ratio(discounted_digital(100.0, 1.0)),
+    );
+    assert_eq!(likelihood_ratio.price, result.price);
+    assert_eq!(likelihood_ratio.standard_error, result.standard_error);
+    assert!(likelihood_ratio.sensitivities[Gbm::SPOT] > 0.0);
+}
+
+#[test]
+fn test_likelihood_ratio_digital_against_finite_differences() {
+    let inputs = [100.0, 0.03, 0.01, 0.2];
+    let (strike, maturity) = (105.0, 1.0);
+    let engine = MonteCarlo::new(4000, vec![maturity], 17);
+    let payoff = Payoff::likelihood_ratio(discounted_digital(strike, maturity));
+    assert_eq!(payoff.estimator(), GreeksEstimator::LikelihoodRatio);
+
+    let result = engine.run_payoff(&Gbm, &inputs, &payoff);
+
+    let price = |args: &[Number]| digital_price(args, strike, maturity);
+    let expected: Vec<f64> = (0..inputs.len())
+        .map(|i| common::finite_difference(&price, &inputs, i))
+        .collect();
+    // about three standard errors of each likelihood-ratio estimate
+    let tolerances = [2e-3, 0.2, 0.2, 0.15];
+    for (i, (sensitivity, difference)) in result.sensitivities.iter().zip(&expected).enumerate() {
+        assert!(
+            (sensitivity - difference).abs() < tolerances[i],
+            "input {}: likelihood ratio {} finite difference {}",
+            i,
+            sensitivity,
+            difference
+        );
+    }
+}
+
+#[test]
+fn test_likelihood_ratio_call_against_pathwise() {
+    // both estimate the same sensitivities of a smooth payoff
+    let inputs = [100.0, 0.03, 0.01, 0.2];
+    let engine = MonteCarlo::new(4000, vec![1.0], 23);
+    let pathwise = engine.run(&Gbm, &inputs, discounted_call(100.0, 1.0));
+    let likelihood_ratio = engine.run_payoff(
+        &Gbm,
+        &inputs,
+        &Payoff::likelihood_ratio(discounted_call(100.0, 1.0)),
+    );
+
+    let tolerances = [0.05, 6.0, 6.0, 6.0];
+    for (i, (lr, pw)) in likelihood_ratio
+        .sensitivities
+        .iter()
+        .zip(&pathwise.sensitivities)
+        .enumerate()
+    {
+        assert!(
+            (lr - pw).abs() < tolerances[i],
+            "input {}: likelihood ratio {} pathwise {}",
+            i,
+            lr,
+            pw
+        );
+    }
+}
+
+#[test]
+fn test_mixed_call_and_digital_against_finite_differences() {
+    // a call paying a rebate above its strike: the call is differentiated
+    // pathwise, the rebate by likelihood ratio
+    let inputs = [100.0, 0.03, 0.01, 0.2];
+    let (strike, maturity, rebate) = (100.0, 1.0, 10.0);
+    let engine = MonteCarlo::new(4000, vec![maturity], 29);
+    let digital = discounted_digital(strike, maturity);
+    let payoff = Payoff::mixed(
+        discounted_call(strike, maturity),
+        |path: &[Number], args: &[Number]| rebate * digital(path, args),
+    );
+    assert_eq!(payoff.estimator(), GreeksEstimator::Mixed);
+    let result = engine.run_payoff(&Gbm, &inputs, &payoff);
+
+    let price = |args: &[Number]| {
+        black_scholes::price(
+            OptionType::Call,
+            args[Gbm::SPOT],
+            Number::from(strike),
+            Number::from(maturity),
+            args[Gbm::RATE],
+            args[Gbm::DIVIDEND_YIELD],
+            args[Gbm::VOLATILITY],
+        ) + rebate * digital_price(args, strike, maturity)
+    };
+    let expected: Vec<f64> = (0..inputs.len())
+        .map(|i| common::finite_difference(&price, &inputs, i))
+        .collect();
+    let tolerances = [0.02, 2.0, 2.0, 1.5];
+    for (i, (sensitivity, difference)) in result.sensitivities.iter().zip(&expected).enumerate() {
+        assert!(
+            (sensitivity - difference).abs() < tolerances[i],
+            "input {}: mixed {} finite difference {}",
+            i,
+            sensitivity,
+            difference
+        );
+    }
+}
+
+#[test]
+fn test_likelihood_ratio_with_the_euler_density() {
+    // Euler Ornstein-Uhlenbeck is Gaussian: X_n has mean theta + (x0 - theta)
+    // d^n and variance sigma^2 dt sum_k d^2k with d = 1 - kappa dt
+    let steps = 4;
+    let dt = 0.25;
+    let time_grid: Vec<f64> = (1..=steps).map(|i| i as f64 * dt).collect();
+    let engine = MonteCarlo::new(4000, time_grid, 31);
+    let inputs = [0.5, 1.5, 1.0, 0.4];
+    let level = 0.9;
+
+    let result = engine.run_payoff(
+        &OrnsteinUhlenbeck,
+        &inputs,
+        &Payoff::likelihood_ratio(|path: &[Number], _inputs: &[Number]| {
+            Number::select(Condition::gt(path[path.len() - 1], level), 1.0, 0.0)
+        }),
+    );
+
+    let probability = |args: &[Number]| {
+        let (x0, kappa, theta, sigma) = (args[0], args[1], args[2], args[3]);
+        let d = 1.0 - kappa * dt;
+        let mean = theta + (x0 - theta) * d.powi(steps);
+        let mut variance = Number::from(0.0);
+        for k in 0..steps {
+            variance = variance + sigma * sigma * dt * d.powi(2 * k);
+        }
+        ((mean - level) / variance.sqrt()).cdf()
+    };
+    let expected: Vec<f64> = (0..inputs.len())
+        .map(|i| common::finite_difference(&probability, &inputs, i))
+        .collect();
+    let tolerances = [0.1, 0.15, 0.15, 0.25];
+    for (i, (sensitivity, difference)) in result.sensitivities.iter().zip(&expected).enumerate() {
+        assert!(
+            (sensitivity - difference).abs() < tolerances[i],
+            "input {}: likelihood ratio {} finite difference {}",
+            i,
+            sensitivity,
+            difference
+        );
+    }
+}
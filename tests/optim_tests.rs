@@ -0,0 +1,206 @@
+use aad::finance::black_scholes::{self, OptionType};
+use aad::number::Number;
+use aad::optim::{LineSearch, Method, Optimizer, Termination};
+
+fn rosenbrock(x: &[Number]) -> Number {
+    (1.0 - x[0]) * (1.0 - x[0]) + 100.0 * (x[1] - x[0] * x[0]) * (x[1] - x[0] * x[0])
+}
+
+/// An ill-conditioned quadratic with its minimum at (1, -2, 3).
+fn quadratic(x: &[Number]) -> Number {
+    let (a, b, c) = (x[0] - 1.0, x[1] + 2.0, x[2] - 3.0);
+    0.5 * a * a + 5.0 * b * b + 20.0 * c * c + a * b
+}
+
+fn optimizers() -> Vec<Optimizer> {
+    vec![
+        Optimizer::gradient_descent(),
+        Optimizer::momentum(),
+        Optimizer::adam(),
+        Optimizer::lbfgs(),
+    ]
+}
+
+fn line_searches() -> [LineSearch; 2] {
+    [
+        LineSearch::Armijo { c1: 1e-4 },
+        LineSearch::Wolfe { c1: 1e-4, c2: 0.9 },
+    ]
+}
+
+#[test]
+fn test_every_method_minimizes_a_quadratic() {
+    for optimizer in optimizers() {
+        for line_search in line_searches() {
+            let optimizer = Optimizer {
+                line_search,
+                max_iterations: 5000,
+                gradient_tolerance: 1e-6,
+                ..optimizer.clone()
+            };
+            let result = optimizer.minimize(quadratic, &[0.0, 0.0, 0.0]);
+            assert!(
+                result.converged(),
+                "{:?} {:?}: {:?} after {} iterations",
+                optimizer.method,
+                line_search,
+                result.termination,
+                result.iterations
+            );
+            assert_eq!(result.termination, Termination::GradientTolerance);
+            for (x, expected) in result.x.iter().zip([1.0, -2.0, 3.0]) {
+                assert!(
+                    (x - expected).abs() < 1e-5,
+                    "{:?}: {:?}",
+                    optimizer.method,
+                    result.x
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_histories_decrease() {
+    for optimizer in optimizers() {
+        for line_search in line_searches() {
+            let optimizer = Optimizer {
+                line_search,
+                max_iterations: 50,
+                ..optimizer.clone()
+            };
+            let result = optimizer.minimize(rosenbrock, &[-1.2, 1.0]);
+
+            assert_eq!(result.objective_history.len(), result.iterations + 1);
+            assert_eq!(result.gradient_norm_history.len(), result.iterations + 1);
+            assert!((result.objective_history[0] - 24.2).abs() < 1e-12);
+            assert_eq!(*result.objective_history.last().unwrap(), result.value);
+            assert_eq!(
+                *result.gradient_norm_history.last().unwrap(),
+                result.gradient_norm
+            );
+            // every accepted step satisfies sufficient decrease
+            assert!(
+                result
+                    .objective_history
+                    .windows(2)
+                    .all(|pair| pair[1] < pair[0]),
+                "{:?} {:?}",
+                optimizer.method,
+                line_search
+            );
+            assert!(result.evaluations > result.iterations);
+        }
+    }
+}
+
+#[test]
+fn test_lbfgs_on_rosenbrock() {
+    let result = Optimizer::lbfgs().minimize(rosenbrock, &[-1.2, 1.0]);
+    assert_eq!(result.termination, Termination::GradientTolerance);
+    assert!(result.iterations < 100, "{} iterations", result.iterations);
+    assert!((result.x[0] - 1.0).abs() < 1e-8 && (result.x[1] - 1.0).abs() < 1e-8);
+    assert!(result.gradient_norm <= 1e-8);
+
+    // far fewer iterations than steepest descent
+    let descent = Optimizer {
+        max_iterations: 200,
+        ..Optimizer::gradient_descent()
+    }
+    .minimize(rosenbrock, &[-1.2, 1.0]);
+    assert_eq!(descent.termination, Termination::MaxIterations);
+    assert!(!descent.converged());
+    assert!(descent.value > result.value);
+}
+
+#[test]
+fn test_calibration_of_a_volatility_smile() {
+    // fits the level and skew of a linear smile to call prices
+    let (spot, rate, maturity) = (100.0, 0.02, 1.0);
+    let strikes = [80.0, 90.0, 100.0, 110.0, 120.0];
+    let smile = |level: Number, skew: Number, strike: f64| level + skew * (strike / spot - 1.0);
+    let price = |volatility: Number, strike: f64| {
+        black_scholes::price(
+            OptionType::Call,
+            Number::from(spot),
+            Number::from(strike),
+            Number::from(maturity),
+            Number::from(rate),
+            Number::from(0.0),
+            volatility,
+        )
+    };
+    let quotes: Vec<f64> = aad::no_tape(|| {
+        strikes
+            .iter()
+            .map(|strike| {
+                price(
+                    smile(Number::new(0.22), Number::new(-0.3), *strike),
+                    *strike,
+                )
+                .result
+            })
+            .collect()
+    });
+
+    let objective = |x: &[Number]| {
+        strikes
+            .iter()
+            .zip(&quotes)
+            .fold(Number::from(0.0), |sum, (strike, quote)| {
+                let error = price(smile(x[0], x[1], *strike), *strike) - *quote;
+                sum + error * error
+            })
+    };
+    for optimizer in [Optimizer::lbfgs(), Optimizer::adam()] {
+        let optimizer = Optimizer {
+            gradient_tolerance: 1e-10,
+            max_iterations: 2000,
+            ..optimizer
+        };
+        let result = optimizer.minimize(objective, &[0.3, 0.0]);
+        assert!(result.converged(), "{:?}", result.termination);
+        assert!((result.x[0] - 0.22).abs() < 1e-6, "{:?}", result.x);
+        assert!((result.x[1] + 0.3).abs() < 1e-5, "{:?}", result.x);
+    }
+}
+
+#[test]
+fn test_objective_tolerance() {
+    let optimizer = Optimizer {
+        objective_tolerance: 1e-3,
+        ..Optimizer::gradient_descent()
+    };
+    let result = optimizer.minimize(rosenbrock, &[-1.2, 1.0]);
+    assert_eq!(result.termination, Termination::ObjectiveTolerance);
+    assert!(result.converged());
+    let history = &result.objective_history;
+    let last = history.len() - 1;
+    assert!(history[last - 1] - history[last] <= 1e-3 * history[last]);
+}
+
+// With the default zero objective tolerance, a step whose decrease was lost
+// to round-off in a large objective was reported as converged
+#[test]
+fn test_zero_objective_tolerance_is_disabled() {
+    fn offset_quadratic(x: &[Number]) -> Number {
+        quadratic(x) + 1e8
+    }
+    let optimizer = Optimizer {
+        line_search: LineSearch::Armijo { c1: 1e-4 },
+        gradient_tolerance: 1e-12,
+        ..Optimizer::gradient_descent()
+    };
+    let result = optimizer.minimize(offset_quadratic, &[0.0, 0.0, 0.0]);
+    assert_eq!(result.termination, Termination::MaxIterations);
+    assert!(!result.converged());
+}
+
+#[test]
+fn test_start_at_the_minimum() {
+    let result = Optimizer::new(Method::Lbfgs { memory: 3 }).minimize(quadratic, &[1.0, -2.0, 3.0]);
+    assert_eq!(result.iterations, 0);
+    assert_eq!(result.evaluations, 1);
+    assert_eq!(result.termination, Termination::GradientTolerance);
+    assert_eq!(result.value, 0.0);
+}
@@ -0,0 +1,278 @@
+use aad::automatic_differentiator::AutomaticDifferentiator;
+use aad::finance::black_scholes::{self, OptionType};
+use aad::finance::pde::{self, CrankNicolson, LocalVolatility};
+use aad::linear_algebra;
+use aad::number::Number;
+
+const SPOT: f64 = 100.0;
+const STRIKE: f64 = 105.0;
+const MATURITY: f64 = 1.0;
+const RATE: f64 = 0.03;
+const DIVIDEND_YIELD: f64 = 0.01;
+
+fn scheme() -> CrankNicolson {
+    CrankNicolson::new(100, 50, 1.25)
+}
+
+/// Volatility rising towards low spots and falling with time.
+fn skewed_surface() -> LocalVolatility {
+    let times = vec![0.25, 0.5, 1.0];
+    let spots = vec![70.0, 90.0, 100.0, 110.0, 130.0];
+    let volatilities = times
+        .iter()
+        .map(|t| {
+            spots
+                .iter()
+                .map(|s| Number::new(0.2 + 0.1 * (100.0 - s) / 100.0 - 0.02 * t))
+                .collect()
+        })
+        .collect();
+    LocalVolatility::new(times, spots, volatilities)
+}
+
+/// Untaped PDE price with the volatilities of `surface` replaced by
+/// `volatilities`.
+fn revalue(
+    option_type: OptionType,
+    spot: f64,
+    rate: f64,
+    surface: &LocalVolatility,
+    volatilities: &[Vec<f64>],
+) -> f64 {
+    aad::no_tape(|| {
+        let bumped = LocalVolatility::new(
+            surface.times.clone(),
+            surface.spots.clone(),
+            volatilities
+                .iter()
+                .map(|row| row.iter().map(|v| Number::new(*v)).collect())
+                .collect(),
+        );
+        scheme()
+            .price(
+                option_type,
+                Number::new(spot),
+                STRIKE,
+                MATURITY,
+                Number::new(rate),
+                Number::new(DIVIDEND_YIELD),
+                &bumped,
+            )
+            .result
+    })
+}
+
+fn values(surface: &LocalVolatility) -> Vec<Vec<f64>> {
+    surface
+        .volatilities
+        .iter()
+        .map(|row| row.iter().map(|v| v.result).collect())
+        .collect()
+}
+
+fn assert_close(adjoint: f64, difference: f64, name: &str) {
+    assert!(
+        (adjoint - difference).abs() <= 1e-6 * difference.abs().max(1.0),
+        "{}: adjoint {} bump and revalue {}",
+        name,
+        adjoint,
+        difference
+    );
+}
+
+#[test]
+fn test_flat_volatility_matches_black_scholes() {
+    for option_type in [OptionType::Call, OptionType::Put] {
+        let (pde_price, closed_form) = aad::no_tape(|| {
+            let pde_price = scheme().price(
+                option_type,
+                Number::new(SPOT),
+                STRIKE,
+                MATURITY,
+                Number::new(RATE),
+                Number::new(DIVIDEND_YIELD),
+                &LocalVolatility::flat(0.25),
+            );
+            let closed_form = black_scholes::price(
+                option_type,
+                Number::new(SPOT),
+                Number::new(STRIKE),
+                Number::new(MATURITY),
+                Number::new(RATE),
+                Number::new(DIVIDEND_YIELD),
+                Number::new(0.25),
+            );
+            (pde_price.result, closed_form.result)
+        });
+        assert!(
+            (pde_price - closed_form).abs() < 5e-3,
+            "{:?}: pde {} closed form {}",
+            option_type,
+            pde_price,
+            closed_form
+        );
+    }
+}
+
+#[test]
+fn test_flat_volatility_greeks_match_black_scholes() {
+    let risk = pde::risk(
+        &scheme(),
+        OptionType::Call,
+        SPOT,
+        STRIKE,
+        MATURITY,
+        RATE,
+        DIVIDEND_YIELD,
+        &LocalVolatility::flat(0.25),
+    );
+    let greeks = black_scholes::EuropeanOption {
+        option_type: OptionType::Call,
+        spot: SPOT,
+        strike: STRIKE,
+        maturity: MATURITY,
+        rate: RATE,
+        dividend_yield: DIVIDEND_YIELD,
+        volatility: 0.25,
+    }
+    .greeks();
+
+    assert!((risk.delta - greeks.delta).abs() < 1e-4);
+    assert!((risk.rho - greeks.rho).abs() < 5e-2);
+    assert!((risk.vegas[0][0] - greeks.vega).abs() < 2e-2);
+}
+
+#[test]
+fn test_local_volatility_risk_against_bump_and_revalue() {
+    let surface = skewed_surface();
+    let volatilities = values(&surface);
+    for option_type in [OptionType::Call, OptionType::Put] {
+        let risk = pde::risk(
+            &scheme(),
+            option_type,
+            SPOT,
+            STRIKE,
+            MATURITY,
+            RATE,
+            DIVIDEND_YIELD,
+            &surface,
+        );
+        let price = |spot, rate, volatilities: &[Vec<f64>]| {
+            revalue(option_type, spot, rate, &surface, volatilities)
+        };
+        assert!((risk.price - price(SPOT, RATE, &volatilities)).abs() < 1e-12);
+
+        let bump = 1e-5;
+        let delta = (price(SPOT + bump, RATE, &volatilities)
+            - price(SPOT - bump, RATE, &volatilities))
+            / (2.0 * bump);
+        assert_close(risk.delta, delta, "delta");
+        let rho = (price(SPOT, RATE + bump, &volatilities)
+            - price(SPOT, RATE - bump, &volatilities))
+            / (2.0 * bump);
+        assert_close(risk.rho, rho, "rho");
+
+        for (i, row) in volatilities.iter().enumerate() {
+            for j in 0..row.len() {
+                let mut up = volatilities.clone();
+                up[i][j] += bump;
+                let mut down = volatilities.clone();
+                down[i][j] -= bump;
+                let vega = (price(SPOT, RATE, &up) - price(SPOT, RATE, &down)) / (2.0 * bump);
+                assert_close(risk.vegas[i][j], vega, &format!("vega ({}, {})", i, j));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_vega_is_concentrated_near_the_money() {
+    let risk = pde::risk(
+        &scheme(),
+        OptionType::Call,
+        SPOT,
+        STRIKE,
+        MATURITY,
+        RATE,
+        DIVIDEND_YIELD,
+        &skewed_surface(),
+    );
+    let total: f64 = risk.vegas.iter().flatten().sum();
+    for row in &risk.vegas {
+        assert!(row[2] + row[3] > row[0] + row[4]);
+    }
+    assert!(risk.vegas.iter().flatten().all(|vega| *vega >= 0.0));
+    assert!(total > 0.0);
+}
+
+/// Solution of the system with coefficients and right hand side `x`, laid
+/// out as 3 lower, 4 diagonal, 3 upper and 4 right hand side entries, dotted
+/// with fixed weights.
+fn weighted_solution(x: &[Number]) -> Number {
+    let solution = linear_algebra::solve_tridiagonal(&x[0..3], &x[3..7], &x[7..10], &x[10..14]);
+    solution
+        .iter()
+        .zip([1.0, -2.0, 0.5, 3.0])
+        .fold(Number::from(0.0), |sum, (x, w)| sum + *x * w)
+}
+
+const SYSTEM: [f64; 14] = [
+    -1.0, 0.5, -0.7, 4.0, 5.0, 3.5, 4.5, 1.2, -0.8, 0.9, 1.0, -2.0, 0.3, 2.5,
+];
+
+#[test]
+fn test_tridiagonal_solve_adjoints_against_finite_differences() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+    let arguments: Vec<Number> = SYSTEM.iter().map(|x| Number::new(*x)).collect();
+    let evaluation = automatic_differentiator.derivatives(weighted_solution, &arguments);
+
+    let untaped = |point: &[f64]| {
+        aad::no_tape(|| {
+            let args: Vec<Number> = point.iter().map(|x| Number::new(*x)).collect();
+            weighted_solution(&args).result
+        })
+    };
+    assert!((evaluation.result - untaped(&SYSTEM)).abs() < 1e-15);
+
+    let bump = 1e-6;
+    for (i, argument) in arguments.iter().enumerate() {
+        let mut up = SYSTEM;
+        up[i] += bump;
+        let mut down = SYSTEM;
+        down[i] -= bump;
+        let difference = (untaped(&up) - untaped(&down)) / (2.0 * bump);
+        assert!(
+            (evaluation.derivative(*argument) - difference).abs() < 1e-8,
+            "entry {}: adjoint {} finite difference {}",
+            i,
+            evaluation.derivative(*argument),
+            difference
+        );
+    }
+}
+
+#[test]
+fn test_tridiagonal_solve_replay() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+    let arguments: Vec<Number> = SYSTEM.iter().map(|x| Number::new(*x)).collect();
+    automatic_differentiator.derivatives(weighted_solution, &arguments);
+
+    let mut shifted = SYSTEM;
+    shifted[4] += 0.25;
+    shifted[12] -= 1.0;
+    let replay = automatic_differentiator.replay(&shifted);
+
+    let mut fresh = AutomaticDifferentiator::new();
+    let arguments: Vec<Number> = shifted.iter().map(|x| Number::new(*x)).collect();
+    let evaluation = fresh.derivatives(weighted_solution, &arguments);
+
+    assert!((replay.evaluation.result - evaluation.result).abs() < 1e-14);
+    for (replayed, recorded) in replay
+        .evaluation
+        .derivatives
+        .iter()
+        .zip(&evaluation.derivatives)
+    {
+        assert!((replayed.derivative - recorded.derivative).abs() < 1e-12);
+    }
+}
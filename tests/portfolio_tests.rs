@@ -0,0 +1,168 @@
+use aad::automatic_differentiator::AutomaticDifferentiator;
+use aad::finance::black_scholes::{self, OptionType};
+use aad::finance::portfolio::Portfolio;
+use aad::number::Number;
+
+/// spot, rate, dividend yield, volatility
+const MARKET: [f64; 4] = [100.0, 0.03, 0.01, 0.2];
+
+fn option(option_type: OptionType, strike: f64, maturity: f64) -> impl Fn(&[Number]) -> Number {
+    move |market: &[Number]| {
+        black_scholes::price(
+            option_type,
+            market[0],
+            Number::from(strike),
+            Number::from(maturity),
+            market[1],
+            market[2],
+            market[3],
+        )
+    }
+}
+
+fn forward(strike: f64, maturity: f64) -> impl Fn(&[Number]) -> Number {
+    move |market: &[Number]| {
+        market[0] * (-1.0 * market[2] * maturity).exp()
+            - strike * (-1.0 * market[1] * maturity).exp()
+    }
+}
+
+fn book() -> Portfolio<'static> {
+    let mut portfolio = Portfolio::new();
+    portfolio
+        .add("call 100 1y", option(OptionType::Call, 100.0, 1.0))
+        .add("put 90 6m", option(OptionType::Put, 90.0, 0.5))
+        .add("call 120 2y", option(OptionType::Call, 120.0, 2.0))
+        .add("forward 105 1y", forward(105.0, 1.0));
+    portfolio
+}
+
+/// Value and sensitivities of a single trade from a recording of its own.
+fn single_trade_risk<P>(pricer: P) -> (f64, Vec<f64>)
+where
+    P: Fn(&[Number]) -> Number,
+{
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+    let arguments: Vec<Number> = MARKET.iter().map(|x| Number::new(*x)).collect();
+    let evaluation = automatic_differentiator.derivatives(pricer, &arguments);
+    (
+        evaluation.result,
+        arguments
+            .iter()
+            .map(|argument| evaluation.derivative(*argument))
+            .collect(),
+    )
+}
+
+fn single_trade_risks() -> Vec<(f64, Vec<f64>)> {
+    vec![
+        single_trade_risk(option(OptionType::Call, 100.0, 1.0)),
+        single_trade_risk(option(OptionType::Put, 90.0, 0.5)),
+        single_trade_risk(option(OptionType::Call, 120.0, 2.0)),
+        single_trade_risk(forward(105.0, 1.0)),
+    ]
+}
+
+#[test]
+fn test_total_risk_is_the_sum_of_the_trade_risks() {
+    let risk = book().risk(&MARKET);
+    let trades = single_trade_risks();
+
+    assert_eq!(risk.present_values.len(), trades.len());
+    for (present_value, (value, _)) in risk.present_values.iter().zip(&trades) {
+        assert!((present_value - value).abs() < 1e-12);
+    }
+    let total: f64 = trades.iter().map(|(value, _)| value).sum();
+    assert!((risk.value - total).abs() < 1e-12);
+
+    assert_eq!(risk.sensitivities.len(), MARKET.len());
+    for (i, sensitivity) in risk.sensitivities.iter().enumerate() {
+        let sum: f64 = trades
+            .iter()
+            .map(|(_, sensitivities)| sensitivities[i])
+            .sum();
+        assert!(
+            (sensitivity - sum).abs() < 1e-10,
+            "input {}: book {} sum of trades {}",
+            i,
+            sensitivity,
+            sum
+        );
+    }
+}
+
+#[test]
+fn test_weighted_risk() {
+    let weights = [1e4, -5e3, 2.5e3, -1e4];
+    let risk = book().weighted_risk(&MARKET, &weights);
+    let trades = single_trade_risks();
+
+    // the present values are those of one unit of each trade
+    for (present_value, (value, _)) in risk.present_values.iter().zip(&trades) {
+        assert!((present_value - value).abs() < 1e-12);
+    }
+    let total: f64 = trades
+        .iter()
+        .zip(weights)
+        .map(|((value, _), weight)| weight * value)
+        .sum();
+    assert!((risk.value - total).abs() < 1e-8);
+    for (i, sensitivity) in risk.sensitivities.iter().enumerate() {
+        let sum: f64 = trades
+            .iter()
+            .zip(weights)
+            .map(|((_, sensitivities), weight)| weight * sensitivities[i])
+            .sum();
+        assert!((sensitivity - sum).abs() < 1e-6 * sum.abs().max(1.0));
+    }
+}
+
+#[test]
+fn test_weights_select_a_trade() {
+    let risk = book().weighted_risk(&MARKET, &[0.0, 0.0, 1.0, 0.0]);
+    let (value, sensitivities) = single_trade_risk(option(OptionType::Call, 120.0, 2.0));
+
+    assert!((risk.value - value).abs() < 1e-12);
+    for (sensitivity, expected) in risk.sensitivities.iter().zip(sensitivities) {
+        assert!((sensitivity - expected).abs() < 1e-12);
+    }
+    // every trade is still valued
+    assert!(risk.present_values.iter().all(|value| *value != 0.0));
+}
+
+#[test]
+fn test_trades_borrowing_data() {
+    // a strip of calls whose strikes live outside the portfolio
+    let strikes: Vec<f64> = (0..200).map(|i| 80.0 + 0.2 * i as f64).collect();
+    let mut portfolio = Portfolio::new();
+    for strike in &strikes {
+        portfolio.add(format!("call {}", strike), |market: &[Number]| {
+            option(OptionType::Call, *strike, 1.0)(market)
+        });
+    }
+    assert_eq!(portfolio.len(), strikes.len());
+    assert_eq!(portfolio.names()[0], "call 80");
+
+    let risk = portfolio.risk(&MARKET);
+    // call prices fall with the strike, each call has a delta between 0 and 1
+    assert!(risk.present_values.windows(2).all(|pair| pair[0] > pair[1]));
+    assert!((risk.value - risk.present_values.iter().sum::<f64>()).abs() < 1e-9);
+    assert!(risk.sensitivities[0] > 0.0 && risk.sensitivities[0] < strikes.len() as f64);
+}
+
+#[test]
+fn test_empty_portfolio() {
+    let portfolio = Portfolio::new();
+    assert!(portfolio.is_empty());
+
+    let risk = portfolio.risk(&MARKET);
+    assert_eq!(risk.value, 0.0);
+    assert_eq!(risk.sensitivities, vec![0.0; MARKET.len()]);
+    assert!(risk.present_values.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "one weight per trade")]
+fn test_weights_must_match_the_trades() {
+    book().weighted_risk(&MARKET, &[1.0, 2.0]);
+}
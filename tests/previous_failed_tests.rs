@@ -251,3 +251,31 @@ fn test_operators_add_sub_mul_div_pi() {
     assert!((dfdy - (2.535237)).abs() < epsilon);
     assert!((dfdz - (-1.58452)).abs() < epsilon);
 }
+
+// The seed went to the last recorded node rather than to the returned one
+#[test]
+fn test_result_recorded_before_the_last_node() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let x = Number::new(2.0);
+    let y = Number::new(5.0);
+
+    let arguments = vec![x, y];
+
+    fn f(args: &[Number]) -> Number {
+        let result = args[0] * args[1];
+        // recorded after the result, but not part of it
+        let _unused = (args[0] + args[1]).exp();
+        result
+    }
+
+    let evaluation = automatic_differentiator.derivatives(f, &arguments);
+    assert_eq!(evaluation.result, 10.0);
+    assert_eq!(evaluation.derivative(x), 5.0);
+    assert_eq!(evaluation.derivative(y), 2.0);
+
+    let replay = automatic_differentiator.replay(&[3.0, 4.0]);
+    assert_eq!(replay.evaluation.result, 12.0);
+    assert_eq!(replay.evaluation.derivative(x), 4.0);
+    assert_eq!(replay.evaluation.derivative(y), 3.0);
+}
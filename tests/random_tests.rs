@@ -0,0 +1,74 @@
+use aad::random::{NormalMethod, Rng};
+
+fn mean_and_variance(xs: &[f64]) -> (f64, f64) {
+    let n = xs.len() as f64;
+    let mean = xs.iter().sum::<f64>() / n;
+    let variance = xs.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / (n - 1.0);
+    (mean, variance)
+}
+
+#[test]
+fn test_same_seed_gives_same_sequence() {
+    let mut a = Rng::new(42);
+    let mut b = Rng::new(42);
+    let mut c = Rng::new(43);
+
+    let xs: Vec<u64> = (0..100).map(|_| a.next_u64()).collect();
+    let ys: Vec<u64> = (0..100).map(|_| b.next_u64()).collect();
+    let zs: Vec<u64> = (0..100).map(|_| c.next_u64()).collect();
+    assert_eq!(xs, ys);
+    assert_ne!(xs, zs);
+}
+
+#[test]
+fn test_seed_zero_is_usable() {
+    let mut rng = Rng::new(0);
+    let xs: Vec<u64> = (0..10).map(|_| rng.next_u64()).collect();
+    assert!(xs.iter().all(|x| *x != 0));
+}
+
+#[test]
+fn test_uniform_moments() {
+    let mut rng = Rng::new(7);
+    let us: Vec<f64> = (0..200_000).map(|_| rng.uniform()).collect();
+    assert!(us.iter().all(|u| 0.0 < *u && *u < 1.0));
+
+    let (mean, variance) = mean_and_variance(&us);
+    assert!((mean - 0.5).abs() < 0.003, "mean {}", mean);
+    assert!(
+        (variance - 1.0 / 12.0).abs() < 0.001,
+        "variance {}",
+        variance
+    );
+}
+
+#[test]
+fn test_normal_moments() {
+    for method in [NormalMethod::BoxMuller, NormalMethod::InverseCdf] {
+        let mut rng = Rng::new(2024);
+        let zs = rng.normals(200_000, method);
+
+        let (mean, variance) = mean_and_variance(&zs);
+        assert!(mean.abs() < 0.01, "{:?}: mean {}", method, mean);
+        assert!(
+            (variance - 1.0).abs() < 0.01,
+            "{:?}: variance {}",
+            method,
+            variance
+        );
+
+        // P(Z < -1) = 0.158655
+        let below = zs.iter().filter(|z| **z < -1.0).count() as f64 / zs.len() as f64;
+        assert!((below - 0.158655).abs() < 0.003, "{:?}: {}", method, below);
+    }
+}
+
+#[test]
+fn test_box_muller_uses_both_normals_of_a_pair() {
+    // two Box-Muller normals consume two uniforms, as do two inverse-cdf normals
+    let mut box_muller = Rng::new(5);
+    let mut inverse_cdf = Rng::new(5);
+    box_muller.normals(2, NormalMethod::BoxMuller);
+    inverse_cdf.normals(2, NormalMethod::InverseCdf);
+    assert_eq!(box_muller.next_u64(), inverse_cdf.next_u64());
+}
@@ -0,0 +1,180 @@
+use aad::finance::black_scholes::{self, OptionType};
+use aad::number::Number;
+use aad::root_finding::{self, NewtonSolver, Termination};
+
+/// Roots (1, 2), (2, 1), (-1, -2) and (-2, -1).
+fn circle_and_hyperbola(x: &[Number]) -> Vec<Number> {
+    vec![x[0] * x[0] + x[1] * x[1] - 5.0, x[0] * x[1] - 2.0]
+}
+
+/// Wallis' cubic, with its real root at 2.0945514815423265.
+fn wallis(x: Number) -> Number {
+    x * x * x - 2.0 * x - 5.0
+}
+
+const WALLIS_ROOT: f64 = 2.0945514815423265;
+
+/// Annually compounded par rates of swaps over one to four years.
+const PAR_RATES: [f64; 4] = [0.02, 0.025, 0.028, 0.03];
+
+/// Errors of the par rates of the swaps priced off the discount factors of
+/// years one to four, as in a bootstrap solved all at once.
+fn swap_errors(discount_factors: &[Number]) -> Vec<Number> {
+    let mut annuity = Number::from(0.0);
+    discount_factors
+        .iter()
+        .zip(PAR_RATES)
+        .map(|(df, rate)| {
+            annuity = annuity + *df;
+            (1.0 - *df) / annuity - rate
+        })
+        .collect()
+}
+
+#[test]
+fn test_system_converges_quadratically() {
+    let result = root_finding::newton_solve(circle_and_hyperbola, &[0.5, 2.5]);
+    assert_eq!(result.termination, Termination::Converged);
+    assert!((result.x[0] - 1.0).abs() < 1e-12 && (result.x[1] - 2.0).abs() < 1e-12);
+    assert!(result.residual_norm <= 1e-12);
+    assert_eq!(result.residual_norm_history.len(), result.iterations + 1);
+    assert!(result.iterations <= 8, "{} iterations", result.iterations);
+
+    // the residual squares from one iteration to the next near the root
+    for pair in result.residual_norm_history.windows(2) {
+        if pair[0] < 0.1 {
+            assert!(pair[1] <= 10.0 * pair[0] * pair[0], "{:?}", pair);
+        }
+    }
+
+    // the Jacobian [[2x, 2y], [y, x]] at the root
+    let expected = [[2.0, 4.0], [2.0, 1.0]];
+    for (row, expected) in result.jacobian.iter().zip(expected) {
+        for (entry, expected) in row.iter().zip(expected) {
+            assert!((entry - expected).abs() < 1e-11);
+        }
+    }
+}
+
+#[test]
+fn test_line_search_rescues_a_diverging_newton_iteration() {
+    // the full Newton step for atan from 3 overshoots to ever larger |x|
+    let result = root_finding::newton_solve(|x: &[Number]| vec![x[0].atan()], &[3.0]);
+    assert!(result.converged());
+    assert!(result.x[0].abs() < 1e-12);
+    assert!(
+        result
+            .residual_norm_history
+            .windows(2)
+            .all(|pair| pair[1] < pair[0])
+    );
+
+    let scalar = root_finding::newton(|x: Number| x.atan(), 3.0);
+    assert!(scalar.converged());
+    assert!(scalar.x.abs() < 1e-12);
+    assert!((scalar.derivative - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_discount_factors_from_par_swaps() {
+    let result = root_finding::newton_solve(swap_errors, &[1.0; 4]);
+    assert!(result.converged(), "{:?}", result.termination);
+
+    // bootstrapped one at a time
+    let mut annuity = 0.0;
+    for (df, rate) in result.x.iter().zip(PAR_RATES) {
+        let expected = (1.0 - rate * annuity) / (1.0 + rate);
+        assert!((df - expected).abs() < 1e-12);
+        annuity += expected;
+    }
+    // each par rate depends only on the discount factors up to its maturity
+    for (i, row) in result.jacobian.iter().enumerate() {
+        assert!(row[..=i].iter().all(|d| *d < 0.0), "row {}: {:?}", i, row);
+        assert!(
+            row[i + 1..].iter().all(|d| *d == 0.0),
+            "row {}: {:?}",
+            i,
+            row
+        );
+    }
+}
+
+#[test]
+fn test_halley_needs_fewer_iterations_than_newton() {
+    let newton = root_finding::newton(wallis, 3.0);
+    let halley = root_finding::halley(wallis, 3.0);
+    for root in [&newton, &halley] {
+        assert!(root.converged());
+        assert!((root.x - WALLIS_ROOT).abs() < 1e-13);
+        assert!((root.derivative - (3.0 * WALLIS_ROOT * WALLIS_ROOT - 2.0)).abs() < 1e-12);
+        assert_eq!(root.residual_history.len(), root.iterations + 1);
+    }
+    assert!(
+        halley.iterations < newton.iterations,
+        "halley {} newton {}",
+        halley.iterations,
+        newton.iterations
+    );
+}
+
+#[test]
+fn test_halley_with_second_derivative() {
+    let newton = root_finding::newton(wallis, 3.0);
+    let halley = root_finding::halley_with_second_derivative(wallis, |x| 6.0 * x, 3.0);
+    assert!(halley.converged());
+    assert!((halley.x - WALLIS_ROOT).abs() < 1e-13);
+    assert!(halley.iterations < newton.iterations);
+    // the differenced f'' is close enough not to change the iterations
+    assert_eq!(
+        halley.iterations,
+        root_finding::halley(wallis, 3.0).iterations
+    );
+}
+
+#[test]
+fn test_implied_volatility() {
+    let price = |volatility: Number| {
+        black_scholes::price(
+            OptionType::Call,
+            Number::from(100.0),
+            Number::from(110.0),
+            Number::from(0.5),
+            Number::from(0.03),
+            Number::from(0.0),
+            volatility,
+        )
+    };
+    let quote = aad::no_tape(|| price(Number::new(0.37)).result);
+    for root in [
+        root_finding::newton(|v: Number| price(v) - quote, 0.2),
+        root_finding::halley(|v: Number| price(v) - quote, 0.2),
+    ] {
+        assert!(root.converged());
+        assert!((root.x - 0.37).abs() < 1e-10);
+        // the derivative at the root is the vega
+        assert!(root.derivative > 0.0);
+    }
+}
+
+#[test]
+fn test_singular_jacobian_and_max_iterations() {
+    let singular = root_finding::newton_solve(
+        |x: &[Number]| vec![x[0] + x[1] - 1.0, 2.0 * x[0] + 2.0 * x[1] - 3.0],
+        &[0.0, 0.0],
+    );
+    assert_eq!(singular.termination, Termination::SingularJacobian);
+    assert_eq!(singular.iterations, 0);
+    assert_eq!(singular.jacobian, vec![vec![1.0, 1.0], vec![2.0, 2.0]]);
+
+    let flat = root_finding::newton(|x: Number| x * x + 1.0, 0.0);
+    assert_eq!(flat.termination, Termination::SingularJacobian);
+
+    let solver = NewtonSolver {
+        max_iterations: 2,
+        ..NewtonSolver::new()
+    };
+    let result = solver.solve(circle_and_hyperbola, &[0.5, 2.5]);
+    assert_eq!(result.termination, Termination::MaxIterations);
+    assert_eq!(result.iterations, 2);
+    assert!(!result.converged());
+}
@@ -0,0 +1,249 @@
+use aad::automatic_differentiator::AutomaticDifferentiator;
+use aad::finance::black_scholes::{self, OptionType};
+use aad::finance::sabr::{self, Sabr, SabrParameters, VolatilityQuote};
+use aad::number::Number;
+use statrs::distribution::{ContinuousCDF, Normal};
+
+mod common;
+
+const FORWARD: f64 = 0.04;
+const MATURITY: f64 = 2.0;
+
+const PARAMETERS: SabrParameters = SabrParameters {
+    alpha: 0.03,
+    beta: 0.5,
+    rho: -0.3,
+    nu: 0.4,
+};
+
+fn volatility(parameters: &SabrParameters, strike: f64) -> f64 {
+    aad::no_tape(|| {
+        Sabr::new(
+            parameters.alpha,
+            parameters.beta,
+            parameters.rho,
+            parameters.nu,
+        )
+        .implied_volatility(Number::new(FORWARD), Number::new(strike), MATURITY)
+        .result
+    })
+}
+
+/// Compares the adjoints of `func` with central finite differences at every point.
+fn test_against_finite_differences<F>(func: F, points: &[Vec<f64>])
+where
+    F: Fn(&[Number]) -> Number,
+{
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    for point in points {
+        let arguments: Vec<Number> = point.iter().map(|x| Number::new(*x)).collect();
+        let evaluation = automatic_differentiator.derivatives(&func, &arguments);
+        assert!(evaluation.result.is_finite());
+
+        for (i, argument) in arguments.iter().enumerate() {
+            let adjoint = evaluation.derivative(*argument);
+            let reference = common::finite_difference(&func, point, i);
+            assert!(
+                (adjoint - reference).abs() <= 1e-6 * reference.abs().max(1.0),
+                "at {:?}, argument {}: adjoint {} finite difference {}",
+                point,
+                i,
+                adjoint,
+                reference
+            );
+        }
+    }
+}
+
+#[test]
+fn test_implied_volatility_against_reference() {
+    // Hagan's formula evaluated in 30 digit arithmetic
+    for (strike, expected) in [
+        (0.02, 0.25339799401608884),
+        (0.03, 0.19026671023479414),
+        (0.04, 0.15285531249999999),
+        (0.05, 0.14029802064918356),
+        (0.06, 0.14380201645523543),
+    ] {
+        let value = volatility(&PARAMETERS, strike);
+        assert!(
+            (value - expected).abs() < 1e-14,
+            "strike {}: {} != {}",
+            strike,
+            value,
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_at_the_money_limit() {
+    // at K = F the volatility is alpha / F^(1 - beta) times the correction
+    let (alpha, beta, rho, nu) = (0.03, 0.5, -0.3, 0.4);
+    let f_power = FORWARD.powf(1.0 - beta);
+    let expected = alpha / f_power
+        * (1.0
+            + ((1.0 - beta).powi(2) / 24.0 * alpha * alpha / (f_power * f_power)
+                + 0.25 * rho * beta * nu * alpha / f_power
+                + (2.0 - 3.0 * rho * rho) / 24.0 * nu * nu)
+                * MATURITY);
+    let at_the_money = volatility(&PARAMETERS, FORWARD);
+    assert!(at_the_money.is_finite());
+    assert!((at_the_money - expected).abs() < 1e-15);
+
+    // continuous across the switch to the series
+    for relative in [1e-9, 1e-7, 1e-5, 1e-3] {
+        for strike in [FORWARD * (1.0 + relative), FORWARD * (1.0 - relative)] {
+            let slope = (volatility(&PARAMETERS, strike) - at_the_money) / (strike - FORWARD);
+            assert!(slope.is_finite() && slope.abs() < 10.0, "slope {}", slope);
+        }
+    }
+}
+
+#[test]
+fn test_implied_volatility_against_finite_differences() {
+    // arguments: forward, strike, alpha, beta, rho, nu
+    let points: Vec<Vec<f64>> = [0.015, 0.035, FORWARD, FORWARD * (1.0 + 1e-6), 0.045, 0.09]
+        .iter()
+        .flat_map(|strike| {
+            [
+                vec![FORWARD, *strike, 0.03, 0.5, -0.3, 0.4],
+                vec![FORWARD, *strike, 0.2, 1.0, 0.4, 0.8],
+                vec![FORWARD, *strike, 0.005, 0.0, 0.0, 0.25],
+            ]
+        })
+        .collect();
+    test_against_finite_differences(
+        |args: &[Number]| {
+            Sabr::new(args[2], args[3], args[4], args[5])
+                .implied_volatility(args[0], args[1], MATURITY)
+        },
+        &points,
+    );
+}
+
+#[test]
+fn test_lognormal_without_vol_of_vol() {
+    // beta = 1 and nu = 0 is Black-Scholes with volatility alpha
+    let parameters = SabrParameters {
+        alpha: 0.25,
+        beta: 1.0,
+        rho: 0.0,
+        nu: 0.0,
+    };
+    for strike in [0.02, 0.04, 0.07] {
+        assert!((volatility(&parameters, strike) - 0.25).abs() < 1e-15);
+    }
+}
+
+#[test]
+fn test_black_price() {
+    // Black (1976): e^(-rT) (F N(d1) - K N(d2))
+    let (forward, strike, maturity, rate, sigma): (f64, f64, f64, f64, f64) =
+        (102.0, 95.0, 0.8, 0.03, 0.27);
+    let d1 = ((forward / strike).ln() + 0.5 * sigma * sigma * maturity) / (sigma * maturity.sqrt());
+    let d2 = d1 - sigma * maturity.sqrt();
+    let norm = Normal::standard();
+    let expected = (-rate * maturity).exp() * (forward * norm.cdf(d1) - strike * norm.cdf(d2));
+    let price = aad::no_tape(|| {
+        black_scholes::black_price(
+            OptionType::Call,
+            Number::new(forward),
+            Number::new(strike),
+            Number::new(maturity),
+            Number::new(rate),
+            Number::new(sigma),
+        )
+        .result
+    });
+    assert!((price - expected).abs() < 1e-12);
+}
+
+#[test]
+fn test_vega_by_parameter() {
+    let (strike, rate) = (0.045, 0.02);
+    for option_type in [OptionType::Call, OptionType::Put] {
+        let risk = sabr::sensitivities(&PARAMETERS, option_type, FORWARD, strike, MATURITY, rate);
+
+        let price = |forward: f64, parameters: &SabrParameters| {
+            aad::no_tape(|| {
+                Sabr::new(
+                    parameters.alpha,
+                    parameters.beta,
+                    parameters.rho,
+                    parameters.nu,
+                )
+                .price(
+                    option_type,
+                    Number::new(forward),
+                    strike,
+                    MATURITY,
+                    Number::new(rate),
+                )
+                .result
+            })
+        };
+        assert!((risk.price - price(FORWARD, &PARAMETERS)).abs() < 1e-15);
+
+        let h = 1e-7;
+        let bumped = |f: fn(&mut SabrParameters, f64)| {
+            let (mut up, mut down) = (PARAMETERS, PARAMETERS);
+            f(&mut up, h);
+            f(&mut down, -h);
+            (price(FORWARD, &up) - price(FORWARD, &down)) / (2.0 * h)
+        };
+        let delta = (price(FORWARD + h, &PARAMETERS) - price(FORWARD - h, &PARAMETERS)) / (2.0 * h);
+        for (name, adjoint, difference) in [
+            ("delta", risk.delta, delta),
+            ("alpha", risk.alpha, bumped(|p, h| p.alpha += h)),
+            ("beta", risk.beta, bumped(|p, h| p.beta += h)),
+            ("rho", risk.rho, bumped(|p, h| p.rho += h)),
+            ("nu", risk.nu, bumped(|p, h| p.nu += h)),
+        ] {
+            assert!(
+                (adjoint - difference).abs() < 1e-6 * difference.abs().max(1e-3),
+                "{}: adjoint {} finite difference {}",
+                name,
+                adjoint,
+                difference
+            );
+        }
+    }
+}
+
+#[test]
+fn test_smile_calibration_recovers_parameters() {
+    let quotes: Vec<VolatilityQuote> = [0.02, 0.025, 0.03, 0.035, 0.04, 0.045, 0.05, 0.06, 0.07]
+        .iter()
+        .map(|strike| VolatilityQuote {
+            strike: *strike,
+            volatility: volatility(&PARAMETERS, *strike),
+        })
+        .collect();
+
+    let initial_guess = SabrParameters {
+        alpha: 0.05,
+        beta: 0.5,
+        rho: 0.2,
+        nu: 1.0,
+    };
+    let calibration = sabr::calibrate(FORWARD, MATURITY, &quotes, initial_guess);
+
+    assert!(calibration.root_mean_square_error < 1e-12);
+    let recovered = calibration.parameters;
+    assert_eq!(recovered.beta, 0.5);
+    for (name, value, expected) in [
+        ("alpha", recovered.alpha, PARAMETERS.alpha),
+        ("rho", recovered.rho, PARAMETERS.rho),
+        ("nu", recovered.nu, PARAMETERS.nu),
+    ] {
+        assert!(
+            (value - expected).abs() < 1e-8,
+            "{}: {} != {}",
+            name,
+            value,
+            expected
+        );
+    }
+}
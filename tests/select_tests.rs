@@ -0,0 +1,183 @@
+use aad::automatic_differentiator::{AutomaticDifferentiator, Evaluation};
+use aad::distributions::{LogDensity, StudentsT};
+use aad::number::{Comparison, Condition, Number};
+
+fn assert_same_evaluation(lhs: &Evaluation, rhs: &Evaluation) {
+    let epsilon = 1e-12;
+    assert!(
+        (lhs.result - rhs.result).abs() < epsilon,
+        "{} != {}",
+        lhs.result,
+        rhs.result
+    );
+    assert_eq!(lhs.derivatives.len(), rhs.derivatives.len());
+    for (l, r) in lhs.derivatives.iter().zip(rhs.derivatives.iter()) {
+        assert!(
+            (l.derivative - r.derivative).abs() < epsilon,
+            "{} != {}",
+            l.derivative,
+            r.derivative
+        );
+    }
+}
+
+// call payoff with a recorded branch: S - K if S > K, 0 otherwise
+fn call_payoff(args: &[Number]) -> Number {
+    args[0].where_gt(args[1], args[0] - args[1], 0.0)
+}
+
+#[test]
+fn test_select_follows_the_condition() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let spot = Number::new(110.0);
+    let strike = Number::new(100.0);
+    let evaluation = automatic_differentiator.derivatives(call_payoff, &[spot, strike]);
+    assert_eq!(evaluation.result, 10.0);
+    assert_eq!(evaluation.derivative(spot), 1.0);
+    assert_eq!(evaluation.derivative(strike), -1.0);
+
+    let spot = Number::new(90.0);
+    let evaluation = automatic_differentiator.derivatives(call_payoff, &[spot, strike]);
+    assert_eq!(evaluation.result, 0.0);
+    assert_eq!(evaluation.derivative(spot), 0.0);
+    assert_eq!(evaluation.derivative(strike), 0.0);
+}
+
+#[test]
+fn test_select_with_each_comparison() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let x = Number::new(2.0);
+    let cases = [
+        (Comparison::Greater, 4.0),
+        (Comparison::GreaterOrEqual, 3.0),
+        (Comparison::Less, 4.0),
+        (Comparison::LessOrEqual, 3.0),
+    ];
+    for (comparison, expected_derivative) in cases {
+        // 3x if the condition holds, x * x otherwise: derivative 3 or 2x = 4
+        let evaluation = automatic_differentiator.derivatives(
+            |args: &[Number]| {
+                let condition = Condition::new(args[0], comparison, 2.0);
+                Number::select(condition, args[0] * 3.0, args[0] * args[0])
+            },
+            &[x],
+        );
+        assert_eq!(evaluation.derivative(x), expected_derivative);
+    }
+}
+
+#[test]
+fn test_select_between_the_same_number() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let x = Number::new(1.5);
+    let evaluation = automatic_differentiator.derivatives(
+        |args: &[Number]| args[0].where_gt(0.0, args[0] * 2.0, args[0] * 2.0),
+        &[x],
+    );
+    assert_eq!(evaluation.derivative(x), 2.0);
+}
+
+#[test]
+fn test_replay_with_the_recorded_values() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let spot = Number::new(110.0);
+    let strike = Number::new(100.0);
+    let evaluation = automatic_differentiator.derivatives(call_payoff, &[spot, strike]);
+    let replay = automatic_differentiator.replay(&[110.0, 100.0]);
+
+    assert!(!replay.control_flow_changed());
+    assert_same_evaluation(&replay.evaluation, &evaluation);
+}
+
+#[test]
+fn test_replay_follows_the_other_branch() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let spot = Number::new(110.0);
+    let strike = Number::new(100.0);
+    automatic_differentiator.derivatives(call_payoff, &[spot, strike]);
+
+    let replay = automatic_differentiator.replay(&[95.0, 100.0]);
+    assert!(replay.control_flow_changed());
+    assert_eq!(replay.changed_branches.len(), 1);
+    assert_eq!(replay.evaluation.result, 0.0);
+    assert_eq!(replay.evaluation.derivative(spot), 0.0);
+    assert_eq!(replay.evaluation.derivative(strike), 0.0);
+
+    // compared with the previous replay, not with the recording
+    let replay = automatic_differentiator.replay(&[97.0, 100.0]);
+    assert!(!replay.control_flow_changed());
+
+    let replay = automatic_differentiator.replay(&[120.0, 105.0]);
+    assert!(replay.control_flow_changed());
+    assert_eq!(replay.evaluation.result, 15.0);
+    assert_eq!(replay.evaluation.derivative(spot), 1.0);
+    assert_eq!(replay.evaluation.derivative(strike), -1.0);
+}
+
+#[test]
+fn test_replay_matches_a_new_recording() {
+    fn f(args: &[Number]) -> Number {
+        let (x, y) = (args[0], args[1]);
+        let smooth = (x * y).sin() + (x / y).exp() - y.ln() * x.powi(3) + x.hypot(y).sqrt();
+        let special = x.normal_cdf(0.5, y) + (x * x + 1.0).lgamma() + x.smooth_max(y, 0.3);
+        let log_pdf = StudentsT::new(x, y, 4.0).log_pdf(Number::from(1.2));
+        let branch = Number::select(Condition::lt(x, y), smooth, special);
+        branch + log_pdf + x.max(y) + (x - 1.0).abs()
+    }
+
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+    let mut reference = AutomaticDifferentiator::new();
+
+    let x = Number::new(0.7);
+    let y = Number::new(1.9);
+    automatic_differentiator.derivatives(f, &[x, y]);
+
+    for (x_value, y_value) in [(0.9, 1.4), (1.3, 0.8), (2.5, 3.0), (0.7, 1.9)] {
+        let replay = automatic_differentiator.replay(&[x_value, y_value]);
+        let x = Number::new(x_value);
+        let y = Number::new(y_value);
+        let evaluation = reference.derivatives(f, &[x, y]);
+        assert_same_evaluation(&replay.evaluation, &evaluation);
+    }
+}
+
+#[test]
+fn test_untaped_branch_is_replayed_as_recorded() {
+    // a branch on `result` is invisible to the tape: the replay keeps x * x
+    fn f(args: &[Number]) -> Number {
+        if args[0].result > 0.0 {
+            args[0] * args[0]
+        } else {
+            args[0] * -1.0
+        }
+    }
+
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+    let x = Number::new(2.0);
+    automatic_differentiator.derivatives(f, &[x]);
+
+    let replay = automatic_differentiator.replay(&[-3.0]);
+    assert!(!replay.control_flow_changed());
+    assert_eq!(replay.evaluation.result, 9.0);
+}
+
+#[test]
+#[should_panic(expected = "one value per argument")]
+fn test_replay_with_the_wrong_number_of_values() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+    let x = Number::new(2.0);
+    automatic_differentiator.derivatives(|args: &[Number]| args[0] * 2.0, &[x]);
+    automatic_differentiator.replay(&[1.0, 2.0]);
+}
+
+#[test]
+fn test_select_without_tape() {
+    let y = aad::no_tape(|| Number::new(3.0).where_gt(1.0, 10.0, 20.0));
+    assert_eq!(y.id, 0);
+    assert_eq!(y.result, 10.0);
+}
@@ -0,0 +1,153 @@
+use aad::automatic_differentiator::AutomaticDifferentiator;
+use aad::number::Number;
+
+mod common;
+
+/// Compares the adjoints of `func` with central finite differences at every point.
+fn test_against_finite_differences<F>(func: F, points: &[Vec<f64>])
+where
+    F: Fn(&[Number]) -> Number,
+{
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    for point in points {
+        let arguments: Vec<Number> = point.iter().map(|x| Number::new(*x)).collect();
+        let evaluation = automatic_differentiator.derivatives(&func, &arguments);
+
+        for (i, argument) in arguments.iter().enumerate() {
+            let adjoint = evaluation.derivative(*argument);
+            let reference = common::finite_difference(&func, point, i);
+            assert!(
+                (adjoint - reference).abs() <= 1e-6 * reference.abs().max(1.0),
+                "at {:?}, argument {}: adjoint {} finite difference {}",
+                point,
+                i,
+                adjoint,
+                reference
+            );
+        }
+    }
+}
+
+fn points(xs: &[f64]) -> Vec<Vec<f64>> {
+    xs.iter().map(|x| vec![*x]).collect()
+}
+
+fn evaluate<F>(func: F, x: f64) -> f64
+where
+    F: Fn(Number) -> Number,
+{
+    aad::no_tape(|| func(Number::new(x)).result)
+}
+
+#[test]
+fn test_smooth_step_against_finite_differences() {
+    let xs = points(&[-3.0, -0.4, -0.01, 0.0, 0.02, 0.5, 2.0]);
+    for eps in [0.05, 0.5, 2.0] {
+        test_against_finite_differences(|args: &[Number]| args[0].smooth_step(eps), &xs);
+    }
+}
+
+#[test]
+fn test_smooth_max_against_finite_differences() {
+    let points = vec![
+        vec![1.0, 0.0],
+        vec![-0.3, 0.2],
+        vec![0.7, 0.7],
+        vec![5.0, -5.0],
+    ];
+    for eps in [0.1, 1.0] {
+        test_against_finite_differences(
+            |args: &[Number]| args[0].smooth_max(args[1], eps),
+            &points,
+        );
+    }
+}
+
+#[test]
+fn test_smooth_abs_against_finite_differences() {
+    let xs = points(&[-2.0, -0.1, 0.0, 0.03, 1.5]);
+    for eps in [0.01, 0.3] {
+        test_against_finite_differences(|args: &[Number]| args[0].smooth_abs(eps), &xs);
+    }
+}
+
+#[test]
+fn test_smooth_indicator_between_against_finite_differences() {
+    let xs = points(&[-1.0, 0.0, 0.45, 1.0, 1.6, 2.0, 4.0]);
+    for eps in [0.05, 0.4] {
+        test_against_finite_differences(
+            |args: &[Number]| args[0].smooth_indicator_between(0.0, 2.0, eps),
+            &xs,
+        );
+    }
+}
+
+#[test]
+fn test_smooth_functions_converge_to_non_smooth_ones() {
+    let eps = 1e-4;
+    for x in [-1.5, -0.2, 0.3, 2.5] {
+        let step = if x > 0.0 { 1.0 } else { 0.0 };
+        let inside = if 0.0 < x && x < 2.0 { 1.0 } else { 0.0 };
+        assert!((evaluate(|x| x.smooth_step(eps), x) - step).abs() < 1e-12);
+        assert!((evaluate(|x| x.smooth_max(0.5, eps), x) - x.max(0.5)).abs() < 1e-12);
+        assert!((evaluate(|x| x.smooth_abs(eps), x) - x.abs()).abs() < 1e-7);
+        assert!(
+            (evaluate(|x| x.smooth_indicator_between(0.0, 2.0, eps), x) - inside).abs() < 1e-12
+        );
+    }
+}
+
+#[test]
+fn test_smooth_functions_far_from_the_kink() {
+    // no overflow of e^(x/eps) for large |x/eps|
+    assert_eq!(evaluate(|x| x.smooth_step(1e-3), 5.0), 1.0);
+    assert_eq!(evaluate(|x| x.smooth_step(1e-3), -5.0), 0.0);
+    assert_eq!(evaluate(|x| x.smooth_max(0.0, 1e-3), 5.0), 5.0);
+    assert_eq!(evaluate(|x| x.smooth_max(0.0, 1e-3), -5.0), 0.0);
+}
+
+#[test]
+fn test_digital_delta_at_the_strike() {
+    // smoothed digital 1{S > K}: delta at the money is 1 / (4 eps)
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let eps = 0.5;
+    let spot = Number::new(100.0);
+    let evaluation = automatic_differentiator.derivatives(
+        |args: &[Number]| (args[0] - 100.0).smooth_step(eps),
+        &[spot],
+    );
+
+    assert!((evaluation.result - 0.5).abs() < 1e-12);
+    assert!((evaluation.derivative(spot) - 0.5).abs() < 1e-12);
+}
+
+#[test]
+fn test_smooth_max_with_itself() {
+    // smooth_max(x, x) = x + eps ln 2, each side gets half the derivative
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let eps = 0.2;
+    let x = Number::new(1.3);
+    let evaluation = automatic_differentiator
+        .derivatives(|args: &[Number]| args[0].smooth_max(args[0], eps), &[x]);
+
+    assert!((evaluation.result - (1.3 + eps * 2.0_f64.ln())).abs() < 1e-12);
+    assert!((evaluation.derivative(x) - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_smooth_functions_record_a_single_node() {
+    // f(x) = smooth_step(2x): the adjoint of x is 2 s (1 - s) / eps
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let eps = 0.25;
+    let x = Number::new(0.1);
+    let evaluation = automatic_differentiator
+        .derivatives(|args: &[Number]| (args[0] * 2.0).smooth_step(eps), &[x]);
+
+    let s = 1.0 / (1.0 + (-0.2_f64 / eps).exp());
+    assert!((evaluation.result - s).abs() < 1e-12);
+    assert!((evaluation.derivative(x) - 2.0 * s * (1.0 - s) / eps).abs() < 1e-12);
+}
@@ -0,0 +1,237 @@
+use aad::automatic_differentiator::AutomaticDifferentiator;
+use aad::number::Number;
+
+mod common;
+
+/// Compares the adjoints of `func` with central finite differences at every point.
+fn test_against_finite_differences<F>(func: F, points: &[Vec<f64>])
+where
+    F: Fn(&[Number]) -> Number,
+{
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    for point in points {
+        let arguments: Vec<Number> = point.iter().map(|x| Number::new(*x)).collect();
+        let evaluation = automatic_differentiator.derivatives(&func, &arguments);
+
+        for (i, argument) in arguments.iter().enumerate() {
+            let adjoint = evaluation.derivative(*argument);
+            let reference = common::finite_difference(&func, point, i);
+            assert!(
+                (adjoint - reference).abs() <= 1e-6 * reference.abs().max(1.0),
+                "at {:?}, argument {}: adjoint {} finite difference {}",
+                point,
+                i,
+                adjoint,
+                reference
+            );
+        }
+    }
+}
+
+fn range(from: f64, to: f64, steps: usize) -> Vec<f64> {
+    (0..=steps)
+        .map(|i| from + (to - from) * i as f64 / steps as f64)
+        .collect()
+}
+
+fn single_argument(xs: Vec<f64>) -> Vec<Vec<f64>> {
+    xs.into_iter().map(|x| vec![x]).collect()
+}
+
+#[test]
+fn test_special_function_values() {
+    let values = aad::no_tape(|| {
+        [
+            (Number::new(5.0).gamma().result, 24.0),
+            (Number::new(0.5).gamma().result, std::f64::consts::PI.sqrt()),
+            (Number::new(10.0).lgamma().result, 362880.0_f64.ln()),
+            (Number::new(1.0).digamma().result, -0.5772156649015329),
+            (Number::new(2.0).beta(3.0).result, 1.0 / 12.0),
+            (
+                Number::new(1.5).regularized_gamma_p(1.0).result,
+                1.0 - (-1.5_f64).exp(),
+            ),
+            (
+                Number::new(1.5).regularized_gamma_q(1.0).result,
+                (-1.5_f64).exp(),
+            ),
+            (Number::new(0.3).regularized_beta(1.0, 1.0).result, 0.3),
+            (Number::new(0.0).students_t_cdf(4.0).result, 0.5),
+            (Number::new(1.0).students_t_cdf(1.0).result, 0.75),
+            (
+                Number::new(3.0).chi_squared_cdf(2.0).result,
+                1.0 - (-1.5_f64).exp(),
+            ),
+        ]
+    });
+
+    for (value, expected) in values {
+        assert!(
+            (value - expected).abs() < 1e-10,
+            "{} != {}",
+            value,
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_gamma_against_finite_differences() {
+    let mut xs = range(0.1, 20.0, 60);
+    xs.extend([-2.5, -1.3, -0.5]);
+    test_against_finite_differences(|args: &[Number]| args[0].gamma(), &single_argument(xs));
+}
+
+#[test]
+fn test_lgamma_against_finite_differences() {
+    let mut xs = range(0.05, 150.0, 120);
+    xs.extend([1.0, 2.0]);
+    test_against_finite_differences(|args: &[Number]| args[0].lgamma(), &single_argument(xs));
+}
+
+#[test]
+fn test_digamma_against_finite_differences() {
+    let mut xs = range(0.05, 100.0, 120);
+    xs.extend([-3.5, -2.2, -0.7]);
+    test_against_finite_differences(|args: &[Number]| args[0].digamma(), &single_argument(xs));
+}
+
+#[test]
+fn test_beta_against_finite_differences() {
+    let grid = [0.3, 1.0, 2.5, 7.0, 30.0];
+    let points: Vec<Vec<f64>> = grid
+        .iter()
+        .flat_map(|a| grid.iter().map(move |b| vec![*a, *b]))
+        .collect();
+    test_against_finite_differences(|args: &[Number]| args[0].beta(args[1]), &points);
+}
+
+#[test]
+fn test_lgamma_of_product() {
+    // lgamma(x * y) with both arguments active
+    let points = vec![vec![0.7, 3.0], vec![4.0, 2.5], vec![12.0, 0.2]];
+    test_against_finite_differences(|args: &[Number]| (args[0] * args[1]).lgamma(), &points);
+}
+
+#[test]
+fn test_regularized_gamma_against_finite_differences() {
+    for a in [0.5, 1.0, 3.0, 10.0, 50.0] {
+        let xs = single_argument(range(0.05 * a, 3.0 * a, 40));
+        test_against_finite_differences(|args: &[Number]| args[0].regularized_gamma_p(a), &xs);
+        test_against_finite_differences(|args: &[Number]| args[0].regularized_gamma_q(a), &xs);
+    }
+}
+
+#[test]
+fn test_regularized_gamma_q_in_the_upper_tail() {
+    // Q(a, x) = e^(-x) sum_{k < a} x^k / k! for integer a, far below the
+    // resolution of 1 - P(a, x)
+    let q = |a: usize, x: f64| -> f64 {
+        (0..a)
+            .map(|k| x.powi(k as i32) / (1..=k).product::<usize>() as f64)
+            .sum::<f64>()
+            * (-x).exp()
+    };
+    let relative = |value: f64, expected: f64| ((value - expected) / expected).abs();
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    for (a, x) in [(1, 40.0), (2, 60.0), (5, 80.0)] {
+        let shape = a as f64;
+        let argument = Number::new(x);
+        let evaluation = automatic_differentiator.derivatives(
+            |args: &[Number]| args[0].regularized_gamma_q(shape),
+            &[argument],
+        );
+        assert!(
+            relative(evaluation.result, q(a, x)) < 1e-10,
+            "Q({}, {})",
+            a,
+            x
+        );
+        // dQ/dx = -x^(a-1) e^(-x) / Gamma(a)
+        let density = q(a, x) - q(a - 1, x);
+        assert!(relative(evaluation.derivative(argument), -density) < 1e-10);
+
+        let replay = automatic_differentiator.replay(&[x + 5.0]);
+        assert!(relative(replay.evaluation.result, q(a, x + 5.0)) < 1e-10);
+    }
+}
+
+#[test]
+fn test_regularized_beta_against_finite_differences() {
+    for a in [0.5, 2.0, 5.0] {
+        for b in [0.5, 2.0, 5.0] {
+            let xs = single_argument(range(0.05, 0.95, 30));
+            test_against_finite_differences(|args: &[Number]| args[0].regularized_beta(a, b), &xs);
+        }
+    }
+}
+
+#[test]
+fn test_students_t_cdf_against_finite_differences() {
+    for nu in [1.0, 2.5, 5.0, 30.0] {
+        let xs = single_argument(range(-10.0, 10.0, 41));
+        test_against_finite_differences(|args: &[Number]| args[0].students_t_cdf(nu), &xs);
+    }
+}
+
+#[test]
+fn test_chi_squared_cdf_against_finite_differences() {
+    for k in [1.0, 2.0, 5.0, 20.0] {
+        let xs = single_argument(range(0.1, 50.0, 50));
+        test_against_finite_differences(|args: &[Number]| args[0].chi_squared_cdf(k), &xs);
+    }
+}
+
+#[test]
+fn test_shape_parameters_against_finite_differences() {
+    let grid = |xs: &[f64], shapes: &[f64]| -> Vec<Vec<f64>> {
+        xs.iter()
+            .flat_map(|x| shapes.iter().map(move |a| vec![*x, *a]))
+            .collect()
+    };
+    let gamma_points = grid(&[0.3, 1.0, 4.0, 12.0], &[0.5, 1.0, 3.0, 10.0]);
+    test_against_finite_differences(
+        |args: &[Number]| args[0].regularized_gamma_p(args[1]),
+        &gamma_points,
+    );
+    test_against_finite_differences(
+        |args: &[Number]| args[0].regularized_gamma_q(args[1]),
+        &gamma_points,
+    );
+    test_against_finite_differences(
+        |args: &[Number]| args[0].chi_squared_cdf(args[1]),
+        &grid(&[0.5, 2.0, 7.0, 20.0], &[1.0, 3.0, 10.0]),
+    );
+    test_against_finite_differences(
+        |args: &[Number]| args[0].students_t_cdf(args[1]),
+        &grid(&[-4.0, -0.5, 0.7, 3.0], &[1.0, 2.5, 10.0]),
+    );
+
+    let beta_points: Vec<Vec<f64>> = grid(&[0.1, 0.4, 0.8], &[0.5, 2.0, 5.0])
+        .into_iter()
+        .flat_map(|point| [0.7, 3.0].map(|b| vec![point[0], point[1], b]))
+        .collect();
+    test_against_finite_differences(
+        |args: &[Number]| args[0].regularized_beta(args[1], args[2]),
+        &beta_points,
+    );
+}
+
+#[test]
+fn test_cdfs_outside_support() {
+    let mut automatic_differentiator = AutomaticDifferentiator::new();
+
+    let x = Number::new(-1.0);
+    let evaluation =
+        automatic_differentiator.derivatives(|args: &[Number]| args[0].chi_squared_cdf(3.0), &[x]);
+    assert_eq!(evaluation.result, 0.0);
+    assert_eq!(evaluation.derivative(x), 0.0);
+
+    let x = Number::new(1.5);
+    let evaluation = automatic_differentiator
+        .derivatives(|args: &[Number]| args[0].regularized_beta(2.0, 3.0), &[x]);
+    assert_eq!(evaluation.result, 1.0);
+    assert_eq!(evaluation.derivative(x), 0.0);
+}